@@ -54,7 +54,7 @@ fn check_errors(
 	// let result = panic::catch_unwind(|| {
 	let result = checker::check_project::<_, EznoParser>(
 		vec![PathBuf::from("main.tsx")],
-		std::iter::once(checker::INTERNAL_DEFINITION_FILE_PATH.into()).collect(),
+		vec![checker::INTERNAL_DEFINITION_FILE_PATH.into()],
 		|path: &Path| -> Option<Vec<u8>> {
 			if code.len() == 1 {
 				Some(code[0].1.to_owned().into())
@@ -68,7 +68,7 @@ fn check_errors(
 			}
 		},
 		type_check_options,
-		(),
+		Default::default(),
 		None,
 	);
 	// });
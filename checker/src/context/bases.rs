@@ -3,13 +3,13 @@ use super::ContextId;
 use crate::TypeId;
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, binary_serialize_derive::BinarySerializable)]
 pub struct Boundary(pub(crate) ContextId);
 
 /// Contains the constraint / bases of dynamic poly types
 ///
 /// TODO generic as only environments should have mutable bases
-#[derive(Default, Debug)]
+#[derive(Default, Debug, binary_serialize_derive::BinarySerializable)]
 pub struct Bases {
 	pub(crate) immutable_bases: HashMap<TypeId, TypeId>,
 	pub(crate) mutable_bases: HashMap<TypeId, (Boundary, TypeId)>,
@@ -199,9 +199,16 @@ pub enum Scope {
 	TryBlock {},
 	// Just blocks and modules
 	Block {},
+	/// Like [`Scope::Block`], but exported items become members of the namespace object
+	Namespace {},
 	Module {
 		source: SourceId,
 		exported: Exported,
+		/// From `export * from ...`. Kept separate as explicit exports take precedence over these,
+		/// merged into `exported` after the module has been synthesised
+		star_exported: Exported,
+		/// Names exported by more than one `export *`, which are left out of `star_exported`
+		ambiguous_star_exports: Vec<String>,
 	},
 	DefinitionModule {
 		source: SourceId,
@@ -637,6 +644,28 @@ impl<'a> Environment<'a> {
 		new_type: TypeId,
 		checking_data: &mut CheckingData<T, A>,
 	) -> TypeId {
+		if let Some((_, _, variable)) = self.get_variable_unbound(variable_name) {
+			checking_data.usage.assigned_variables.insert((self.get_source(), variable.get_id()));
+			checking_data.usage.references.insert(assignment_position, variable.get_id());
+
+			// Only conditional branches merge their values into the scope the variable is declared in
+			let in_inner_scope = self
+				.parents_iter()
+				.take_while(|ctx| !get_on_ctx!(ctx.variables.contains_key(variable_name)))
+				.any(|ctx| {
+					!matches!(
+						ctx,
+						GeneralContext::Syntax(syntax) if matches!(
+							syntax.context_type.scope,
+							Scope::Conditional { .. } | Scope::PassThrough { .. }
+						)
+					)
+				});
+			if in_inner_scope {
+				checking_data.usage.assigned_in_inner_scopes.insert(variable.get_id());
+			}
+		}
+
 		let result = self.assign_to_variable(
 			variable_name,
 			assignment_position,
@@ -912,6 +941,9 @@ impl<'a> Environment<'a> {
 			}
 		};
 
+		checking_data.usage.record_read(self.get_source(), &og_var);
+		checking_data.usage.references.insert(position, og_var.get_id());
+
 		let reference = RootReference::Variable(og_var.get_id());
 
 		if let VariableOrImport::Variable { context: Some(ref context), .. } = og_var {
@@ -970,6 +1002,9 @@ impl<'a> Environment<'a> {
 								return Ok(VariableWithValue(og_var.clone(), current_value));
 							} else if let Type::Constant(_) = ty {
 								return Ok(VariableWithValue(og_var.clone(), current_value));
+							} else if current_value == TypeId::ERROR_TYPE {
+								// Such as an import of an invalid module, which has already been reported
+								return Ok(VariableWithValue(og_var.clone(), current_value));
 							}
 
 							crate::utilities::notify!("Free variable!");
@@ -1099,11 +1134,28 @@ impl<'a> Environment<'a> {
 		T: crate::ReadFromFS,
 	{
 		if let Decidable::Known(result) = is_type_truthy_falsy(condition, &checking_data.types) {
+			// Values assigned in inner scopes are not tracked after them, so the condition could
+			// have a different value
+			let source = self.get_source();
+			let usage = &checking_data.usage;
+			let reads_variable_assigned_in_inner_scope =
+				usage.references.iter().any(|(position, variable)| {
+					position.source == source
+						&& pos.start <= position.start
+						&& position.end <= pos.end
+						&& usage.assigned_in_inner_scopes.contains(variable)
+				});
+
 			// TODO could be better
-			checking_data.diagnostics_container.add_warning(TypeCheckWarning::DeadBranch {
-				expression_span: pos.with_source(self.get_source()),
-				expression_value: result,
-			});
+			if !reads_variable_assigned_in_inner_scope {
+				checking_data.diagnostics_container.add_warning(TypeCheckWarning::DeadBranch {
+					expression_span: pos.with_source(source),
+					expression_value: result,
+				});
+				if checking_data.options.store_expression_type_mappings {
+					checking_data.local_type_mappings.dead_branches.push(pos, result);
+				}
+			}
 
 			return if result {
 				then_evaluate(self, checking_data)
@@ -1374,7 +1426,8 @@ impl<'a> Environment<'a> {
 					| Scope::Conditional { .. }
 					| Scope::TryBlock {}
 					| Scope::DefaultFunctionParameter {}
-					| Scope::Block {} => {}
+					| Scope::Block {}
+					| Scope::Namespace {} => {}
 				}
 			}
 		}
@@ -18,7 +18,7 @@ pub enum Publicity {
 }
 
 /// Things that are currently true or have happened
-#[derive(Debug, Default, binary_serialize_derive::BinarySerializable)]
+#[derive(Debug, Default, Clone, binary_serialize_derive::BinarySerializable)]
 pub struct LocalInformation {
 	pub(crate) events: Vec<Event>,
 	/// TODO think about tasks. These are things that may happen at next stop point
@@ -135,7 +135,10 @@ impl LocalInformation {
 		self.events.extend(other.events);
 		self.queued_events.extend(other.queued_events);
 		self.variable_current_value.extend(other.variable_current_value);
-		self.current_properties.extend(other.current_properties);
+		// Append rather than replace, so properties from interface merging are kept
+		for (on, properties) in other.current_properties {
+			self.current_properties.entry(on).or_default().extend(properties);
+		}
 		self.prototypes.extend(other.prototypes);
 		self.closure_current_values.extend(other.closure_current_values);
 		self.configurable.extend(other.configurable);
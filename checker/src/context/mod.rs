@@ -11,6 +11,7 @@ pub mod invocation;
 
 pub(crate) use invocation::CallCheckingBehavior;
 pub use root::RootContext;
+pub(crate) use root::CONTEXT_FILE_HEADER;
 
 pub(crate) use bases::Boundary;
 
@@ -56,7 +57,15 @@ pub type Environment<'a> = Context<environment::Syntax<'a>>;
 
 static ENVIRONMENT_ID_COUNTER: AtomicU16 = AtomicU16::new(1);
 
-#[derive(PartialEq, Eq, Clone, Copy, derive_debug_extras::DebugExtras, Hash)]
+#[derive(
+	PartialEq,
+	Eq,
+	Clone,
+	Copy,
+	derive_debug_extras::DebugExtras,
+	Hash,
+	binary_serialize_derive::BinarySerializable,
+)]
 pub struct ContextId(u16);
 
 impl ContextId {
@@ -286,6 +295,7 @@ impl<T: ContextType> Context<T> {
 					Scope::Iteration { .. } => "iteration",
 					Scope::TryBlock { .. } => "try",
 					Scope::Block {} => "block",
+					Scope::Namespace {} => "namespace",
 					Scope::Module { .. } => "module",
 					Scope::TypeAlias => "type alias",
 					Scope::DefinitionModule { .. } => "definition module",
@@ -344,6 +354,7 @@ impl<T: ContextType> Context<T> {
 				| Scope::TryBlock { .. }
 				| Scope::TypeAlias
 				| Scope::Block {}
+				| Scope::Namespace {}
 				| Scope::PassThrough { .. }
 				| Scope::DefaultFunctionParameter { .. }
 				| Scope::DefinitionModule { .. }
@@ -356,7 +367,7 @@ impl<T: ContextType> Context<T> {
 	/// Similar to [`Context::get_this_unbound`]
 	///
 	/// First `bool` is whether this variable is on [`Context<Root>`]
-	fn get_variable_unbound(
+	pub(crate) fn get_variable_unbound(
 		&self,
 		variable_name: &str,
 	) -> Option<(bool, Option<Boundary>, &VariableOrImport)> {
@@ -601,6 +612,7 @@ impl<T: ContextType> Context<T> {
 			Scope::InterfaceEnvironment { .. }
 			| Scope::TypeAlias
 			| Scope::Block {}
+			| Scope::Namespace {}
 			| Scope::TryBlock {}
 			| Scope::PassThrough { .. }
 			| Scope::Module { .. }
@@ -639,6 +651,14 @@ impl<T: ContextType> Context<T> {
 				// TODO don't need to clone all the time
 				self.info.prototypes.extend(info.prototypes.clone());
 
+				// The body of a namespace is run once, so functions it exports can read the
+				// values of its variables
+				if matches!(scope, Scope::Namespace {}) {
+					self.info
+						.variable_current_value
+						.extend(mem::take(&mut info.variable_current_value));
+				}
+
 				// TODO also lift vars, regardless of scope
 				if matches!(scope, Scope::PassThrough { .. }) {
 					self.variables.extend(variables);
@@ -5,10 +5,11 @@ use crate::{
 		modules::{Exported, SynthesisedModule},
 		variables::VariableOrImport,
 	},
-	types::TypeId,
+	serialization::{checksum, with_source_table, BinarySerializable, LongString, SourceTable},
+	types::{TypeId, TypeStore},
 	CheckingData, Environment, GeneralContext,
 };
-use source_map::SourceId;
+use source_map::{FileSystem, MapFileStore, Nullable, SourceId, WithPathMap};
 use std::{collections::HashMap, iter::FromIterator, mem};
 
 pub type RootContext = Context<Root>;
@@ -38,7 +39,12 @@ impl ContextType for Root {
 	}
 }
 
-const _CONTEXT_FILE_HEADER: &[u8] = b"EZNO\0CONTEXT\0FILE";
+pub(crate) const CONTEXT_FILE_HEADER: &[u8] = b"EZNO\0CONTEXT\0FILE";
+/// Incremented when the layout of [`RootContext::serialize`] changes
+const CONTEXT_FILE_VERSION: u32 = 2;
+
+const U32_BYTES: usize = (u32::BITS / u8::BITS) as usize;
+const U64_BYTES: usize = (u64::BITS / u8::BITS) as usize;
 
 impl RootContext {
 	/// Merges two [`RootEnvironments`]. May be used for multiple `.d.ts` files
@@ -110,14 +116,23 @@ impl RootContext {
 		module: A::Module<'static>,
 		checking_data: &'a mut CheckingData<T, A>,
 	) -> &'a SynthesisedModule<A::OwnedModule> {
-		let module_scope = crate::Scope::Module { source, exported: Exported::default() };
+		let module_scope = crate::Scope::Module {
+			source,
+			exported: Exported::default(),
+			star_exported: Exported::default(),
+			ambiguous_star_exports: Vec::new(),
+		};
 		let mut environment = self.new_lexical_environment(module_scope);
 		A::synthesise_module(&module, source, &mut environment, checking_data);
 
-		let crate::Scope::Module { exported, .. } = environment.context_type.scope else {
+		let crate::Scope::Module { mut exported, star_exported, .. } =
+			environment.context_type.scope
+		else {
 			unreachable!()
 		};
 
+		exported.merge_star_exported(star_exported);
+
 		let module = SynthesisedModule {
 			content: A::owned_module_from_module(module),
 			exported,
@@ -126,53 +141,138 @@ impl RootContext {
 			mappings: mem::take(&mut checking_data.local_type_mappings),
 		};
 
+		checking_data.usage.synthesised_modules.push(source);
+
 		// TODO better way to do this?
 		checking_data.modules.synthesised_modules.insert(source, module);
 		checking_data.modules.synthesised_modules.get(&source).unwrap()
 	}
 
-	/// TODO working things out:
-	/// - strings could reference a big string
+	/// Snapshot of the root and the types it references, to be restored with [`Self::deserialize`]. Includes
+	/// the content of sources that positions point to (the definition files).
+	///
+	/// TODO strings could reference a big string
 	#[must_use]
-	pub fn serialize(self) -> Vec<u8> {
-		todo!()
-	}
+	pub fn serialize(self, types: TypeStore, files: &MapFileStore<WithPathMap>) -> Vec<u8> {
+		let Self {
+			context_type: Root,
+			context_id: _,
+			variables,
+			named_types,
+			variable_names,
+			deferred_function_constraints,
+			bases,
+			can_reference_this,
+			possibly_mutated_objects,
+			info,
+		} = self;
 
-	pub fn deserialize(_source: &[u8], _backing_source: SourceId) -> Result<Self, String> {
-		todo!()
-		// let mut ctx = Root::new_with_primitive_references();
+		let (body, table) = with_source_table(Default::default(), || {
+			let mut body = Vec::new();
+			variables.serialize(&mut body);
+			named_types.serialize(&mut body);
+			variable_names.serialize(&mut body);
+			deferred_function_constraints.serialize(&mut body);
+			bases.serialize(&mut body);
+			can_reference_this.serialize(&mut body);
+			possibly_mutated_objects.serialize(&mut body);
+			info.serialize(&mut body);
+			types.serialize(&mut body);
+			body
+		});
 
-		// if !source.starts_with(HEADER) {
-		// 	return Err("Missing header".to_owned());
-		// }
+		// Sources are written first, so they can be registered before the body is read
+		let mut rest = Vec::new();
+		u32::try_from(table.sources.len()).unwrap().serialize(&mut rest);
+		for source in table.sources {
+			LongString::from(files.get_file_path(source).as_path()).serialize(&mut rest);
+			LongString(files.get_file_content(source)).serialize(&mut rest);
+		}
+		rest.extend(body);
 
-		// let mut bytes = source.into_iter();
+		let mut buf = CONTEXT_FILE_HEADER.to_vec();
+		CONTEXT_FILE_VERSION.serialize(&mut buf);
+		env!("CARGO_PKG_VERSION").to_owned().serialize(&mut buf);
+		u32::try_from(rest.len()).unwrap().serialize(&mut buf);
+		checksum(&rest).serialize(&mut buf);
+		buf.extend(rest);
+		buf
+	}
+
+	/// Restores a root (and its types) from [`Self::serialize`]. The sources it references are added to `files`.
+	///
+	/// Fails if `bytes` are not a context file, were created by a different version or are
+	/// truncated or corrupted
+	pub fn deserialize(
+		bytes: &[u8],
+		files: &mut MapFileStore<WithPathMap>,
+	) -> Result<(Self, TypeStore), String> {
+		let Some(content) = bytes.strip_prefix(CONTEXT_FILE_HEADER) else {
+			return Err("not a context file (missing header)".to_owned());
+		};
+		let mut bytes = content.iter().copied();
+		let source = SourceId::NULL;
 
-		// {
-		// 	assert_eq!(bytes.by_ref().take(HEADER.len()).collect::<Vec<_>>(), HEADER);
-		// }
+		if bytes.len() < U32_BYTES {
+			return Err("context file is truncated".to_owned());
+		}
+		let format_version = u32::deserialize(&mut bytes, source);
+		if format_version != CONTEXT_FILE_VERSION {
+			return Err(format!(
+				"context file has format version {format_version}, expected {CONTEXT_FILE_VERSION}"
+			));
+		}
 
-		// // Types
-		// let count = u16::from_le_bytes([bytes.next().unwrap(), bytes.next().unwrap()]);
+		let created_by = match bytes.next() {
+			Some(length) if bytes.len() >= length as usize => {
+				String::from_utf8(bytes.by_ref().take(length as usize).collect())
+					.unwrap_or_default()
+			}
+			_ => return Err("context file is truncated".to_owned()),
+		};
+		let current = env!("CARGO_PKG_VERSION");
+		if created_by != current {
+			return Err(format!(
+				"context file was created by ezno-checker {created_by}, but this is {current}"
+			));
+		}
 
-		// for _ in 0..count {
-		// 	let ty = Type::deserialize(&mut bytes, backing_source);
-		// 	ctx.new_type(ty);
-		// }
-		// crate::utilities::notify!("Registered {:?} types", count);
+		if bytes.len() < U32_BYTES + U64_BYTES
+			|| u32::deserialize(&mut bytes, source) as usize != bytes.len() - U64_BYTES
+		{
+			return Err("context file is truncated".to_owned());
+		}
+		let expected_checksum = u64::deserialize(&mut bytes, source);
+		if checksum(&content[content.len() - bytes.len()..]) != expected_checksum {
+			return Err("context file is corrupted".to_owned());
+		}
 
-		// ctx.variables = BinarySerializable::deserialize(&mut bytes, backing_source);
-		// // TODO terrible
-		// VariableId::set_counter_bad((ctx.variables.len() + 1) as u16);
-		// ctx.variable_names = BinarySerializable::deserialize(&mut bytes, backing_source);
+		let count = u32::deserialize(&mut bytes, source);
+		let sources = (0..count)
+			.map(|_| {
+				let path = LongString::deserialize(&mut bytes, source).0;
+				let content = LongString::deserialize(&mut bytes, source).0;
+				files.new_source_id(path.into(), content)
+			})
+			.collect();
 
-		// ctx.proofs = BinarySerializable::deserialize(&mut bytes, backing_source);
-		// ctx.functions_on_type = BinarySerializable::deserialize(&mut bytes, backing_source);
-		// ctx.subtyping_constant_proofs = BinarySerializable::deserialize(&mut bytes, backing_source);
-		// ctx.terms_reverse = BinarySerializable::deserialize(&mut bytes, backing_source);
-		// ctx.proxies = BinarySerializable::deserialize(&mut bytes, backing_source);
-		// ctx.can_reference_this = BinarySerializable::deserialize(&mut bytes, backing_source);
+		let (result, _) = with_source_table(SourceTable::from_sources(sources), || {
+			let root = Self {
+				context_type: Root,
+				context_id: ContextId::ROOT,
+				variables: BinarySerializable::deserialize(&mut bytes, source),
+				named_types: BinarySerializable::deserialize(&mut bytes, source),
+				variable_names: BinarySerializable::deserialize(&mut bytes, source),
+				deferred_function_constraints: BinarySerializable::deserialize(&mut bytes, source),
+				bases: BinarySerializable::deserialize(&mut bytes, source),
+				can_reference_this: BinarySerializable::deserialize(&mut bytes, source),
+				possibly_mutated_objects: BinarySerializable::deserialize(&mut bytes, source),
+				info: BinarySerializable::deserialize(&mut bytes, source),
+			};
+			let types = TypeStore::deserialize(&mut bytes, source);
+			(root, types)
+		});
 
-		// Ok(ctx)
+		Ok(result)
 	}
 }
@@ -14,7 +14,7 @@ use std::{
 	path::PathBuf,
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, binary_serialize_derive::BinarySerializable)]
 #[cfg_attr(feature = "serde-serialize", derive(serde::Serialize), serde(rename_all = "lowercase"))]
 #[cfg_attr(target_family = "wasm", derive(tsify::Tsify))]
 pub enum DiagnosticKind {
@@ -32,20 +32,75 @@ pub enum Diagnostic {
 	Global {
 		reason: String,
 		kind: DiagnosticKind,
+		#[cfg_attr(feature = "serde-serialize", serde(skip_serializing_if = "Option::is_none"))]
+		#[cfg_attr(target_family = "wasm", tsify(type = "string | undefined"))]
+		code: Option<DiagnosticCode>,
 	},
 	Position {
 		reason: String,
 		position: SpanWithSource,
 		kind: DiagnosticKind,
+		#[cfg_attr(feature = "serde-serialize", serde(skip_serializing_if = "Option::is_none"))]
+		#[cfg_attr(target_family = "wasm", tsify(type = "string | undefined"))]
+		code: Option<DiagnosticCode>,
 	},
 	PositionWithAdditionalLabels {
 		reason: String,
 		position: SpanWithSource,
 		labels: Vec<(String, Option<SpanWithSource>)>,
 		kind: DiagnosticKind,
+		#[cfg_attr(feature = "serde-serialize", serde(skip_serializing_if = "Option::is_none"))]
+		#[cfg_attr(target_family = "wasm", tsify(type = "string | undefined"))]
+		code: Option<DiagnosticCode>,
 	},
 }
 
+/// Stable identifier of a kind of diagnostic (such as `E0003`), for referencing in documentation and
+/// for suppressing. Errors start with `E` and warnings with `W`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, binary_serialize_derive::BinarySerializable)]
+pub struct DiagnosticCode {
+	prefix: u8,
+	number: u16,
+}
+
+impl DiagnosticCode {
+	const fn error(number: u16) -> Self {
+		Self { prefix: b'E', number }
+	}
+
+	const fn warning(number: u16) -> Self {
+		Self { prefix: b'W', number }
+	}
+}
+
+impl Display for DiagnosticCode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}{:04}", char::from(self.prefix), self.number)
+	}
+}
+
+impl std::str::FromStr for DiagnosticCode {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid =
+			|| format!("invalid diagnostic code '{s}', expected a form like E0001 or W0001");
+		let (prefix, number) = match s.as_bytes().first() {
+			Some(prefix @ (b'E' | b'W')) if s.len() == 5 => (*prefix, &s[1..]),
+			_ => return Err(invalid()),
+		};
+		let number = number.parse().map_err(|_| invalid())?;
+		Ok(Self { prefix, number })
+	}
+}
+
+#[cfg(feature = "serde-serialize")]
+impl serde::Serialize for DiagnosticCode {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
 /// Temporary dead zone. Between the variable identifier being hoisted and the value being assigned
 pub struct TDZ {
 	pub variable_name: String,
@@ -89,6 +144,23 @@ impl Diagnostic {
 		}
 	}
 
+	#[must_use]
+	pub fn code(&self) -> Option<DiagnosticCode> {
+		match self {
+			Diagnostic::Global { code, .. }
+			| Diagnostic::Position { code, .. }
+			| Diagnostic::PositionWithAdditionalLabels { code, .. } => *code,
+		}
+	}
+
+	fn position(&self) -> Option<SpanWithSource> {
+		match self {
+			Diagnostic::Global { .. } => None,
+			Diagnostic::Position { position, .. }
+			| Diagnostic::PositionWithAdditionalLabels { position, .. } => Some(*position),
+		}
+	}
+
 	#[must_use]
 	pub fn kind(&self) -> DiagnosticKind {
 		match self {
@@ -130,11 +202,30 @@ impl DiagnosticsContainer {
 		self.diagnostics.push(info.into());
 	}
 
+	/// For restoring diagnostics
+	pub(crate) fn add(&mut self, diagnostic: Diagnostic) {
+		self.has_error |= matches!(diagnostic.kind(), DiagnosticKind::Error);
+		self.diagnostics.push(diagnostic);
+	}
+
 	#[must_use]
 	pub fn has_error(&self) -> bool {
 		self.has_error
 	}
 
+	/// For suppressing diagnostics
+	pub fn retain(&mut self, f: impl FnMut(&Diagnostic) -> bool) {
+		self.diagnostics.retain(f);
+		self.has_error = self
+			.diagnostics
+			.iter()
+			.any(|diagnostic| matches!(diagnostic.kind(), DiagnosticKind::Error));
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+		self.diagnostics.iter()
+	}
+
 	pub fn sources(&self) -> impl Iterator<Item = SourceId> + '_ {
 		self.diagnostics.iter().flat_map(diagnostics::Diagnostic::sources)
 	}
@@ -154,6 +245,104 @@ impl DiagnosticsContainer {
 	}
 }
 
+/// `// @ezno-ignore` and `// @ezno-expect-error`, optionally followed by codes. Suppress diagnostics
+/// which start on the following line. `@ezno-expect-error` only suppresses errors and is itself an
+/// error if there is nothing to suppress
+#[derive(Debug, Clone, binary_serialize_derive::BinarySerializable)]
+pub(crate) struct SuppressionComment {
+	pub expect_error: bool,
+	/// Empty if all codes are suppressed
+	pub codes: Vec<DiagnosticCode>,
+	pub position: SpanWithSource,
+}
+
+impl SuppressionComment {
+	/// `None` if `comment` (without the leading `//`) is not a suppression comment
+	pub(crate) fn from_comment(
+		comment: &str,
+		position: SpanWithSource,
+	) -> Option<Result<Self, TypeCheckError<'static>>> {
+		let comment = comment.trim_start();
+		let (expect_error, rest) = if let Some(rest) = comment.strip_prefix("@ezno-ignore") {
+			(false, rest)
+		} else if let Some(rest) = comment.strip_prefix("@ezno-expect-error") {
+			(true, rest)
+		} else {
+			return None;
+		};
+
+		let codes = rest
+			.split(|c: char| c.is_whitespace() || c == ',')
+			.filter(|part| !part.is_empty())
+			.map(str::parse)
+			.collect::<Result<Vec<DiagnosticCode>, _>>();
+
+		Some(match codes {
+			Ok(codes) => Ok(Self { expect_error, codes, position }),
+			Err(reason) => Err(TypeCheckError::InvalidSuppressionComment { reason, position }),
+		})
+	}
+
+	fn suppresses(&self, diagnostic: &Diagnostic) -> bool {
+		let kind_matches = !self.expect_error || matches!(diagnostic.kind(), DiagnosticKind::Error);
+		kind_matches
+			&& (self.codes.is_empty()
+				|| diagnostic.code().is_some_and(|code| self.codes.contains(&code)))
+	}
+}
+
+impl DiagnosticsContainer {
+	/// Removes diagnostics suppressed by `comments` and adds errors for unused `@ezno-expect-error`s
+	pub(crate) fn apply_suppression_comments(
+		&mut self,
+		comments: &[SuppressionComment],
+		files: &impl source_map::FileSystem,
+	) {
+		if comments.is_empty() {
+			return;
+		}
+
+		// Byte range of the line after each comment
+		#[allow(clippy::cast_possible_truncation)]
+		let following_lines: Vec<std::ops::Range<u32>> = comments
+			.iter()
+			.map(|comment| {
+				files.get_source_by_id(comment.position.source, |source| {
+					let content = &source.content;
+					let after = content.get(comment.position.end as usize..).unwrap_or_default();
+					let Some(line_break) = after.find('\n') else {
+						return 0..0;
+					};
+					let start = comment.position.end as usize + line_break + 1;
+					let end = content[start..].find('\n').map_or(content.len(), |idx| start + idx);
+					start as u32..end as u32
+				})
+			})
+			.collect();
+
+		let mut used = vec![false; comments.len()];
+		self.retain(|diagnostic| {
+			let Some(position) = diagnostic.position() else { return true };
+			let suppressed_by =
+				comments.iter().zip(&following_lines).position(|(comment, line)| {
+					comment.position.source == position.source
+						&& line.contains(&position.start)
+						&& comment.suppresses(diagnostic)
+				});
+			if let Some(idx) = suppressed_by {
+				used[idx] = true;
+			}
+			suppressed_by.is_none()
+		});
+
+		for (comment, used) in comments.iter().zip(used) {
+			if comment.expect_error && !used {
+				self.add_error(TypeCheckError::UnusedExpectErrorComment(comment.position));
+			}
+		}
+	}
+}
+
 impl IntoIterator for DiagnosticsContainer {
 	type Item = Diagnostic;
 
@@ -264,7 +453,11 @@ pub(crate) struct NoEnvironmentSpecified;
 
 impl From<NoEnvironmentSpecified> for Diagnostic {
 	fn from(_error: NoEnvironmentSpecified) -> Self {
-		Diagnostic::Global { reason: "No environment".to_owned(), kind: DiagnosticKind::Error }
+		Diagnostic::Global {
+			reason: "No environment".to_owned(),
+			kind: DiagnosticKind::Error,
+			code: None,
+		}
 	}
 }
 
@@ -281,7 +474,8 @@ mod defined_errors_and_warnings {
 	use crate::Diagnostic;
 
 	use super::{
-		NotInLoopOrCouldNotFindLabel, PropertyRepresentation, TypeStringRepresentation, TDZ,
+		DiagnosticCode, NotInLoopOrCouldNotFindLabel, PropertyRepresentation,
+		TypeStringRepresentation, TDZ,
 	};
 
 	/// Reasons for errors, intermediate type for generating [Diagnostic]s
@@ -385,6 +579,14 @@ mod defined_errors_and_warnings {
 		PropertyNotWriteable(SpanWithSource),
 		NotTopLevelImport(SpanWithSource),
 		DoubleDefaultExport(SpanWithSource),
+		ConflictingStarExport {
+			name: String,
+			position: SpanWithSource,
+		},
+		InvalidJSON {
+			reason: String,
+			position: SpanWithSource,
+		},
 		CannotOpenFile {
 			file: CouldNotOpenFile,
 			position: Option<SpanWithSource>,
@@ -428,11 +630,93 @@ mod defined_errors_and_warnings {
 			base: TypeStringRepresentation,
 			overload: TypeStringRepresentation,
 		},
+		/// A `// @ezno-expect-error` comment where the following line has no (matching) errors
+		UnusedExpectErrorComment(SpanWithSource),
+		InvalidSuppressionComment {
+			reason: String,
+			position: SpanWithSource,
+		},
+	}
+
+	/// Codes are stable: new variants get new numbers and numbers of removed variants are not reused.
+	/// Errors from calling functions start at 100 and from assignments start at 200
+	impl TypeCheckError<'_> {
+		pub(crate) fn code(&self) -> DiagnosticCode {
+			let number = match self {
+				TypeCheckError::FunctionCallingError(error) => match error {
+					FunctionCallingError::InvalidArgumentType { .. } => 101,
+					FunctionCallingError::MissingArgument { .. } => 102,
+					FunctionCallingError::ExcessArguments { .. } => 103,
+					FunctionCallingError::NotCallable { .. } => 104,
+					FunctionCallingError::ReferenceRestrictionDoesNotMatch { .. } => 105,
+					FunctionCallingError::CyclicRecursion(..) => 106,
+					FunctionCallingError::NoLogicForIdentifier(..) => 107,
+					FunctionCallingError::NeedsToBeCalledWithNewKeyword(..) => 108,
+					FunctionCallingError::TDZ { .. } => 109,
+					FunctionCallingError::SetPropertyConstraint { .. } => 110,
+					FunctionCallingError::UnconditionalThrow { .. } => 111,
+					FunctionCallingError::MismatchedThis { .. } => 112,
+				},
+				TypeCheckError::AssignmentError(error) => match error {
+					AssignmentError::DoesNotMeetConstraint { .. } => 201,
+					AssignmentError::PropertyConstraint { .. } => 202,
+					AssignmentError::Constant(..) => 203,
+					AssignmentError::VariableNotFound { .. } => 204,
+					AssignmentError::TDZ(..) => 205,
+				},
+				TypeCheckError::PropertyDoesNotExist { .. } => 1,
+				TypeCheckError::NotInLoopOrCouldNotFindLabel(..) => 2,
+				TypeCheckError::RestParameterAnnotationShouldBeArrayType(..) => 3,
+				TypeCheckError::CouldNotFindVariable { .. } => 4,
+				TypeCheckError::CouldNotFindType(..) => 5,
+				TypeCheckError::TypeHasNoGenericParameters(..) => 6,
+				TypeCheckError::InvalidComparison(..) => 7,
+				TypeCheckError::InvalidAddition(..) => 8,
+				TypeCheckError::InvalidUnaryOperation(..) => 9,
+				TypeCheckError::ReturnedTypeDoesNotMatch { .. } => 10,
+				TypeCheckError::TypeIsNotIndexable(..) => 11,
+				TypeCheckError::TypeIsNotIterable(..) => 12,
+				TypeCheckError::NonTopLevelExport(..) => 13,
+				TypeCheckError::FieldNotExported { .. } => 14,
+				TypeCheckError::InvalidJSXAttribute { .. } => 15,
+				TypeCheckError::InvalidJSXInterpolatedValue { .. } => 16,
+				TypeCheckError::NotSatisfied { .. } => 17,
+				TypeCheckError::CatchTypeDoesNotMatch { .. } => 18,
+				TypeCheckError::Unsupported { .. } => 19,
+				TypeCheckError::ReDeclaredVariable { .. } => 20,
+				TypeCheckError::InvalidDefaultParameter { .. } => 21,
+				TypeCheckError::FunctionDoesNotMeetConstraint { .. } => 22,
+				TypeCheckError::StatementsNotRun { .. } => 23,
+				TypeCheckError::CannotRedeclareVariable { .. } => 24,
+				TypeCheckError::GenericArgumentDoesNotMeetRestriction { .. } => 25,
+				TypeCheckError::NotDefinedOperator(..) => 26,
+				TypeCheckError::PropertyNotWriteable(..) => 27,
+				TypeCheckError::NotTopLevelImport(..) => 28,
+				TypeCheckError::DoubleDefaultExport(..) => 29,
+				TypeCheckError::ConflictingStarExport { .. } => 30,
+				TypeCheckError::InvalidJSON { .. } => 31,
+				TypeCheckError::CannotOpenFile { .. } => 32,
+				TypeCheckError::VariableNotDefinedInContext { .. } => 33,
+				TypeCheckError::TypeNeedsTypeArguments(..) => 34,
+				TypeCheckError::CannotFindType(..) => 35,
+				TypeCheckError::TypeAlreadyDeclared { .. } => 36,
+				TypeCheckError::TDZ(..) => 37,
+				TypeCheckError::InvalidMathematicalOrBitwiseOperation { .. } => 38,
+				TypeCheckError::InvalidCast { .. } => 39,
+				TypeCheckError::UnreachableVariableClosedOver(..) => 40,
+				TypeCheckError::IncompatibleOverloadParameter { .. } => 41,
+				TypeCheckError::IncompatibleOverloadReturnType { .. } => 42,
+				TypeCheckError::UnusedExpectErrorComment(..) => 43,
+				TypeCheckError::InvalidSuppressionComment { .. } => 44,
+			};
+			DiagnosticCode::error(number)
+		}
 	}
 
 	impl From<TypeCheckError<'_>> for Diagnostic {
 		fn from(error: TypeCheckError<'_>) -> Self {
 			let kind = super::DiagnosticKind::Error;
+			let code = Some(error.code());
 			match error {
 				TypeCheckError::CouldNotFindVariable { variable, possibles: _, position } => {
 					Diagnostic::Position {
@@ -441,13 +725,13 @@ mod defined_errors_and_warnings {
 							// possibles Consider '{:?}'
 						),
 						position,
-						kind,
+						kind, code,
 					}
 				}
 				TypeCheckError::CouldNotFindType(reference, pos) => Diagnostic::Position {
 					reason: format!("Could not find type '{reference}'"),
 					position: pos,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::PropertyDoesNotExist { property, on, site } => {
 					Diagnostic::Position {
@@ -456,7 +740,7 @@ mod defined_errors_and_warnings {
 							PropertyRepresentation::StringKey(property) => format!("No property '{property}' on {on}"),
 						},
 						position: site,
-						kind,
+						kind, code,
 					}
 				}
 				TypeCheckError::FunctionCallingError(error) => match error {
@@ -479,7 +763,7 @@ mod defined_errors_and_warnings {
 									),
 									Some(restriction_pos),
 								)],
-								kind,
+								kind, code,
 							}
 						} else {
 							Diagnostic::PositionWithAdditionalLabels {
@@ -491,7 +775,7 @@ mod defined_errors_and_warnings {
 									format!("Parameter has type {parameter_type}"),
 									Some(parameter_position),
 								)],
-								kind,
+								kind, code,
 							}
 						}
 					}
@@ -499,7 +783,7 @@ mod defined_errors_and_warnings {
 						Diagnostic::PositionWithAdditionalLabels {
 							reason: "Missing argument".into(),
 							position: call_site,
-							kind,
+							kind, code,
 							labels: vec![(
 								"(non-optional) Parameter declared here".into(),
 								Some(parameter_position),
@@ -510,14 +794,14 @@ mod defined_errors_and_warnings {
 						Diagnostic::Position {
 							reason: "Excess argument".into(),
 							position,
-							kind,
+							kind, code,
 						}
 					}
 					FunctionCallingError::NotCallable { calling, call_site } => {
 						Diagnostic::Position {
 							reason: format!("Cannot call type {calling}"),
 							position: call_site,
-							kind,
+							kind, code,
 						}
 					}
 					FunctionCallingError::ReferenceRestrictionDoesNotMatch {
@@ -531,19 +815,19 @@ mod defined_errors_and_warnings {
 					// 		identifier, requirement, found
 					// 	),
 					// 	position: call_site,
-					// 	kind,
+					// 	kind, code,
 					// },
 					FunctionCallingError::CyclicRecursion(_, call_site) => Diagnostic::Position {
 						reason: "Encountered recursion".into(),
 						position: call_site,
-						kind,
+						kind, code,
 					},
-					FunctionCallingError::NoLogicForIdentifier(name, position) => Diagnostic::Position { reason: format!("no logic for constant function {name}"), kind, position },
-					FunctionCallingError::NeedsToBeCalledWithNewKeyword(position) => Diagnostic::Position { reason: "class constructor must be called with new".to_owned(), kind, position },
+					FunctionCallingError::NoLogicForIdentifier(name, position) => Diagnostic::Position { reason: format!("no logic for constant function {name}"), kind, code, position },
+					FunctionCallingError::NeedsToBeCalledWithNewKeyword(position) => Diagnostic::Position { reason: "class constructor must be called with new".to_owned(), kind, code, position },
 					FunctionCallingError::TDZ { error: TDZ { position, variable_name }, call_site } => Diagnostic::PositionWithAdditionalLabels {
 						reason: format!("Variable '{variable_name}' used before declaration"),
 						position: call_site.unwrap(),
-						kind,
+						kind, code,
 						labels: vec![(
 							"Variable referenced here".to_owned(),
 							Some(position),
@@ -552,7 +836,7 @@ mod defined_errors_and_warnings {
 					FunctionCallingError::SetPropertyConstraint { property_type, value_type, assignment_position, call_site } => Diagnostic::PositionWithAdditionalLabels {
 						reason: "Invalid assignment to parameter".to_owned(),
 						position: call_site.unwrap(),
-						kind,
+						kind, code,
 						labels: vec![(
 							format!(
 								"Type {value_type} does not meet property constraint {property_type}"
@@ -566,7 +850,7 @@ mod defined_errors_and_warnings {
 								"{value} unconditionally thrown in function"
 							),
 							position: call_site.unwrap(),
-							kind,
+							kind, code,
 						}
 					}
 					FunctionCallingError::MismatchedThis { call_site, expected, found } => Diagnostic::Position {
@@ -574,7 +858,7 @@ mod defined_errors_and_warnings {
 							"The 'this' context of the function is expected to be {expected}, found {found}"
 						),
 						position: call_site,
-						kind,
+						kind, code,
 					},
 				},
 				TypeCheckError::AssignmentError(error) => match error {
@@ -592,7 +876,7 @@ mod defined_errors_and_warnings {
 							format!("Variable declared with type {variable_type}"),
 							Some(variable_site),
 						)],
-						kind,
+						kind, code,
 					},
 					AssignmentError::PropertyConstraint {
 						property_constraint: property_type,
@@ -603,25 +887,25 @@ mod defined_errors_and_warnings {
 							"Type {value_type} does not meet property constraint {property_type}"
 						),
 						position: assignment_position,
-						kind,
+						kind, code,
 					},
 					AssignmentError::Constant(position) => Diagnostic::Position {
 						reason: "Cannot assign to constant".into(),
 						position,
-						kind,
+						kind, code,
 					},
 					AssignmentError::VariableNotFound { variable, assignment_position } => {
 						Diagnostic::Position {
 							reason: format!("Cannot assign to unknown variable '{variable}'"),
 							position: assignment_position,
-							kind,
+							kind, code,
 						}
 					}
 					AssignmentError::TDZ(TDZ { variable_name, position }) => {
 						Diagnostic::Position {
 							reason: format!("Cannot assign to '{variable_name}' before declaration"),
 							position,
-							kind,
+							kind, code,
 						}
 					}
 				},
@@ -636,7 +920,7 @@ mod defined_errors_and_warnings {
 						"Type {attribute_name} is not assignable to {value_type} attribute of type {attribute_type}",
 					),
 					position: value_site,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::ReturnedTypeDoesNotMatch {
 					annotation_position,
@@ -652,7 +936,7 @@ mod defined_errors_and_warnings {
 						Some(annotation_position),
 					)).collect(),
 					position: returned_position,
-					kind,
+					kind, code,
 				},
         TypeCheckError::InvalidDefaultParameter {
 					expected,
@@ -663,7 +947,7 @@ mod defined_errors_and_warnings {
 						"Cannot use a default value of type {found} for parameter of type {expected}",
           ),
 					position: at,
-					kind,
+					kind, code,
 				},
         TypeCheckError::CatchTypeDoesNotMatch {
 					expected,
@@ -674,13 +958,13 @@ mod defined_errors_and_warnings {
              "Cannot catch type {found} because the try block throws {expected}",
 					),
 					position: at,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::TypeHasNoGenericParameters(name, position) => {
 					Diagnostic::Position {
 						reason: format!("Type '{name}' has no generic parameters",),
 						position,
-						kind,
+						kind, code,
 					}
 				}
 
@@ -692,13 +976,13 @@ mod defined_errors_and_warnings {
 				TypeCheckError::NonTopLevelExport(position) => Diagnostic::Position {
 					reason: "Cannot export at not top level".to_owned(),
 					position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::FieldNotExported { file, importing, position } => {
 					Diagnostic::Position {
 						reason: format!("{importing} not exported from {file}"),
 						position,
-						kind,
+						kind, code,
 					}
 				}
 				TypeCheckError::InvalidJSXInterpolatedValue {
@@ -710,19 +994,19 @@ mod defined_errors_and_warnings {
 					Diagnostic::Position {
 						reason: "Rest parameter annotation should be array type".to_owned(),
 						position: pos,
-						kind,
+						kind, code,
 					}
 				}
 				TypeCheckError::Unsupported { thing, at } => Diagnostic::Position {
 					reason: format!("Unsupported: {thing}"),
 					position: at,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::ReDeclaredVariable { name, position } => {
 					Diagnostic::Position {
 						reason: format!("Cannot declare variable {name}"),
 						position,
-						kind,
+						kind, code,
 					}
 				}
 				TypeCheckError::FunctionDoesNotMeetConstraint {
@@ -734,34 +1018,34 @@ mod defined_errors_and_warnings {
 						"{function_constraint} constraint on function does not match synthesised form {function_type}",
 					),
 					position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::StatementsNotRun { between } => Diagnostic::Position {
 					reason: "Statements are never run".to_owned(),
 					position: between,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::NotSatisfied { at, expected, found } => Diagnostic::Position {
 					reason: format!("Expected {expected}, found {found}"),
 					position: at,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::CannotRedeclareVariable { name, position } => {
 					Diagnostic::Position {
 						reason: format!("Cannot redeclare variable '{name}'"),
 						position,
-						kind,
+						kind, code,
 					}
 				}
 				TypeCheckError::NotDefinedOperator(op, position) => Diagnostic::Position {
 					reason: format!("Operator not typed {op}"),
 					position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::PropertyNotWriteable(position) => Diagnostic::Position {
 					reason: "Property not writeable".into(),
 					position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::GenericArgumentDoesNotMeetRestriction {
 					argument,
@@ -772,22 +1056,32 @@ mod defined_errors_and_warnings {
 						"Generic argument {argument} does not match {parameter_restriction}"
 					),
 					position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::NotTopLevelImport(position) => Diagnostic::Position {
 					reason: "Import must be in the top of the scope".to_owned(),
 					position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::DoubleDefaultExport(_) => todo!(),
+				TypeCheckError::ConflictingStarExport { name, position } => Diagnostic::Position {
+					reason: format!("'{name}' is exported from multiple modules by `export *`"),
+					position,
+					kind, code,
+				},
+				TypeCheckError::InvalidJSON { reason, position } => Diagnostic::Position {
+					reason: format!("Invalid JSON: {reason}"),
+					position,
+					kind, code,
+				},
 				TypeCheckError::CannotOpenFile { file, position } => if let Some(position) = position {
 					Diagnostic::Position {
 						reason: "Cannot find file".to_owned(),
 						position,
-						kind,
+						kind, code,
 					}
 				} else {
-					Diagnostic::Global { reason: format!("Cannot find file {}", file.0.display()), kind }
+					Diagnostic::Global { reason: format!("Cannot find file {}", file.0.display()), kind, code }
 				},
 				TypeCheckError::VariableNotDefinedInContext {
 					variable,
@@ -797,47 +1091,47 @@ mod defined_errors_and_warnings {
 				} => Diagnostic::Position {
 					reason: format!("'{variable}' is only available on the {expected_context}, currently in {current_context}"),
 					position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::TypeNeedsTypeArguments(ty, position) => Diagnostic::Position {
 					reason: format!("Type {ty} requires type arguments"),
 					position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::CannotFindType(ty, position) => Diagnostic::Position {
 					reason: format!("Cannot find type {ty}"),
 					position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::TypeAlreadyDeclared { name, position } => Diagnostic::Position {
 					reason: format!("Type named '{name}' already declared"),
 					position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::TDZ(TDZ { position, variable_name }) => Diagnostic::Position {
 					reason: format!("Variable '{variable_name}' used before declaration"),
 					position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::InvalidMathematicalOrBitwiseOperation { operator, lhs, rhs, position } => Diagnostic::Position {
 					// TODO temp
 					reason: format!("Cannot {lhs} {operator:?} {rhs}"),
 					position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::NotInLoopOrCouldNotFindLabel(_) => todo!(),
 				TypeCheckError::InvalidCast { position, from, to } => {
 					Diagnostic::Position {
 						reason: format!("Cannot cast {from} to {to}"),
 						position,
-						kind,
+						kind, code,
 					}
 				}
 				TypeCheckError::UnreachableVariableClosedOver(name, function_position) => {
 					Diagnostic::Position {
 						reason: format!("Function contains unreachable closed over variable '{name}'"),
 						position: function_position,
-						kind,
+						kind, code,
 					}
 				}
 				TypeCheckError::IncompatibleOverloadParameter { parameter_position, overloaded_parameter_position, parameter, overloaded_parameter } => Diagnostic::PositionWithAdditionalLabels {
@@ -849,7 +1143,7 @@ mod defined_errors_and_warnings {
 						Some(parameter_position),
 					)],
 					position: overloaded_parameter_position,
-					kind,
+					kind, code,
 				},
 				TypeCheckError::IncompatibleOverloadReturnType { base_position, overload_position, base, overload } => Diagnostic::PositionWithAdditionalLabels {
 				reason: format!(
@@ -860,8 +1154,18 @@ mod defined_errors_and_warnings {
 					Some(base_position),
 				)],
 				position: overload_position,
-				kind,
+				kind, code,
 			},
+				TypeCheckError::UnusedExpectErrorComment(position) => Diagnostic::Position {
+					reason: "Unused '@ezno-expect-error' comment".to_owned(),
+					position,
+					kind, code,
+				},
+				TypeCheckError::InvalidSuppressionComment { reason, position } => Diagnostic::Position {
+					reason,
+					position,
+					kind, code,
+				},
 			}
 		}
 	}
@@ -892,45 +1196,91 @@ mod defined_errors_and_warnings {
 		},
 		InvalidOrUnimplementedDefinitionFileItem(SpanWithSource),
 		Unreachable(SpanWithSource),
+		UnusedImport {
+			name: String,
+			position: SpanWithSource,
+		},
+		UnusedDeclaration {
+			name: String,
+			position: SpanWithSource,
+		},
+		UnusedExport {
+			name: String,
+			position: SpanWithSource,
+		},
+		/// The module is still being synthesised, so the imported bindings are not checked
+		CircularImport {
+			path: String,
+			position: SpanWithSource,
+		},
+	}
+
+	/// See [`TypeCheckError::code`]
+	impl TypeCheckWarning {
+		pub(crate) fn code(&self) -> DiagnosticCode {
+			let number = match self {
+				TypeCheckWarning::AwaitUsedOnNonPromise(..) => 1,
+				TypeCheckWarning::DeadBranch { .. } => 2,
+				TypeCheckWarning::IgnoringAsExpression(..) => 3,
+				TypeCheckWarning::Unimplemented { .. } => 4,
+				TypeCheckWarning::UselessExpression { .. } => 5,
+				TypeCheckWarning::MergingInterfaceInSameContext { .. } => 6,
+				TypeCheckWarning::TypesDoNotIntersect { .. } => 7,
+				TypeCheckWarning::InvalidOrUnimplementedDefinitionFileItem(..) => 8,
+				TypeCheckWarning::Unreachable(..) => 9,
+				TypeCheckWarning::UnusedImport { .. } => 10,
+				TypeCheckWarning::UnusedDeclaration { .. } => 11,
+				TypeCheckWarning::UnusedExport { .. } => 12,
+				TypeCheckWarning::CircularImport { .. } => 13,
+			};
+			DiagnosticCode::warning(number)
+		}
 	}
 
 	impl From<TypeCheckWarning> for Diagnostic {
 		fn from(warning: TypeCheckWarning) -> Self {
 			let kind = super::DiagnosticKind::Warning;
+			let code = Some(warning.code());
 
 			match warning {
 				TypeCheckWarning::AwaitUsedOnNonPromise(position) => Diagnostic::Position {
 					reason: "Unnecessary await expression / type is not promise".to_owned(),
 					position,
 					kind,
+					code,
 				},
 				TypeCheckWarning::DeadBranch { expression_span, expression_value } => {
 					Diagnostic::Position {
 						reason: format!("Expression is always {expression_value:?}"),
 						position: expression_span,
 						kind,
+						code,
 					}
 				}
 				TypeCheckWarning::IgnoringAsExpression(position) => Diagnostic::Position {
 					reason: "'as' expressions are ignore by the checker".to_owned(),
 					position,
 					kind,
+					code,
 				},
 				TypeCheckWarning::Unimplemented { thing, at } => Diagnostic::Position {
 					reason: format!("Unsupported: {thing}"),
 					position: at,
 					kind,
+					code,
 				},
 				TypeCheckWarning::UselessExpression { expression_span } => Diagnostic::Position {
 					reason: "Expression is always true".to_owned(),
 					position: expression_span,
 					kind,
+					code,
 				},
 				TypeCheckWarning::MergingInterfaceInSameContext { position } => {
 					Diagnostic::Position {
 						reason: "Merging interfaces in the same context".to_owned(),
 						position,
 						kind,
+						code,
 					}
 				}
 				TypeCheckWarning::TypesDoNotIntersect { left, right, position } => {
@@ -938,6 +1288,7 @@ mod defined_errors_and_warnings {
 						reason: format!("No intersection between types {left} and {right}"),
 						position,
 						kind,
+						code,
 					}
 				}
 				TypeCheckWarning::InvalidOrUnimplementedDefinitionFileItem(position) => {
@@ -946,12 +1297,40 @@ mod defined_errors_and_warnings {
 							.to_owned(),
 						position,
 						kind,
+						code,
 					}
 				}
 				TypeCheckWarning::Unreachable(position) => Diagnostic::Position {
 					reason: "Unreachable statement".to_owned(),
 					position,
 					kind,
+					code,
+				},
+				TypeCheckWarning::UnusedImport { name, position } => Diagnostic::Position {
+					reason: format!("Unused import '{name}'"),
+					position,
+					kind,
+					code,
+				},
+				TypeCheckWarning::UnusedDeclaration { name, position } => Diagnostic::Position {
+					reason: format!("'{name}' is declared but never read"),
+					position,
+					kind,
+					code,
+				},
+				TypeCheckWarning::UnusedExport { name, position } => Diagnostic::Position {
+					reason: format!("Export '{name}' is not imported by any checked module"),
+					position,
+					kind,
+					code,
+				},
+				TypeCheckWarning::CircularImport { path, position } => Diagnostic::Position {
+					reason: format!(
+						"'{path}' is imported circularly, so the imported bindings are not checked"
+					),
+					position,
+					kind,
+					code,
 				},
 			}
 		}
@@ -974,6 +1353,7 @@ impl From<TypeDefinitionModuleNotFound> for Diagnostic {
 				val.0.as_path().display()
 			),
 			kind: DiagnosticKind::Error,
+			code: None,
 		}
 	}
 }
@@ -985,6 +1365,7 @@ impl From<EntryPointNotFound> for Diagnostic {
 		Diagnostic::Global {
 			reason: format!("Could not entry point module at '{}'", val.0.as_path().display()),
 			kind: DiagnosticKind::Error,
+			code: None,
 		}
 	}
 }
@@ -1,5 +1,7 @@
 use std::{collections::HashMap, iter};
 
+use source_map::Span;
+
 use crate::{
 	context::{
 		environment::Label, get_value_of_variable, information::get_properties_on_type,
@@ -12,9 +14,10 @@ use crate::{
 	features::operations::CanonicalEqualityAndInequality,
 	types::{
 		generics::{generic_type_arguments::TypeArgumentStore, FunctionTypeArguments},
-		substitute, Constructor, ObjectNature, PolyNature, TypeStore,
+		is_type_truthy_falsy, substitute, Constructor, ObjectNature, PolyNature, TypeStore,
 	},
-	CheckingData, Constant, Environment, LocalInformation, Scope, Type, TypeId, VariableId,
+	CheckingData, Constant, Decidable, Environment, LocalInformation, Scope, Type, TypeId,
+	VariableId,
 };
 
 #[derive(Clone, Copy)]
@@ -40,6 +43,7 @@ pub enum IterationBehavior<'a, A: crate::ASTImplementation> {
 pub fn synthesise_iteration<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 	behavior: IterationBehavior<A>,
 	label: Label,
+	position: Span,
 	environment: &mut Environment,
 	checking_data: &mut CheckingData<T, A>,
 	loop_body: impl FnOnce(&mut Environment, &mut CheckingData<T, A>),
@@ -94,6 +98,8 @@ pub fn synthesise_iteration<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 				&loop_info,
 			);
 
+			record_loop_without_iterations(condition, fixed_iterations, position, checking_data);
+
 			let mut errors_and_info = ErrorsAndInfo::default();
 
 			let run_iteration_block = run_iteration_block(
@@ -120,6 +126,7 @@ pub fn synthesise_iteration<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 						// TODO temp
 						position: source_map::Nullable::NULL,
 						kind: crate::diagnostics::DiagnosticKind::Info,
+						code: None,
 					},
 				);
 			}
@@ -303,6 +310,8 @@ pub fn synthesise_iteration<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 				&loop_info,
 			);
 
+			record_loop_without_iterations(condition, fixed_iterations, position, checking_data);
+
 			for (var, (start, _)) in dependent_variables {
 				environment.info.variable_current_value.insert(var, start);
 			}
@@ -692,6 +701,26 @@ struct Values {
 	>,
 }
 
+/// For removing the loop from the output
+fn record_loop_without_iterations<T: crate::ReadFromFS, A: crate::ASTImplementation>(
+	condition: TypeId,
+	fixed_iterations: Result<LoopStructure, ()>,
+	position: Span,
+	checking_data: &mut CheckingData<T, A>,
+) {
+	if !checking_data.options.store_expression_type_mappings {
+		return;
+	}
+	let without_iterations =
+		matches!(is_type_truthy_falsy(condition, &checking_data.types), Decidable::Known(false))
+			|| fixed_iterations.is_ok_and(|under| {
+				matches!(under.calculate_iterations(&checking_data.types), Ok(0))
+			});
+	if without_iterations {
+		checking_data.local_type_mappings.loops_without_iterations.push(position, ());
+	}
+}
+
 /// Not quite a "Hoare triple"
 #[derive(Debug, Clone, Copy, binary_serialize_derive::BinarySerializable)]
 pub struct LoopStructure {
@@ -1,16 +1,24 @@
-use std::path::{Path, PathBuf};
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+};
 
 use super::variables::{VariableMutability, VariableOrImport};
 use crate::{
 	context::{
-		information::{get_value_of_constant_import_variable, LocalInformation},
+		information::{get_value_of_constant_import_variable, LocalInformation, Publicity},
 		VariableRegisterArguments,
 	},
-	get_source, CheckingData, Environment, Instance, Scope, Type, TypeId, TypeMappings, VariableId,
+	features::objects::ObjectBuilder,
+	get_source,
+	types::properties::{PropertyKey, PropertyValue},
+	unused::ImportedBinding,
+	CheckingData, Constant, Environment, File, Instance, Scope, Type, TypeId, TypeMappings,
+	VariableId,
 };
 
 use simple_json_parser::{JSONKey, RootJSONValue};
-use source_map::{FileSystem, Span};
+use source_map::{FileSystem, Span, SpanWithSource};
 
 #[derive(Debug)]
 pub struct NamePair<'a> {
@@ -85,9 +93,68 @@ impl Exported {
 
 		(variable, r#type)
 	}
+
+	/// For `export * from ...`. Does not include `default`.
+	///
+	/// Names that are also exported by a previous `export *` (but refer to a different item) are
+	/// ambiguous, so are removed and skipped by later `export *`s. Returns the newly ambiguous names
+	pub(crate) fn add_star_exported(
+		&mut self,
+		from: &Exported,
+		ambiguous: &mut Vec<String>,
+	) -> Vec<String> {
+		let mut conflicts = Vec::new();
+		for (name, (variable, mutability)) in &from.named {
+			if name == "default" || ambiguous.contains(name) {
+				continue;
+			}
+			if let Some(index) = self.named.iter().position(|(export, _)| export == name) {
+				if self.named[index].1 .0 != *variable {
+					self.named.remove(index);
+					conflicts.push(name.clone());
+				}
+			} else {
+				self.named.push((name.clone(), (*variable, *mutability)));
+			}
+		}
+		for (name, ty) in &from.named_types {
+			if ambiguous.contains(name) {
+				continue;
+			}
+			let existing = self.named_types.iter().position(|(export, _)| export == name);
+			if conflicts.contains(name)
+				|| existing.is_some_and(|index| self.named_types[index].1 != *ty)
+			{
+				if let Some(index) = existing {
+					self.named_types.remove(index);
+				}
+				if !conflicts.contains(name) {
+					conflicts.push(name.clone());
+				}
+			} else if existing.is_none() {
+				self.named_types.push((name.clone(), *ty));
+			}
+		}
+		ambiguous.extend(conflicts.iter().cloned());
+		conflicts
+	}
+
+	/// Explicit exports take precedence over ones from `export * from ...`
+	pub(crate) fn merge_star_exported(&mut self, star_exported: Exported) {
+		for (name, value) in star_exported.named {
+			if !self.named.iter().any(|(export, _)| *export == name) {
+				self.named.push((name, value));
+			}
+		}
+		for (name, ty) in star_exported.named_types {
+			if !self.named_types.iter().any(|(export, _)| *export == name) {
+				self.named_types.push((name, ty));
+			}
+		}
+	}
 }
 
-/// After a syntax error
+/// After a syntax error or a circular import
 pub struct InvalidModule;
 
 pub type FinalModule<M> = Result<SynthesisedModule<M>, InvalidModule>;
@@ -110,6 +177,8 @@ pub fn import_items<
 	checking_data: &mut CheckingData<T, A>,
 	also_export: bool,
 	type_only: bool,
+	// The `type` in `with { type: "json" }`
+	attribute_type: Option<&str>,
 ) {
 	if !matches!(environment.context_type.scope, crate::Scope::Module { .. }) {
 		checking_data.diagnostics_container.add_error(
@@ -120,7 +189,13 @@ pub fn import_items<
 		return;
 	}
 
-	let exports = import_file(partial_import_path, environment, checking_data);
+	let exports = import_file(
+		partial_import_path,
+		attribute_type,
+		import_position.with_source(environment.get_source()),
+		environment,
+		checking_data,
+	);
 
 	if let Err(ref err) = exports {
 		checking_data.diagnostics_container.add_error(
@@ -135,20 +210,20 @@ pub fn import_items<
 
 	if let Some((default_name, position)) = default_import {
 		if let Ok(Ok(ref exports)) = exports {
-			if let Some(item) = &exports.default {
-				let id = crate::VariableId(current_source, position.start);
-				let v = VariableOrImport::ConstantImport {
-					to: None,
-					import_specified_at: position.with_source(current_source),
-				};
-				environment.info.variable_current_value.insert(id, *item);
-				let existing = environment.variables.insert(default_name.to_owned(), v);
-				if let Some(_existing) = existing {
-					todo!("diagnostic")
-				}
-			} else {
-				todo!("emit 'no default export' diagnostic")
-			}
+			import_default(
+				exports,
+				default_name,
+				position,
+				partial_import_path,
+				environment,
+				checking_data,
+			);
+			checking_data.usage.imports.push(ImportedBinding {
+				name: default_name.to_owned(),
+				variable: Some(crate::VariableId(current_source, position.start)),
+				is_type: false,
+				position: position.with_source(current_source),
+			});
 		} else {
 			environment.register_variable_handle_error(
 				default_name,
@@ -167,9 +242,60 @@ pub fn import_items<
 		ImportKind::Parts(parts) => {
 			for part in parts {
 				if let Ok(Ok(ref exports)) = exports {
+					if part.value == "default" && !type_only {
+						let value = import_default(
+							exports,
+							part.r#as,
+							part.position,
+							partial_import_path,
+							environment,
+							checking_data,
+						);
+						if !also_export {
+							checking_data.usage.imports.push(ImportedBinding {
+								name: part.r#as.to_owned(),
+								variable: Some(crate::VariableId(
+									current_source,
+									part.position.start,
+								)),
+								is_type: false,
+								position: part.position.with_source(current_source),
+							});
+						} else if let (Some(value), Scope::Module { ref mut exported, .. }) =
+							(value, &mut environment.context_type.scope)
+						{
+							if part.r#as == "default" {
+								exported.default = Some(value);
+							} else {
+								let id = crate::VariableId(current_source, part.position.start);
+								exported.named.push((
+									part.r#as.to_owned(),
+									(id, VariableMutability::Constant),
+								));
+							}
+						}
+						continue;
+					}
+
 					let (exported_variable, exported_type) =
 						exports.get_export(part.value, type_only);
 
+					if let Some((variable, _)) = exported_variable {
+						checking_data.usage.imported_variables.insert((current_source, variable));
+						if also_export {
+							checking_data.usage.used_exports.insert((current_source, variable));
+						}
+					}
+					if !also_export && (exported_variable.is_some() || exported_type.is_some()) {
+						checking_data.usage.imports.push(ImportedBinding {
+							name: part.r#as.to_owned(),
+							variable: exported_variable
+								.map(|_| crate::VariableId(current_source, part.position.start)),
+							is_type: exported_type.is_some(),
+							position: part.position.with_source(current_source),
+						});
+					}
+
 					if exported_variable.is_none() && exported_type.is_none() {
 						let position = part.position.with_source(current_source);
 						checking_data.diagnostics_container.add_error(
@@ -226,6 +352,13 @@ pub fn import_items<
 					if let Some(ty) = exported_type {
 						let existing = environment.named_types.insert(part.r#as.to_owned(), ty);
 						assert!(existing.is_none(), "TODO exception");
+						if also_export {
+							if let Scope::Module { ref mut exported, .. } =
+								environment.context_type.scope
+							{
+								exported.named_types.push((part.r#as.to_owned(), ty));
+							}
+						}
 					}
 				} else {
 					// This happens if imported is an invalid file (syntax issue, doesn't exist etc)
@@ -245,7 +378,25 @@ pub fn import_items<
 			}
 		}
 		ImportKind::All { under, position } => {
-			if let Ok(Ok(ref exports)) = exports {
+			if let (Ok(Ok(ref exports)), true) = (&exports, also_export) {
+				// `export * as under from ...` does not put the namespace into scope
+				let value = checking_data.types.register_type(Type::SpecialObject(
+					crate::features::objects::SpecialObjects::Import(exports.clone()),
+				));
+				let id = crate::VariableId(current_source, position.start);
+				environment.info.variable_current_value.insert(id, value);
+				if let Scope::Module { ref mut exported, .. } = environment.context_type.scope {
+					exported.named.push((under.to_owned(), (id, VariableMutability::Constant)));
+				}
+				checking_data.usage.mark_all_imported(current_source, exports);
+			} else if let Ok(Ok(ref exports)) = exports {
+				checking_data.usage.mark_all_imported(current_source, exports);
+				checking_data.usage.imports.push(ImportedBinding {
+					name: under.to_owned(),
+					variable: Some(crate::VariableId(current_source, position.start)),
+					is_type: false,
+					position: position.with_source(current_source),
+				});
 				let value = checking_data.types.register_type(Type::SpecialObject(
 					crate::features::objects::SpecialObjects::Import(exports.clone()),
 				));
@@ -260,8 +411,7 @@ pub fn import_items<
 					position.with_source(current_source),
 					&mut checking_data.diagnostics_container,
 				);
-			} else {
-				crate::utilities::notify!("TODO :?");
+			} else if !also_export {
 				environment.register_variable_handle_error(
 					under,
 					VariableRegisterArguments {
@@ -275,41 +425,150 @@ pub fn import_items<
 			}
 		}
 		ImportKind::Everything => {
+			// Nothing is put into scope. Errors from invalid or missing files have already been emitted
 			if let Ok(Ok(ref exports)) = exports {
-				for (name, (variable, mutability)) in &exports.named {
-					// TODO are variables put into scope?
-					if let Scope::Module { ref mut exported, .. } = environment.context_type.scope {
-						exported.named.push((name.clone(), (*variable, *mutability)));
+				checking_data.usage.mark_all_imported(current_source, exports);
+				if let Scope::Module {
+					ref mut star_exported, ref mut ambiguous_star_exports, ..
+				} = environment.context_type.scope
+				{
+					let conflicts =
+						star_exported.add_star_exported(exports, ambiguous_star_exports);
+					for name in conflicts {
+						checking_data.diagnostics_container.add_error(
+							crate::diagnostics::TypeCheckError::ConflictingStarExport {
+								name,
+								position: import_position.with_source(current_source),
+							},
+						);
 					}
 				}
-			} else {
-				// TODO ??
 			}
 		}
 	}
 }
 
+/// Registers a variable for the default export of a module. Returns the value if it exists
+fn import_default<T: crate::ReadFromFS, A: crate::ASTImplementation>(
+	exports: &Exported,
+	name: &str,
+	position: Span,
+	partial_import_path: &str,
+	environment: &mut Environment,
+	checking_data: &mut CheckingData<T, A>,
+) -> Option<TypeId> {
+	let current_source = environment.get_source();
+	// `export { x as default }` is registered under `named`
+	let value = exports.default.or_else(|| {
+		exports
+			.get_export("default", false)
+			.0
+			.map(|(variable, _)| get_value_of_constant_import_variable(variable, environment))
+	});
+
+	if let Some(value) = value {
+		let id = crate::VariableId(current_source, position.start);
+		let v = VariableOrImport::ConstantImport {
+			to: None,
+			import_specified_at: position.with_source(current_source),
+		};
+		environment.info.variable_current_value.insert(id, value);
+		let existing = environment.variables.insert(name.to_owned(), v);
+		if let Some(_existing) = existing {
+			todo!("diagnostic")
+		}
+	} else {
+		let position = position.with_source(current_source);
+		checking_data.diagnostics_container.add_error(
+			crate::diagnostics::TypeCheckError::FieldNotExported {
+				file: partial_import_path,
+				position,
+				importing: "default",
+			},
+		);
+		environment.register_variable_handle_error(
+			name,
+			VariableRegisterArguments {
+				constant: true,
+				space: None,
+				initial_value: Some(TypeId::ERROR_TYPE),
+			},
+			position,
+			&mut checking_data.diagnostics_container,
+		);
+	}
+	value
+}
+
+/// `import("./path")` with a constant path. Resolves to a namespace object of the exports, like
+/// `import * as x from "./path"`
+pub fn synthesise_dynamic_import<T: crate::ReadFromFS, A: crate::ASTImplementation>(
+	partial_import_path: &str,
+	position: SpanWithSource,
+	environment: &mut Environment,
+	checking_data: &mut CheckingData<T, A>,
+) -> TypeId {
+	match import_file(partial_import_path, None, position, environment, checking_data) {
+		Ok(Ok(exports)) => {
+			checking_data.usage.mark_all_imported(environment.get_source(), &exports);
+			let namespace = checking_data.types.register_type(Type::SpecialObject(
+				crate::features::objects::SpecialObjects::Import(exports),
+			));
+			checking_data.types.new_promise_type(namespace, position)
+		}
+		Ok(Err(InvalidModule)) => TypeId::ERROR_TYPE,
+		Err(err) => {
+			checking_data.diagnostics_container.add_error(
+				crate::diagnostics::TypeCheckError::CannotOpenFile {
+					file: err,
+					position: Some(position),
+				},
+			);
+			TypeId::ERROR_TYPE
+		}
+	}
+}
+
+/// Modules cannot be synthesised from JSON files (as there is no AST), so these are stored separately
+/// under the path and whether they were imported with `with { type: "json" }`
+pub(crate) type JSONModules = HashMap<(PathBuf, bool), (Exported, LocalInformation)>;
+
 pub fn import_file<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 	to_import: &str,
+	attribute_type: Option<&str>,
+	position: SpanWithSource,
 	environment: &mut Environment,
 	checking_data: &mut CheckingData<T, A>,
 ) -> Result<Result<Exported, InvalidModule>, CouldNotOpenFile> {
+	/// `None` for a module which is still being synthesised
+	type ModuleError<A> = Option<<A as crate::ASTImplementation>::ParseError>;
+
 	fn get_module<'a, T: crate::ReadFromFS, A: crate::ASTImplementation>(
 		full_importer: &Path,
 		_definition_file: Option<&Path>,
 		environment: &mut Environment,
 		checking_data: &'a mut CheckingData<T, A>,
-	) -> Option<Result<&'a SynthesisedModule<A::OwnedModule>, A::ParseError>> {
+	) -> Option<Result<&'a SynthesisedModule<A::OwnedModule>, ModuleError<A>>> {
 		let existing = checking_data.modules.files.get_source_at_path(full_importer);
-		if let Some(existing) = existing {
-			Some(Ok(checking_data
+		let pending = existing.is_some_and(|existing| {
+			checking_data
 				.modules
-				.synthesised_modules
-				.get(&existing)
-				.expect("existing file, but not synthesised")))
+				.previous
+				.as_ref()
+				.is_some_and(|previous| previous.pending.contains(&existing))
+		});
+
+		if let Some(existing) = existing.filter(|_| !pending) {
+			record_dependency(full_importer, environment, checking_data);
+			// Modules are added once synthesised, so a missing one is part of a circular import
+			Some(checking_data.modules.synthesised_modules.get(&existing).ok_or(None))
+		} else if let Some(source) = crate::incremental::restore(full_importer, checking_data) {
+			record_dependency(full_importer, environment, checking_data);
+			checking_data.modules.synthesised_modules.get(&source).map(Ok)
 		} else {
 			let content = checking_data.modules.file_reader.read_file(full_importer);
 			if let Some(content) = content {
+				record_dependency(full_importer, environment, checking_data);
 				let (source, module) =
 					get_source(checking_data, full_importer, String::from_utf8(content).unwrap());
 
@@ -320,9 +579,10 @@ pub fn import_file<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 							root.new_module_context(source, module, checking_data);
 						Some(Ok(new_module_context))
 					}
-					Err(err) => Some(Err(err)),
+					Err(err) => Some(Err(Some(err))),
 				}
 			} else {
+				record_resolution_input(full_importer, environment, checking_data);
 				None
 			}
 		}
@@ -378,7 +638,21 @@ pub fn import_file<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 				.unwrap()
 				.to_path_buf();
 
-		if full_importer.extension().is_some() {
+		if full_importer.extension().is_some_and(|ext| ext == "json") {
+			let precise = attribute_type == Some("json");
+			let result =
+				get_json_module(full_importer.clone(), precise, environment, checking_data);
+			if result.is_some() {
+				record_dependency(&full_importer, environment, checking_data);
+			} else {
+				record_resolution_input(&full_importer, environment, checking_data);
+			}
+			return match result {
+				Some(Ok(exported)) => Ok(Ok(exported)),
+				Some(Err(())) => Ok(Err(InvalidModule)),
+				None => Err(CouldNotOpenFile(PathBuf::from(to_import.to_owned()))),
+			};
+		} else if full_importer.extension().is_some() {
 			get_module(&full_importer, None, environment, checking_data)
 		} else {
 			let mut result = None;
@@ -394,6 +668,13 @@ pub fn import_file<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 		}
 	} else {
 		crate::utilities::notify!("Here {}", to_import);
+		let package_json = checking_data
+			.modules
+			.current_working_directory
+			.join("node_modules")
+			.join(to_import)
+			.join("package.json");
+		record_resolution_input(&package_json, environment, checking_data);
 		let result = get_package_from_node_modules(
 			to_import,
 			&checking_data.modules.current_working_directory,
@@ -412,10 +693,330 @@ pub fn import_file<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 			environment.info.extend_ref(&synthesised_module.info);
 			Ok(Ok(synthesised_module.exported.clone()))
 		}
-		Some(Err(error)) => {
+		Some(Err(Some(error))) => {
 			checking_data.diagnostics_container.add_error(error);
 			Ok(Err(InvalidModule))
 		}
+		Some(Err(None)) => {
+			checking_data.diagnostics_container.add_warning(
+				crate::diagnostics::TypeCheckWarning::CircularImport {
+					path: to_import.to_owned(),
+					position,
+				},
+			);
+			Ok(Err(InvalidModule))
+		}
 		None => Err(CouldNotOpenFile(PathBuf::from(to_import.to_owned()))),
 	}
 }
+
+/// Records that the current module imports `path`. For incremental checking
+fn record_dependency<T: crate::ReadFromFS, A: crate::ASTImplementation>(
+	path: &Path,
+	environment: &Environment,
+	checking_data: &mut CheckingData<T, A>,
+) {
+	checking_data
+		.modules
+		.dependencies
+		.entry(environment.get_source())
+		.or_default()
+		.push(path.to_path_buf());
+}
+
+/// Records that resolving an import of the current module depends on the file at `path` (which
+/// may not exist). For incremental checking and watching
+fn record_resolution_input<T: crate::ReadFromFS, A: crate::ASTImplementation>(
+	path: &Path,
+	environment: &Environment,
+	checking_data: &mut CheckingData<T, A>,
+) {
+	checking_data
+		.modules
+		.resolution_inputs
+		.entry(environment.get_source())
+		.or_default()
+		.push(path.to_path_buf());
+}
+
+/// Synthesises the content of a JSON file as the default export. Literal types are only used when
+/// `precise` (imported with `with { type: "json" }`) as otherwise the file is expected to change
+fn get_json_module<T: crate::ReadFromFS, A: crate::ASTImplementation>(
+	path: PathBuf,
+	precise: bool,
+	environment: &mut Environment,
+	checking_data: &mut CheckingData<T, A>,
+) -> Option<Result<Exported, ()>> {
+	enum JSONValue {
+		String(String),
+		Number(f64),
+		Boolean(bool),
+		Null,
+		Object(Vec<(String, JSONValue)>),
+		Array(Vec<JSONValue>),
+	}
+
+	/// Parses JSON (with comments). Unlike `simple_json_parser`, which only calls back for
+	/// leaf values, this keeps empty objects and arrays and unescapes strings
+	struct JSONParser<'a> {
+		on: &'a str,
+		at: usize,
+	}
+
+	type JSONParseError = (usize, &'static str);
+
+	impl<'a> JSONParser<'a> {
+		fn rest(&self) -> &'a str {
+			&self.on[self.at..]
+		}
+
+		fn skip_whitespace_and_comments(&mut self) {
+			loop {
+				let rest = self.rest();
+				let trimmed = rest.trim_start();
+				self.at += rest.len() - trimmed.len();
+				if let Some(comment) = trimmed.strip_prefix("//") {
+					self.at += 2 + comment.find('\n').unwrap_or(comment.len());
+				} else if let Some(comment) = trimmed.strip_prefix("/*") {
+					self.at += 2 + comment.find("*/").map_or(comment.len(), |end| end + 2);
+				} else {
+					break;
+				}
+			}
+		}
+
+		fn eat(&mut self, expected: char) -> bool {
+			self.skip_whitespace_and_comments();
+			let found = self.rest().starts_with(expected);
+			if found {
+				self.at += expected.len_utf8();
+			}
+			found
+		}
+
+		fn parse_value(&mut self) -> Result<JSONValue, JSONParseError> {
+			self.skip_whitespace_and_comments();
+			let rest = self.rest();
+			let keyword =
+				[("true", JSONValue::Boolean(true)), ("false", JSONValue::Boolean(false))]
+					.into_iter()
+					.chain(std::iter::once(("null", JSONValue::Null)))
+					.find(|(keyword, _)| rest.starts_with(keyword));
+
+			if let Some((keyword, value)) = keyword {
+				self.at += keyword.len();
+				Ok(value)
+			} else if self.eat('{') {
+				let mut entries: Vec<(String, JSONValue)> = Vec::new();
+				if self.eat('}') {
+					return Ok(JSONValue::Object(entries));
+				}
+				loop {
+					self.skip_whitespace_and_comments();
+					let key = self.parse_string()?;
+					if !self.eat(':') {
+						return Err((self.at, "expected ':'"));
+					}
+					let value = self.parse_value()?;
+					// Later keys override earlier ones
+					if let Some(entry) = entries.iter_mut().find(|(existing, _)| *existing == key) {
+						entry.1 = value;
+					} else {
+						entries.push((key, value));
+					}
+					if self.eat('}') {
+						return Ok(JSONValue::Object(entries));
+					} else if !self.eat(',') {
+						return Err((self.at, "expected ',' or '}'"));
+					}
+				}
+			} else if self.eat('[') {
+				let mut items = Vec::new();
+				if self.eat(']') {
+					return Ok(JSONValue::Array(items));
+				}
+				loop {
+					items.push(self.parse_value()?);
+					if self.eat(']') {
+						return Ok(JSONValue::Array(items));
+					} else if !self.eat(',') {
+						return Err((self.at, "expected ',' or ']'"));
+					}
+				}
+			} else if rest.starts_with('"') {
+				self.parse_string().map(JSONValue::String)
+			} else if rest.starts_with(|c: char| c == '-' || c.is_ascii_digit()) {
+				let length = rest
+					.find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+					.unwrap_or(rest.len());
+				let number = rest[..length].parse().map_err(|_| (self.at, "invalid number"))?;
+				self.at += length;
+				Ok(JSONValue::Number(number))
+			} else {
+				Err((self.at, "expected value"))
+			}
+		}
+
+		fn parse_string(&mut self) -> Result<String, JSONParseError> {
+			let Some(rest) = self.rest().strip_prefix('"') else {
+				return Err((self.at, "expected string"));
+			};
+			let mut value = String::new();
+			let mut chars = rest.char_indices();
+			while let Some((idx, char)) = chars.next() {
+				match char {
+					'"' => {
+						self.at += idx + 2;
+						return Ok(value);
+					}
+					'\\' => match chars.next().map(|(_, escaped)| escaped) {
+						Some('n') => value.push('\n'),
+						Some('t') => value.push('\t'),
+						Some('r') => value.push('\r'),
+						Some('b') => value.push('\u{8}'),
+						Some('f') => value.push('\u{c}'),
+						Some('u') => {
+							// `idx` is the position of the backslash
+							let code_unit = |at: usize| {
+								rest.get(at..at + 4)
+									.and_then(|hex| u16::from_str_radix(hex, 16).ok())
+							};
+							let mut code_units =
+								vec![code_unit(idx + 2).ok_or((self.at, "invalid escape"))?];
+							// Surrogate pairs are written as two escapes
+							if (0xD800..0xDC00).contains(&code_units[0])
+								&& rest.get(idx + 6..idx + 8) == Some("\\u")
+							{
+								code_units.extend(code_unit(idx + 8));
+							}
+							// Skip the (ASCII) hex digits and any second escape
+							chars.nth(code_units.len() * 6 - 3);
+							value.extend(
+								char::decode_utf16(code_units)
+									.map(|result| result.unwrap_or(char::REPLACEMENT_CHARACTER)),
+							);
+						}
+						Some(escaped) => value.push(escaped),
+						None => break,
+					},
+					char => value.push(char),
+				}
+			}
+			Err((self.on.len(), "unterminated string"))
+		}
+	}
+
+	impl JSONValue {
+		fn into_type(
+			self,
+			precise: bool,
+			types: &mut crate::types::TypeStore,
+			info: &mut LocalInformation,
+		) -> TypeId {
+			match self {
+				JSONValue::String(value) if precise => {
+					types.new_constant_type(Constant::String(value))
+				}
+				JSONValue::String(_) => TypeId::STRING_TYPE,
+				JSONValue::Number(value) if precise => {
+					if let Ok(value) = value.try_into() {
+						types.new_constant_type(Constant::Number(value))
+					} else {
+						TypeId::NUMBER_TYPE
+					}
+				}
+				JSONValue::Number(_) => TypeId::NUMBER_TYPE,
+				JSONValue::Boolean(true) if precise => TypeId::TRUE,
+				JSONValue::Boolean(false) if precise => TypeId::FALSE,
+				JSONValue::Boolean(_) => TypeId::BOOLEAN_TYPE,
+				JSONValue::Null => TypeId::NULL_TYPE,
+				JSONValue::Object(entries) => {
+					let object = ObjectBuilder::new(None, types, info).build_object();
+					for (key, value) in entries {
+						let value = value.into_type(precise, types, info);
+						let key = PropertyKey::String(std::borrow::Cow::Owned(key));
+						info.register_property(
+							object,
+							Publicity::Public,
+							key,
+							PropertyValue::Value(value),
+							false,
+							None,
+						);
+					}
+					object
+				}
+				JSONValue::Array(items) => {
+					let array =
+						ObjectBuilder::new(Some(TypeId::ARRAY_TYPE), types, info).build_object();
+					let length = items.len();
+					for (idx, value) in items.into_iter().enumerate() {
+						let value = value.into_type(precise, types, info);
+						info.register_property(
+							array,
+							Publicity::Public,
+							PropertyKey::from_usize(idx),
+							PropertyValue::Value(value),
+							false,
+							None,
+						);
+					}
+					#[allow(clippy::cast_precision_loss)]
+					let length = types.new_constant_type(Constant::Number((length as f64).try_into().unwrap()));
+					info.register_property(
+						array,
+						Publicity::Public,
+						PropertyKey::String("length".into()),
+						PropertyValue::Value(length),
+						false,
+						None,
+					);
+					array
+				}
+			}
+		}
+	}
+
+	let key = (path, precise);
+	if let Some((exported, info)) = checking_data.modules.json_modules.get(&key) {
+		environment.info.extend_ref(info);
+		return Some(Ok(exported.clone()));
+	}
+
+	let (path, _) = &key;
+	let File::Source(source, content) = checking_data.modules.get_file(path)? else {
+		return None;
+	};
+
+	let mut parser = JSONParser { on: &content, at: 0 };
+	let result = parser.parse_value().and_then(|value| {
+		parser.skip_whitespace_and_comments();
+		if parser.rest().is_empty() {
+			Ok(value)
+		} else {
+			Err((parser.at, "expected end of file"))
+		}
+	});
+
+	let root = match result {
+		Ok(root) => root,
+		Err((at, reason)) => {
+			#[allow(clippy::cast_possible_truncation)]
+			let at = at as u32;
+			checking_data.diagnostics_container.add_error(
+				crate::diagnostics::TypeCheckError::InvalidJSON {
+					reason: reason.to_owned(),
+					position: Span { start: at, end: at + 1, source: () }.with_source(source),
+				},
+			);
+			return Some(Err(()));
+		}
+	};
+
+	let mut info = LocalInformation::default();
+	let value = root.into_type(precise, &mut checking_data.types, &mut info);
+	let exported = Exported { default: Some(value), ..Default::default() };
+	environment.info.extend_ref(&info);
+	checking_data.modules.json_modules.insert(key, (exported.clone(), info));
+	Some(Ok(exported))
+}
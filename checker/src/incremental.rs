@@ -0,0 +1,561 @@
+//! Incremental checking. The results of synthesising modules are saved along with hashes of their
+//! content and of their (transitive) dependencies. On the next run modules where these are unchanged
+//! are restored rather than synthesised again.
+//!
+//! Types registered after the definitions which the saved modules reference are saved, with the
+//! others left as placeholders, so that types restored modules reference keep their [`TypeId`]s.
+//! Paths probed when resolving imports are hashed along with dependencies, so that creating a file
+//! which an import would now resolve to invalidates the importer
+
+use std::{
+	collections::{hash_map::DefaultHasher, HashMap, HashSet},
+	hash::{Hash, Hasher},
+	path::{Path, PathBuf},
+};
+
+use source_map::{FileSystem, MapFileStore, Nullable, SourceId, SpanWithSource, WithPathMap};
+
+use crate::{
+	diagnostics::SuppressionComment,
+	features::modules::{Exported, SynthesisedModule},
+	serialization::{checksum, record_reached, with_source_table, LongString, SourceTable},
+	types::store::TypeStoreAdditions,
+	unused::ImportedBinding,
+	BinarySerializable, CheckingData, Diagnostic, DiagnosticCode, DiagnosticKind, LocalInformation,
+	ReadFromFS, TypeCheckOptions, VariableId,
+};
+
+const INCREMENTAL_CACHE_MARKER: &[u8] = b"ezno-incremental-cache";
+/// Incremented when the layout of [`generate`] changes
+const INCREMENTAL_CACHE_VERSION: u32 = 1;
+
+/// Returned from [`crate::check_project_incremental`]
+pub struct IncrementalCache {
+	/// To be passed to the next run
+	pub bytes: Vec<u8>,
+	/// Number of modules restored from the previous run rather than synthesised
+	pub restored_modules: usize,
+}
+
+fn content_hash(content: &[u8]) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	content.hash(&mut hasher);
+	hasher.finish()
+}
+
+/// Changes to any of these invalidate all previous results
+pub(crate) fn project_key<T: ReadFromFS>(
+	type_definition_files: &[PathBuf],
+	options: &TypeCheckOptions,
+	file_reader: &T,
+) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	env!("CARGO_PKG_VERSION").hash(&mut hasher);
+	options.hash(&mut hasher);
+	for path in type_definition_files {
+		path.hash(&mut hasher);
+		if path == Path::new(crate::INTERNAL_DEFINITION_FILE_PATH) {
+			crate::INTERNAL_DEFINITION_FILE.hash(&mut hasher);
+		} else {
+			file_reader.read_file(path).hash(&mut hasher);
+		}
+	}
+	hasher.finish()
+}
+
+/// [`Diagnostic::Global`]s are not attributed to a module and so are not saved
+#[derive(binary_serialize_derive::BinarySerializable)]
+enum CachedDiagnostic {
+	Position {
+		reason: LongString,
+		position: SpanWithSource,
+		kind: DiagnosticKind,
+		code: Option<DiagnosticCode>,
+	},
+	PositionWithAdditionalLabels {
+		reason: LongString,
+		position: SpanWithSource,
+		labels: Vec<(LongString, Option<SpanWithSource>)>,
+		kind: DiagnosticKind,
+		code: Option<DiagnosticCode>,
+	},
+}
+
+impl CachedDiagnostic {
+	fn from_diagnostic(diagnostic: &Diagnostic) -> Option<Self> {
+		match diagnostic {
+			Diagnostic::Global { .. } => None,
+			Diagnostic::Position { reason, position, kind, code } => Some(Self::Position {
+				reason: LongString(reason.clone()),
+				position: *position,
+				kind: *kind,
+				code: *code,
+			}),
+			Diagnostic::PositionWithAdditionalLabels { reason, position, labels, kind, code } => {
+				Some(Self::PositionWithAdditionalLabels {
+					reason: LongString(reason.clone()),
+					position: *position,
+					labels: labels
+						.iter()
+						.map(|(label, position)| (LongString(label.clone()), *position))
+						.collect(),
+					kind: *kind,
+					code: *code,
+				})
+			}
+		}
+	}
+
+	fn into_diagnostic(self) -> Diagnostic {
+		match self {
+			Self::Position { reason, position, kind, code } => {
+				Diagnostic::Position { reason: reason.0, position, kind, code }
+			}
+			Self::PositionWithAdditionalLabels { reason, position, labels, kind, code } => {
+				Diagnostic::PositionWithAdditionalLabels {
+					reason: reason.0,
+					position,
+					labels: labels
+						.into_iter()
+						.map(|(label, position)| (label.0, position))
+						.collect(),
+					kind,
+					code,
+				}
+			}
+		}
+	}
+}
+
+/// The parts of [`crate::unused::Usage`] recorded whilst synthesising a module
+#[derive(Default, binary_serialize_derive::BinarySerializable)]
+struct ModuleUsage {
+	read_variables: Vec<VariableId>,
+	assigned_variables: Vec<VariableId>,
+	imported_variables: Vec<VariableId>,
+	used_exports: Vec<VariableId>,
+	imports: Vec<(String, Option<VariableId>, (bool, SpanWithSource))>,
+	referenced_type_names: Vec<String>,
+	declarations: Vec<(VariableId, String, SpanWithSource)>,
+	references: Vec<(SpanWithSource, VariableId)>,
+	assigned_in_inner_scopes: Vec<VariableId>,
+}
+
+#[derive(binary_serialize_derive::BinarySerializable)]
+struct CachedModule {
+	path: LongString,
+	content_hash: u64,
+	/// Direct dependencies (including JSON files). Modules are restored first
+	imports: Vec<LongString>,
+	/// Paths probed when resolving imports of this module
+	resolution_inputs: Vec<LongString>,
+	/// Transitive dependencies and resolution inputs and hashes of their content (`None` if the
+	/// file did not exist)
+	dependencies: Vec<(LongString, Option<u64>)>,
+	exported: Exported,
+	info: LocalInformation,
+	diagnostics: Vec<CachedDiagnostic>,
+	usage: ModuleUsage,
+	suppression_comments: Vec<SuppressionComment>,
+}
+
+/// Loaded from a previous run
+pub(crate) struct PreviousResults {
+	modules: HashMap<PathBuf, CachedModule>,
+	/// Sources registered when loading which have not been restored or synthesised yet
+	pub(crate) pending: HashSet<SourceId>,
+	hashes: HashMap<PathBuf, Option<u64>>,
+	validity: HashMap<PathBuf, bool>,
+	pub(crate) restored: usize,
+}
+
+impl PreviousResults {
+	fn current_hash<T: ReadFromFS>(
+		&mut self,
+		path: &Path,
+		files: &MapFileStore<WithPathMap>,
+		file_reader: &T,
+	) -> Option<u64> {
+		*self.hashes.entry(path.to_path_buf()).or_insert_with(|| {
+			if let Some(source) = files.get_source_at_path(path) {
+				Some(content_hash(files.get_file_content(source).as_bytes()))
+			} else {
+				file_reader.read_file(path).as_deref().map(content_hash)
+			}
+		})
+	}
+
+	fn is_valid<T: ReadFromFS>(
+		&mut self,
+		path: &Path,
+		files: &MapFileStore<WithPathMap>,
+		file_reader: &T,
+	) -> bool {
+		if let Some(valid) = self.validity.get(path) {
+			return *valid;
+		}
+		let Some(entry) = self.modules.get(path) else { return false };
+		let (content_hash, dependencies) = (
+			entry.content_hash,
+			entry
+				.dependencies
+				.iter()
+				.map(|(path, hash)| (PathBuf::from(&path.0), *hash))
+				.collect::<Vec<_>>(),
+		);
+
+		let valid = self.current_hash(path, files, file_reader) == Some(content_hash)
+			&& dependencies
+				.iter()
+				.all(|(path, hash)| self.current_hash(path, files, file_reader) == *hash);
+
+		self.validity.insert(path.to_path_buf(), valid);
+		valid
+	}
+}
+
+/// Returns `None` if `bytes` are not results for this project (or were created by a different version
+/// or are corrupted).
+/// Otherwise adds the saved types to the [`crate::types::TypeStore`] and registers the sources the
+/// results reference
+pub(crate) fn load<T: ReadFromFS, A: crate::ASTImplementation>(
+	bytes: &[u8],
+	key: u64,
+	checking_data: &mut CheckingData<T, A>,
+) -> Option<PreviousResults> {
+	let content = bytes.strip_prefix(INCREMENTAL_CACHE_MARKER)?;
+	let mut header = content.iter().copied();
+	let source = SourceId::NULL;
+
+	if header.len() < 3 * U32_BYTES + 2 * U64_BYTES
+		|| u32::deserialize(&mut header, source) != INCREMENTAL_CACHE_VERSION
+		|| u64::deserialize(&mut header, source) != key
+		|| u32::deserialize(&mut header, source) as usize != checking_data.types.count_of_types()
+		|| u32::deserialize(&mut header, source) as usize != header.len() - U64_BYTES
+	{
+		return None;
+	}
+
+	// A truncated or corrupted cache is treated as a miss rather than read
+	let expected_checksum = u64::deserialize(&mut header, source);
+	let rest = &content[content.len() - header.len()..];
+	if rest.len() < U32_BYTES || checksum(rest) != expected_checksum {
+		return None;
+	}
+
+	let table_start = u32::deserialize(&mut rest.iter().copied(), source) as usize;
+	let body = rest.get(U32_BYTES..table_start)?;
+
+	let mut table_bytes = rest[table_start..].iter().copied();
+	let count = u32::deserialize(&mut table_bytes, source);
+
+	let mut pending = HashSet::new();
+	let sources = (0..count)
+		.map(|_| {
+			let path = PathBuf::from(LongString::deserialize(&mut table_bytes, source).0);
+			let files = &mut checking_data.modules.files;
+			files.get_source_at_path(&path).unwrap_or_else(|| {
+				// Files which cannot be read are registered empty, so results depending on them are invalid
+				let content = checking_data
+					.modules
+					.file_reader
+					.read_file(&path)
+					.and_then(|content| String::from_utf8(content).ok())
+					.unwrap_or_default();
+				let source = files.new_source_id(path, content);
+				pending.insert(source);
+				source
+			})
+		})
+		.collect();
+
+	let ((modules, additions), _) = with_source_table(SourceTable::from_sources(sources), || {
+		let mut body = body.iter().copied();
+		let modules = Vec::<CachedModule>::deserialize(&mut body, source);
+		let additions = TypeStoreAdditions::deserialize(&mut body, source);
+		(modules, additions)
+	});
+
+	checking_data.types.extend_with(additions);
+
+	Some(PreviousResults {
+		modules: modules
+			.into_iter()
+			.map(|module| (PathBuf::from(&module.path.0), module))
+			.collect(),
+		pending,
+		hashes: HashMap::new(),
+		validity: HashMap::new(),
+		restored: 0,
+	})
+}
+
+/// Restores the module at `path` (and the modules it imports) if its content and dependencies are unchanged
+pub(crate) fn restore<T: ReadFromFS, A: crate::ASTImplementation>(
+	path: &Path,
+	checking_data: &mut CheckingData<T, A>,
+) -> Option<SourceId> {
+	let crate::ModuleData { previous, files, file_reader, .. } = &mut checking_data.modules;
+	let previous = previous.as_mut()?;
+	if !previous.is_valid(path, files, *file_reader) {
+		return None;
+	}
+	let source = files.get_source_at_path(path)?;
+	let entry = previous.modules.remove(path)?;
+	previous.pending.remove(&source);
+
+	let CachedModule {
+		imports,
+		resolution_inputs,
+		exported,
+		info,
+		diagnostics,
+		usage,
+		suppression_comments,
+		..
+	} = entry;
+
+	let imports: Vec<PathBuf> = imports.into_iter().map(|path| PathBuf::from(path.0)).collect();
+	for import in &imports {
+		let synthesised =
+			checking_data.modules.files.get_source_at_path(import).is_some_and(|source| {
+				checking_data.modules.synthesised_modules.contains_key(&source)
+			});
+
+		if !synthesised {
+			restore(import, checking_data);
+		}
+	}
+	checking_data.modules.dependencies.insert(source, imports);
+	checking_data
+		.modules
+		.resolution_inputs
+		.insert(source, resolution_inputs.into_iter().map(|path| PathBuf::from(path.0)).collect());
+
+	let content = checking_data.modules.files.get_file_content(source);
+	let module = crate::parse_module(checking_data, path, source, content).ok()?;
+
+	if let Some(ref mut previous) = checking_data.modules.previous {
+		previous.restored += 1;
+	}
+
+	let module = SynthesisedModule {
+		content: A::owned_module_from_module(module),
+		exported,
+		info,
+		mappings: Default::default(),
+	};
+	checking_data.modules.synthesised_modules.insert(source, module);
+
+	for diagnostic in diagnostics {
+		checking_data.diagnostics_container.add(diagnostic.into_diagnostic());
+	}
+	checking_data.suppression_comments.extend(suppression_comments);
+
+	let usage_data = &mut checking_data.usage;
+	usage_data.synthesised_modules.push(source);
+	usage_data.read_variables.extend(usage.read_variables.into_iter().map(|id| (source, id)));
+	usage_data
+		.assigned_variables
+		.extend(usage.assigned_variables.into_iter().map(|id| (source, id)));
+	usage_data
+		.imported_variables
+		.extend(usage.imported_variables.into_iter().map(|id| (source, id)));
+	usage_data.used_exports.extend(usage.used_exports.into_iter().map(|id| (source, id)));
+	usage_data.imports.extend(usage.imports.into_iter().map(
+		|(name, variable, (is_type, position))| ImportedBinding {
+			name,
+			variable,
+			is_type,
+			position,
+		},
+	));
+	usage_data
+		.referenced_type_names
+		.extend(usage.referenced_type_names.into_iter().map(|name| (source, name)));
+	usage_data.declarations.extend(usage.declarations);
+	usage_data.references.extend(usage.references);
+	usage_data.assigned_in_inner_scopes.extend(usage.assigned_in_inner_scopes);
+
+	Some(source)
+}
+
+/// Saves the results of synthesised (and restored) modules. Modules are skipped if their
+/// dependencies have diagnostics that cannot be attributed to a module (such as parse errors)
+pub(crate) fn generate<T: ReadFromFS, A: crate::ASTImplementation>(
+	key: u64,
+	definitions_type_count: usize,
+	checking_data: &CheckingData<T, A>,
+) -> Vec<u8> {
+	let modules = &checking_data.modules;
+	let files = &modules.files;
+
+	let diagnostics_by_source = {
+		let mut map = HashMap::<SourceId, Vec<&Diagnostic>>::new();
+		for diagnostic in checking_data.diagnostics_container.iter() {
+			if let Some(source) = diagnostic.sources().next() {
+				map.entry(source).or_default().push(diagnostic);
+			}
+		}
+		map
+	};
+
+	let unattributed: HashSet<SourceId> = diagnostics_by_source
+		.keys()
+		.copied()
+		.filter(|source| !modules.synthesised_modules.contains_key(source))
+		.collect();
+
+	let hash_of = |path: &Path| match files.get_source_at_path(path) {
+		Some(source) => Some(content_hash(files.get_file_content(source).as_bytes())),
+		None => modules.file_reader.read_file(path).as_deref().map(content_hash),
+	};
+
+	let mut cached_modules = Vec::new();
+
+	'modules: for source in &checking_data.usage.synthesised_modules {
+		let Some(module) = modules.synthesised_modules.get(source) else { continue };
+		let path = files.get_file_path(*source);
+		let imports = modules.dependencies.get(source).cloned().unwrap_or_default();
+
+		let resolution_inputs = modules.resolution_inputs.get(source).cloned().unwrap_or_default();
+
+		let mut dependencies = Vec::new();
+		let mut seen = HashSet::from([path.clone()]);
+		// Resolution inputs can be missing. Changes to those of dependencies can change their exports
+		let mut probed: Vec<PathBuf> = resolution_inputs.clone();
+		let mut stack = imports.clone();
+		while let Some(dependency) = stack.pop() {
+			if !seen.insert(dependency.clone()) {
+				continue;
+			}
+			let dependency_source = files.get_source_at_path(&dependency);
+			if dependency_source.is_some_and(|source| unattributed.contains(&source)) {
+				continue 'modules;
+			}
+			let Some(hash) = hash_of(&dependency) else { continue 'modules };
+			if let Some(source) = dependency_source {
+				stack.extend(modules.dependencies.get(&source).into_iter().flatten().cloned());
+				probed
+					.extend(modules.resolution_inputs.get(&source).into_iter().flatten().cloned());
+			}
+			dependencies.push((LongString::from(dependency.as_path()), Some(hash)));
+		}
+		for path in probed {
+			if seen.insert(path.clone()) {
+				let hash = hash_of(&path);
+				dependencies.push((LongString::from(path.as_path()), hash));
+			}
+		}
+
+		let usage = &checking_data.usage;
+		let usage = ModuleUsage {
+			read_variables: usage
+				.read_variables
+				.iter()
+				.filter_map(|(from, id)| (from == source).then_some(*id))
+				.collect(),
+			assigned_variables: usage
+				.assigned_variables
+				.iter()
+				.filter_map(|(from, id)| (from == source).then_some(*id))
+				.collect(),
+			imported_variables: usage
+				.imported_variables
+				.iter()
+				.filter_map(|(from, id)| (from == source).then_some(*id))
+				.collect(),
+			used_exports: usage
+				.used_exports
+				.iter()
+				.filter_map(|(from, id)| (from == source).then_some(*id))
+				.collect(),
+			imports: usage
+				.imports
+				.iter()
+				.filter(|import| import.position.source == *source)
+				.map(|import| {
+					(import.name.clone(), import.variable, (import.is_type, import.position))
+				})
+				.collect(),
+			referenced_type_names: usage
+				.referenced_type_names
+				.iter()
+				.filter(|(from, _)| from == source)
+				.map(|(_, name)| name.clone())
+				.collect(),
+			declarations: usage
+				.declarations
+				.iter()
+				.filter(|(id, ..)| id.0 == *source)
+				.cloned()
+				.collect(),
+			references: usage
+				.references
+				.iter()
+				.filter(|(position, _)| position.source == *source)
+				.map(|(position, id)| (*position, *id))
+				.collect(),
+			assigned_in_inner_scopes: usage
+				.assigned_in_inner_scopes
+				.iter()
+				.filter(|id| id.0 == *source)
+				.copied()
+				.collect(),
+		};
+
+		cached_modules.push(CachedModule {
+			path: LongString::from(path.as_path()),
+			content_hash: content_hash(files.get_file_content(*source).as_bytes()),
+			imports: imports.iter().map(|import| LongString::from(import.as_path())).collect(),
+			resolution_inputs: resolution_inputs
+				.iter()
+				.map(|path| LongString::from(path.as_path()))
+				.collect(),
+			dependencies,
+			exported: module.exported.clone(),
+			info: module.info.clone(),
+			diagnostics: diagnostics_by_source
+				.get(source)
+				.into_iter()
+				.flatten()
+				.filter_map(|diagnostic| CachedDiagnostic::from_diagnostic(diagnostic))
+				.collect(),
+			usage,
+			suppression_comments: checking_data
+				.suppression_comments
+				.iter()
+				.filter(|comment| comment.position.source == *source)
+				.cloned()
+				.collect(),
+		});
+	}
+
+	let (body, table) = with_source_table(SourceTable::default(), || {
+		let mut body = Vec::new();
+		let ((), reached) = record_reached(|| cached_modules.serialize(&mut body));
+		checking_data.types.additions_reached(definitions_type_count, reached).serialize(&mut body);
+		body
+	});
+
+	// The paths of the sources referenced in the body follow it
+	let mut rest = Vec::new();
+	u32::try_from(U32_BYTES + body.len()).unwrap().serialize(&mut rest);
+	rest.extend(body);
+	u32::try_from(table.sources.len()).unwrap().serialize(&mut rest);
+	for source in table.sources {
+		LongString::from(files.get_file_path(source).as_path()).serialize(&mut rest);
+	}
+
+	let mut buf = INCREMENTAL_CACHE_MARKER.to_vec();
+	INCREMENTAL_CACHE_VERSION.serialize(&mut buf);
+	key.serialize(&mut buf);
+	u32::try_from(definitions_type_count).unwrap().serialize(&mut buf);
+	u32::try_from(rest.len()).unwrap().serialize(&mut buf);
+	checksum(&rest).serialize(&mut buf);
+	buf.extend(rest);
+	buf
+}
+
+const U32_BYTES: usize = (u32::BITS / u8::BITS) as usize;
+const U64_BYTES: usize = (u64::BITS / u8::BITS) as usize;
@@ -5,11 +5,13 @@ pub mod context;
 pub mod diagnostics;
 pub mod events;
 pub mod features;
+mod incremental;
 mod options;
 pub mod range_map;
 mod serialization;
 mod type_mappings;
 pub mod types;
+mod unused;
 mod utilities;
 
 pub const INTERNAL_DEFINITION_FILE_PATH: &str = "internal.ts.d.bin";
@@ -35,12 +37,14 @@ use std::{
 use types::TypeStore;
 
 pub use context::{GeneralContext, Logical, RootContext};
-pub use diagnostics::{Diagnostic, DiagnosticKind, DiagnosticsContainer};
+pub use diagnostics::{Diagnostic, DiagnosticCode, DiagnosticKind, DiagnosticsContainer};
+pub use incremental::IncrementalCache;
 pub use options::TypeCheckOptions;
 pub use types::{calling::call_type_handle_errors, generics::GenericTypeParameters, subtyping};
 
 pub use type_mappings::*;
 pub use types::{properties::PropertyValue, Constant, Type, TypeId};
+pub use unused::VariableUsage;
 
 pub use context::{information::LocalInformation, Environment, Scope};
 
@@ -64,7 +68,7 @@ pub use source_map::{self, SourceId, Span};
 
 pub trait ASTImplementation: Sized {
 	type ParseOptions;
-	/// Custom allocator etc
+	/// Custom allocator, base parse options etc
 	type ParserRequirements;
 
 	type ParseError: Into<Diagnostic>;
@@ -150,7 +154,12 @@ pub trait ASTImplementation: Sized {
 
 	fn parameter_constrained<'a>(parameter: &'a Self::TypeParameter<'a>) -> bool;
 
-	fn parse_options(is_js: bool, parse_comments: bool, lsp_mode: bool) -> Self::ParseOptions;
+	fn parse_options(
+		is_js: bool,
+		parse_comments: bool,
+		lsp_mode: bool,
+		parser_requirements: &Self::ParserRequirements,
+	) -> Self::ParseOptions;
 
 	fn owned_module_from_module(m: Self::Module<'static>) -> Self::OwnedModule;
 
@@ -178,6 +187,14 @@ pub struct ModuleData<'a, FileReader, AST: ASTImplementation> {
 	pub(crate) _currently_checking_modules: HashSet<PathBuf>,
 	/// The result of checking. Includes exported variables and info
 	pub(crate) synthesised_modules: HashMap<SourceId, SynthesisedModule<AST::OwnedModule>>,
+	pub(crate) json_modules: features::modules::JSONModules,
+	/// Direct dependencies of modules (including JSON files). For incremental checking
+	pub(crate) dependencies: HashMap<SourceId, Vec<PathBuf>>,
+	/// Paths read or probed when resolving the imports of modules (such as `./a.ts` before `./a.js`
+	/// or a `package.json`). Creating or changing one of these can change what an import resolves to
+	pub(crate) resolution_inputs: HashMap<SourceId, Vec<PathBuf>>,
+	/// From the previous run when checking incrementally
+	pub(crate) previous: Option<incremental::PreviousResults>,
 }
 
 impl<'a, T, A> ModuleData<'a, T, A>
@@ -194,6 +211,10 @@ where
 		Self {
 			files: files.unwrap_or_default(),
 			synthesised_modules: Default::default(),
+			json_modules: Default::default(),
+			dependencies: Default::default(),
+			resolution_inputs: Default::default(),
+			previous: None,
 			_currently_checking_modules: Default::default(),
 			// custom_module_resolvers,
 			file_reader: file_resolver,
@@ -243,7 +264,7 @@ pub enum File {
 pub struct VariableId(pub SourceId, pub u32);
 
 /// TODO split for annotations based functions
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, binary_serialize_derive::BinarySerializable)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct FunctionId(pub SourceId, pub u32);
 
 impl FunctionId {
@@ -287,6 +308,12 @@ pub struct CheckingData<'a, FSResolver, ModuleAST: ASTImplementation> {
 
 	/// Do not repeat emitting unimplemented parts
 	unimplemented_items: HashSet<&'static str>,
+
+	/// For finding unused imports, declarations and exports
+	pub(crate) usage: unused::Usage,
+
+	/// Applied after checking. From `// @ezno-ignore` and `// @ezno-expect-error`
+	pub(crate) suppression_comments: Vec<diagnostics::SuppressionComment>,
 }
 
 impl<'a, T, A> CheckingData<'a, T, A>
@@ -312,6 +339,8 @@ where
 			modules,
 			types: Default::default(),
 			unimplemented_items: Default::default(),
+			usage: Default::default(),
+			suppression_comments: Default::default(),
 		}
 	}
 
@@ -400,6 +429,23 @@ pub struct CheckOutput<A: crate::ASTImplementation> {
 	pub modules: HashMap<SourceId, SynthesisedModule<A::OwnedModule>>,
 	pub diagnostics: crate::DiagnosticsContainer,
 	pub top_level_information: crate::LocalInformation,
+	/// In the order they were checked. All entry points share one [`RootContext`]
+	pub entry_points: Vec<EntryPointStatistics>,
+	pub variable_usage: VariableUsage,
+	/// Files modules import (including JSON files) and paths probed when resolving imports, which
+	/// may not exist. Changes to these can change the result
+	pub imported_paths: Vec<PathBuf>,
+}
+
+/// Modules and diagnostics are counted against the first entry point that reaches them
+#[derive(Debug)]
+pub struct EntryPointStatistics {
+	pub path: PathBuf,
+	/// Includes modules restored from an incremental cache
+	pub modules: usize,
+	pub diagnostics: usize,
+	/// `None` on platforms without a clock
+	pub duration: Option<std::time::Duration>,
 }
 
 impl<A: crate::ASTImplementation> CheckOutput<A> {
@@ -425,15 +471,64 @@ impl<A: crate::ASTImplementation> CheckOutput<A> {
 	}
 }
 
-#[allow(clippy::needless_pass_by_value)]
 pub fn check_project<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 	entry_points: Vec<PathBuf>,
-	type_definition_files: HashSet<PathBuf>,
+	type_definition_files: Vec<PathBuf>,
 	resolver: T,
 	options: TypeCheckOptions,
 	parser_requirements: A::ParserRequirements,
 	existing_files: Option<MapFileStore<WithPathMap>>,
 ) -> CheckOutput<A> {
+	check_project_inner(
+		entry_points,
+		type_definition_files,
+		resolver,
+		options,
+		parser_requirements,
+		existing_files,
+		None,
+	)
+	.0
+}
+
+/// Same as [`check_project`], but modules (and their dependencies) which have not changed since the run
+/// that created `previous_cache` are restored rather than synthesised. The returned cache should be passed
+/// to the next run
+pub fn check_project_incremental<T: crate::ReadFromFS, A: crate::ASTImplementation>(
+	entry_points: Vec<PathBuf>,
+	type_definition_files: Vec<PathBuf>,
+	resolver: T,
+	options: TypeCheckOptions,
+	parser_requirements: A::ParserRequirements,
+	previous_cache: Option<&[u8]>,
+) -> (CheckOutput<A>, IncrementalCache) {
+	let (output, cache) = check_project_inner(
+		entry_points,
+		type_definition_files,
+		resolver,
+		options,
+		parser_requirements,
+		None,
+		Some(previous_cache),
+	);
+	(output, cache.expect("incremental cache"))
+}
+
+/// `incremental` is `Some` (with the previous results if they exist) when checking incrementally
+#[allow(clippy::needless_pass_by_value, clippy::option_option)]
+fn check_project_inner<T: crate::ReadFromFS, A: crate::ASTImplementation>(
+	entry_points: Vec<PathBuf>,
+	type_definition_files: Vec<PathBuf>,
+	resolver: T,
+	options: TypeCheckOptions,
+	parser_requirements: A::ParserRequirements,
+	existing_files: Option<MapFileStore<WithPathMap>>,
+	incremental: Option<Option<&[u8]>>,
+) -> (CheckOutput<A>, Option<IncrementalCache>) {
+	let key = incremental
+		.is_some()
+		.then(|| incremental::project_key(&type_definition_files, &options, &resolver));
+
 	let mut checking_data =
 		CheckingData::<T, A>::new(options, &resolver, existing_files, parser_requirements);
 
@@ -443,55 +538,135 @@ pub fn check_project<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 	add_definition_files_to_root(type_definition_files, &mut root, &mut checking_data);
 
 	if checking_data.diagnostics_container.has_error() {
-		return CheckOutput {
+		let output = CheckOutput {
 			types: checking_data.types,
 			module_contents: checking_data.modules.files,
 			modules: Default::default(),
 			diagnostics: checking_data.diagnostics_container,
 			top_level_information: Default::default(),
+			entry_points: Default::default(),
+			variable_usage: Default::default(),
+			imported_paths: Default::default(),
 		};
+		let cache = key.map(|_| IncrementalCache { bytes: Vec::new(), restored_modules: 0 });
+		return (output, cache);
 	}
 
 	crate::utilities::notify!("--- Finished definition file ---");
 
-	for point in &entry_points {
-		let entry_content = checking_data.modules.file_reader.read_file(point);
+	let definitions_type_count = checking_data.types.count_of_types();
+	if let (Some(key), Some(Some(bytes))) = (key, incremental) {
+		checking_data.modules.previous = incremental::load(bytes, key, &mut checking_data);
+	}
 
-		if let Some(content) = entry_content {
-			let (source, module) =
-				get_source(&mut checking_data, point, String::from_utf8(content).unwrap());
+	let mut entry_sources = HashSet::new();
 
-			match module {
-				Ok(module) => {
-					root.new_module_context(source, module, &mut checking_data);
-				}
-				Err(err) => {
-					checking_data.diagnostics_container.add_error(err);
-				}
-			}
-		} else {
-			checking_data.diagnostics_container.add_error(TypeCheckError::CannotOpenFile {
-				file: CouldNotOpenFile(point.clone()),
-				position: None,
-			});
+	let mut entry_point_statistics = Vec::with_capacity(entry_points.len());
+
+	for point in entry_points {
+		#[cfg(not(target_family = "wasm"))]
+		let start = std::time::Instant::now();
+		let modules_before = checking_data.modules.synthesised_modules.len();
+		let diagnostics_before = checking_data.diagnostics_container.iter().count();
+
+		if let Some(source) = check_entry_point(&point, &mut root, &mut checking_data) {
+			entry_sources.insert(source);
 		}
+
+		entry_point_statistics.push(EntryPointStatistics {
+			path: point,
+			modules: checking_data.modules.synthesised_modules.len() - modules_before,
+			diagnostics: checking_data.diagnostics_container.iter().count() - diagnostics_before,
+			#[cfg(not(target_family = "wasm"))]
+			duration: Some(start.elapsed()),
+			#[cfg(target_family = "wasm")]
+			duration: None,
+		});
 	}
 
+	let cache = key.map(|key| IncrementalCache {
+		bytes: incremental::generate(key, definitions_type_count, &checking_data),
+		restored_modules: checking_data.modules.previous.as_ref().map_or(0, |p| p.restored),
+	});
+
 	let CheckingData {
-		diagnostics_container,
+		mut diagnostics_container,
 		local_type_mappings: _,
 		modules,
-		options: _,
+		options,
 		types,
 		unimplemented_items: _,
+		usage,
+		suppression_comments,
 	} = checking_data;
 
-	CheckOutput {
+	if options.report_unused {
+		usage.report(&modules.synthesised_modules, &entry_sources, &mut diagnostics_container);
+	}
+
+	diagnostics_container.apply_suppression_comments(&suppression_comments, &modules.files);
+
+	let imported_paths = {
+		let paths =
+			modules.dependencies.into_values().chain(modules.resolution_inputs.into_values());
+		let unique: HashSet<PathBuf> = paths.flatten().collect();
+		unique.into_iter().collect()
+	};
+
+	let output = CheckOutput {
 		types,
 		module_contents: modules.files,
 		modules: modules.synthesised_modules,
 		diagnostics: diagnostics_container,
 		top_level_information: root.info,
+		entry_points: entry_point_statistics,
+		variable_usage: usage.get_variable_usage(),
+		imported_paths,
+	};
+	(output, cache)
+}
+
+/// Returns the source if the entry point could be read
+fn check_entry_point<T: crate::ReadFromFS, A: crate::ASTImplementation>(
+	point: &Path,
+	root: &mut RootContext,
+	checking_data: &mut CheckingData<T, A>,
+) -> Option<SourceId> {
+	// May have been imported by an earlier entry point
+	let existing = checking_data
+		.modules
+		.files
+		.get_source_at_path(point)
+		.filter(|source| checking_data.modules.synthesised_modules.contains_key(source));
+	if let Some(source) = existing {
+		return Some(source);
+	}
+
+	if let Some(source) = incremental::restore(point, checking_data) {
+		return Some(source);
+	}
+
+	let entry_content = checking_data.modules.file_reader.read_file(point);
+
+	if let Some(content) = entry_content {
+		let (source, module) =
+			get_source(checking_data, point, String::from_utf8(content).unwrap());
+
+		match module {
+			Ok(module) => {
+				root.new_module_context(source, module, checking_data);
+			}
+			Err(err) => {
+				checking_data.diagnostics_container.add_error(err);
+			}
+		}
+		Some(source)
+	} else {
+		checking_data.diagnostics_container.add_error(TypeCheckError::CannotOpenFile {
+			file: CouldNotOpenFile(point.to_path_buf()),
+			position: None,
+		});
+		None
 	}
 }
 
@@ -503,8 +678,27 @@ fn get_source<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 	SourceId,
 	Result<<A as ASTImplementation>::Module<'static>, <A as ASTImplementation>::ParseError>,
 ) {
-	let source = checking_data.modules.files.new_source_id(path.to_path_buf(), content.clone());
+	let files = &mut checking_data.modules.files;
+	// May have been registered when loading previous results
+	let source = if let Some(existing) = files.get_source_at_path(path) {
+		files.update_file(existing, content.clone());
+		if let Some(ref mut previous) = checking_data.modules.previous {
+			previous.pending.remove(&existing);
+		}
+		existing
+	} else {
+		files.new_source_id(path.to_path_buf(), content.clone())
+	};
+
+	(source, parse_module(checking_data, path, source, content))
+}
 
+pub(crate) fn parse_module<T: crate::ReadFromFS, A: crate::ASTImplementation>(
+	checking_data: &mut CheckingData<T, A>,
+	path: &Path,
+	source: SourceId,
+	content: String,
+) -> Result<<A as ASTImplementation>::Module<'static>, <A as ASTImplementation>::ParseError> {
 	// TODO abstract using similar to import logic
 	let is_js = path.extension().and_then(|s| s.to_str()).map_or(false, |s| s.ends_with("js"));
 
@@ -512,16 +706,15 @@ fn get_source<T: crate::ReadFromFS, A: crate::ASTImplementation>(
 		is_js,
 		checking_data.options.parse_comments,
 		checking_data.options.lsp_mode,
+		&checking_data.modules.parser_requirements,
 	);
 
-	let module = A::module_from_string(
+	A::module_from_string(
 		source,
 		content,
 		parse_options,
 		&mut checking_data.modules.parser_requirements,
-	);
-
-	(source, module)
+	)
 }
 
 const CACHE_MARKER: &[u8] = b"ezno-cache-file";
@@ -536,13 +729,14 @@ pub(crate) struct Cache {
 	// pub(crate) content: String,
 }
 
+/// Definition files are merged into the root in order, so later files can merge into interfaces
+/// and add to the globals of earlier ones. A cache can only be the first file
 pub(crate) fn add_definition_files_to_root<T: crate::ReadFromFS, A: crate::ASTImplementation>(
-	type_definition_files: HashSet<PathBuf>,
+	type_definition_files: Vec<PathBuf>,
 	root: &mut RootContext,
 	checking_data: &mut CheckingData<T, A>,
 ) {
-	let length = type_definition_files.len();
-	for path in type_definition_files {
+	for (idx, path) in type_definition_files.into_iter().enumerate() {
 		let file = if path == PathBuf::from(crate::INTERNAL_DEFINITION_FILE_PATH) {
 			File::Binary(crate::INTERNAL_DEFINITION_FILE.to_owned())
 		} else if let Some(file) = checking_data.modules.get_file(&path) {
@@ -551,6 +745,7 @@ pub(crate) fn add_definition_files_to_root<T: crate::ReadFromFS, A: crate::ASTIm
 			checking_data.diagnostics_container.add_error(Diagnostic::Global {
 				reason: format!("could not find {}", path.display()),
 				kind: crate::DiagnosticKind::Error,
+				code: None,
 			});
 			continue;
 		};
@@ -558,8 +753,43 @@ pub(crate) fn add_definition_files_to_root<T: crate::ReadFromFS, A: crate::ASTIm
 		match file {
 			File::Binary(mut content) => {
 				crate::utilities::notify!("Using cache :)");
-				assert_eq!(length, 1, "only a single cache is current supported");
+				if idx != 0 {
+					checking_data.diagnostics_container.add_error(Diagnostic::Global {
+						reason: format!(
+							"cache {} must be the first definition file",
+							path.display()
+						),
+						kind: crate::DiagnosticKind::Error,
+						code: None,
+					});
+					continue;
+				}
 
+				if content.starts_with(context::CONTEXT_FILE_HEADER) {
+					match RootContext::deserialize(&content, &mut checking_data.modules.files) {
+						Ok((restored, types)) => {
+							*root = restored;
+							checking_data.types = types;
+						}
+						Err(reason) => {
+							checking_data.diagnostics_container.add_error(Diagnostic::Global {
+								reason: format!("{}: {reason}", path.display()),
+								kind: crate::DiagnosticKind::Error,
+								code: None,
+							});
+						}
+					}
+					continue;
+				} else if !content.starts_with(CACHE_MARKER) {
+					checking_data.diagnostics_container.add_error(Diagnostic::Global {
+						reason: format!("{} is not a definition cache", path.display()),
+						kind: crate::DiagnosticKind::Error,
+						code: None,
+					});
+					continue;
+				}
+
+				// Single file layout, used by the bundled internal definitions
 				let vec = content[CACHE_MARKER.len()..(CACHE_MARKER.len() + U32_BYTES as usize)]
 					.to_owned();
 
@@ -624,51 +854,24 @@ pub(crate) fn add_definition_files_to_root<T: crate::ReadFromFS, A: crate::ASTIm
 
 const U32_BYTES: u32 = u32::BITS / u8::BITS;
 
+/// Synthesises the definition files `on` (merged in order, see [`add_definition_files_to_root`]) and
+/// serializes the resulting root. The output can be passed as the first definition file to skip
+/// synthesising them again. Returns the diagnostics if the files have errors
 pub fn generate_cache<T: crate::ReadFromFS, A: crate::ASTImplementation>(
-	on: &Path,
+	on: &[PathBuf],
 	read: &T,
 	parser_requirements: A::ParserRequirements,
-) -> Vec<u8> {
+) -> Result<Vec<u8>, (DiagnosticsContainer, MapFileStore<WithPathMap>)> {
 	let mut checking_data =
 		CheckingData::<T, A>::new(Default::default(), read, None, parser_requirements);
 
 	let mut root = crate::context::RootContext::new_with_primitive_references();
 
-	add_definition_files_to_root(
-		HashSet::from_iter([on.to_path_buf()]),
-		&mut root,
-		&mut checking_data,
-	);
+	add_definition_files_to_root(on.to_vec(), &mut root, &mut checking_data);
 
-	assert!(
-		!checking_data.diagnostics_container.has_error(),
-		"found error in definition file {:#?}",
-		checking_data.diagnostics_container.get_diagnostics()
-	);
-
-	let mut buf = CACHE_MARKER.to_vec();
-
-	buf.extend_from_slice(&0_u32.to_le_bytes());
-
-	let cache = Cache {
-		variables: root.variables,
-		named_types: root.named_types,
-		info: root.info,
-		types: checking_data.types,
-	};
-	cache.serialize(&mut buf);
-
-	let cache_len: usize = buf.len() - CACHE_MARKER.len() - U32_BYTES as usize;
-	// Set length
-	buf[CACHE_MARKER.len()..(CACHE_MARKER.len() + U32_BYTES as usize)]
-		.copy_from_slice(&(cache_len as u32).to_le_bytes());
-
-	// TODO not great
-	let Some(File::Source(source, content)) = checking_data.modules.get_file(on) else { panic!() };
-
-	let path = on.to_str().unwrap().to_owned();
-	(source, path).serialize(&mut buf);
-	buf.extend_from_slice(content.as_bytes());
+	if checking_data.diagnostics_container.has_error() {
+		return Err((checking_data.diagnostics_container, checking_data.modules.files));
+	}
 
-	buf
+	Ok(root.serialize(checking_data.types, &checking_data.modules.files))
 }
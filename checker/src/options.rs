@@ -3,6 +3,7 @@
 #[cfg_attr(feature = "serde-serialize", derive(serde::Deserialize), serde(default))]
 #[cfg_attr(target_family = "wasm", derive(tsify::Tsify))]
 #[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Hash)]
 pub struct TypeCheckOptions {
 	/// Parameters cannot be reassigned
 	pub constant_parameters: bool,
@@ -34,6 +35,9 @@ pub struct TypeCheckOptions {
 
 	/// Allows partial syntax and collects other information for using in editor
 	pub lsp_mode: bool,
+
+	/// Warn on unused imports, unused declarations and exports that no checked module imports
+	pub report_unused: bool,
 }
 
 impl Default for TypeCheckOptions {
@@ -48,6 +52,7 @@ impl Default for TypeCheckOptions {
 			strict_casts: false,
 			store_expression_type_mappings: false,
 			lsp_mode: false,
+			report_unused: false,
 			// TODO false at some point hopefully!
 			allow_cast: true,
 		}
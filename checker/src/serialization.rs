@@ -3,11 +3,106 @@
 //! The trait and code currently exists here as there may be some context related things.
 //! May become a separate crate at some point
 
-use std::collections::{HashMap, HashSet};
+use std::{
+	cell::RefCell,
+	collections::{HashMap, HashSet},
+};
 
-use source_map::{SourceId, SpanWithSource};
+use source_map::{Nullable, SourceId, SpanWithSource};
 
-use crate::TypeId;
+use crate::{FunctionId, TypeId};
+
+thread_local! {
+	static SOURCE_TABLE: RefCell<Option<SourceTable>> = const { RefCell::new(None) };
+	static REACHED: RefCell<Option<Reached>> = const { RefCell::new(None) };
+}
+
+/// Maps [`SourceId`]s to indexes, so data spanning multiple sources can be saved and loaded
+#[derive(Default)]
+pub(crate) struct SourceTable {
+	pub sources: Vec<SourceId>,
+	indexes: HashMap<SourceId, u16>,
+}
+
+impl SourceTable {
+	pub(crate) fn from_sources(sources: Vec<SourceId>) -> Self {
+		#[allow(clippy::cast_possible_truncation)]
+		let indexes = sources.iter().enumerate().map(|(idx, source)| (*source, idx as u16)).collect();
+		Self { sources, indexes }
+	}
+
+	const NULL_INDEX: u16 = u16::MAX;
+
+	fn index_of(&mut self, source: SourceId) -> u16 {
+		if source == SourceId::NULL {
+			return Self::NULL_INDEX;
+		}
+		*self.indexes.entry(source).or_insert_with(|| {
+			self.sources.push(source);
+			u16::try_from(self.sources.len() - 1).expect("too many sources")
+		})
+	}
+
+	fn get(&self, index: u16) -> SourceId {
+		if index == Self::NULL_INDEX {
+			SourceId::NULL
+		} else {
+			self.sources[index as usize]
+		}
+	}
+}
+
+/// Whilst `cb` runs, [`SourceId`]s (including those in [`SpanWithSource`]s) are written as indexes
+/// into `table` rather than being skipped and replaced by the backing source when deserializing.
+/// Sources not in the table are appended to it when serializing
+pub(crate) fn with_source_table<R>(table: SourceTable, cb: impl FnOnce() -> R) -> (R, SourceTable) {
+	SOURCE_TABLE.with(|current| *current.borrow_mut() = Some(table));
+	let result = cb();
+	let table = SOURCE_TABLE.with(|current| current.borrow_mut().take()).unwrap();
+	(result, table)
+}
+
+fn serialize_source(source: SourceId, buf: &mut Vec<u8>) {
+	SOURCE_TABLE.with(|table| {
+		if let Some(table) = table.borrow_mut().as_mut() {
+			buf.extend_from_slice(&table.index_of(source).to_le_bytes());
+		}
+	});
+}
+
+fn deserialize_source<I: Iterator<Item = u8>>(iter: &mut I, backing_source: SourceId) -> SourceId {
+	SOURCE_TABLE.with(|table| {
+		if let Some(table) = table.borrow().as_ref() {
+			table.get(u16::from_le_bytes([iter.next().unwrap(), iter.next().unwrap()]))
+		} else {
+			backing_source
+		}
+	})
+}
+
+/// [`TypeId`]s and [`FunctionId`]s written whilst recording
+#[derive(Default)]
+pub(crate) struct Reached {
+	pub types: HashSet<TypeId>,
+	pub functions: HashSet<FunctionId>,
+}
+
+/// Records the [`TypeId`]s and [`FunctionId`]s serialized whilst `cb` runs. Used to find the types
+/// that some data references
+pub(crate) fn record_reached<R>(cb: impl FnOnce() -> R) -> (R, Reached) {
+	REACHED.with(|current| *current.borrow_mut() = Some(Reached::default()));
+	let result = cb();
+	let reached = REACHED.with(|current| current.borrow_mut().take()).unwrap();
+	(result, reached)
+}
+
+/// FNV-1a. Deserializing assumes well formed input, so corrupted bodies are rejected before
+/// they are read
+pub(crate) fn checksum(bytes: &[u8]) -> u64 {
+	bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+		(hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+	})
+}
 
 /// TODO unsure about iterator
 /// This is automated by the derive macro TODO link
@@ -26,7 +121,28 @@ impl BinarySerializable for String {
 
 	fn deserialize<I: Iterator<Item = u8>>(iter: &mut I, _source: SourceId) -> Self {
 		let len = iter.next().unwrap();
-		iter.by_ref().take(len as usize).map(|v| v as char).collect::<String>()
+		String::from_utf8(iter.by_ref().take(len as usize).collect()).unwrap()
+	}
+}
+
+/// [`String`]s are serialized with a `u8` length, which is not enough for paths and file contents
+pub(crate) struct LongString(pub String);
+
+impl BinarySerializable for LongString {
+	fn serialize(self, buf: &mut Vec<u8>) {
+		buf.extend_from_slice(&u32::try_from(self.0.len()).unwrap().to_le_bytes());
+		buf.extend_from_slice(self.0.as_bytes());
+	}
+
+	fn deserialize<I: Iterator<Item = u8>>(iter: &mut I, source: SourceId) -> Self {
+		let len = u32::deserialize(iter, source);
+		Self(String::from_utf8(iter.take(len as usize).collect()).unwrap())
+	}
+}
+
+impl From<&std::path::Path> for LongString {
+	fn from(path: &std::path::Path) -> Self {
+		Self(path.display().to_string())
 	}
 }
 
@@ -197,23 +313,36 @@ impl BinarySerializable for SpanWithSource {
 	fn serialize(self, buf: &mut Vec<u8>) {
 		self.start.serialize(buf);
 		self.end.serialize(buf);
+		serialize_source(self.source, buf);
 	}
 
 	fn deserialize<I: Iterator<Item = u8>>(iter: &mut I, source: SourceId) -> Self {
 		SpanWithSource {
 			start: u32::deserialize(iter, source),
 			end: u32::deserialize(iter, source),
-			source,
+			source: deserialize_source(iter, source),
 		}
 	}
 }
 
-// TODO fix for external references
+/// Only written when in [`with_source_table`]
 impl BinarySerializable for SourceId {
-	fn serialize(self, _buf: &mut Vec<u8>) {}
+	fn serialize(self, buf: &mut Vec<u8>) {
+		serialize_source(self, buf);
+	}
 
-	fn deserialize<I: Iterator<Item = u8>>(_iter: &mut I, source: SourceId) -> Self {
-		source
+	fn deserialize<I: Iterator<Item = u8>>(iter: &mut I, source: SourceId) -> Self {
+		deserialize_source(iter, source)
+	}
+}
+
+impl BinarySerializable for u16 {
+	fn serialize(self, buf: &mut Vec<u8>) {
+		buf.extend_from_slice(&self.to_le_bytes());
+	}
+
+	fn deserialize<I: Iterator<Item = u8>>(iter: &mut I, _source: SourceId) -> Self {
+		u16::from_le_bytes([iter.next().unwrap(), iter.next().unwrap()])
 	}
 }
 
@@ -232,8 +361,24 @@ impl BinarySerializable for u32 {
 	}
 }
 
+impl BinarySerializable for u64 {
+	fn serialize(self, buf: &mut Vec<u8>) {
+		buf.extend_from_slice(&self.to_le_bytes());
+	}
+
+	fn deserialize<I: Iterator<Item = u8>>(iter: &mut I, _source: SourceId) -> Self {
+		let bytes = [(); 8].map(|()| iter.next().unwrap());
+		u64::from_le_bytes(bytes)
+	}
+}
+
 impl BinarySerializable for TypeId {
 	fn serialize(self, buf: &mut Vec<u8>) {
+		REACHED.with(|reached| {
+			if let Some(reached) = reached.borrow_mut().as_mut() {
+				reached.types.insert(self);
+			}
+		});
 		buf.extend_from_slice(&self.0.to_le_bytes());
 	}
 
@@ -242,6 +387,22 @@ impl BinarySerializable for TypeId {
 	}
 }
 
+impl BinarySerializable for FunctionId {
+	fn serialize(self, buf: &mut Vec<u8>) {
+		REACHED.with(|reached| {
+			if let Some(reached) = reached.borrow_mut().as_mut() {
+				reached.functions.insert(self);
+			}
+		});
+		self.0.serialize(buf);
+		self.1.serialize(buf);
+	}
+
+	fn deserialize<I: Iterator<Item = u8>>(iter: &mut I, source: SourceId) -> Self {
+		Self(SourceId::deserialize(iter, source), u32::deserialize(iter, source))
+	}
+}
+
 impl BinarySerializable for ordered_float::NotNan<f64> {
 	fn serialize(self, buf: &mut Vec<u8>) {
 		buf.extend_from_slice(&self.into_inner().to_le_bytes());
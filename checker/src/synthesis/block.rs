@@ -1,6 +1,11 @@
 use parser::{ASTNode, Statement, StatementOrDeclaration};
+use source_map::SpanWithSource;
 
-use crate::{context::Environment, diagnostics::TypeCheckWarning, CheckingData};
+use crate::{
+	context::Environment,
+	diagnostics::{SuppressionComment, TypeCheckWarning},
+	CheckingData,
+};
 
 use super::{
 	declarations::synthesise_declaration, hoisting::hoist_statements,
@@ -15,6 +20,11 @@ pub(super) fn synthesise_block<T: crate::ReadFromFS>(
 ) {
 	hoist_statements(statements, environment, checking_data);
 
+	// Before synthesis, as comments may be above unreachable statements
+	if checking_data.options.parse_comments {
+		register_suppression_comments(statements, environment, checking_data);
+	}
+
 	let mut elements = statements.iter();
 	for element in elements.by_ref() {
 		match element {
@@ -42,8 +52,41 @@ pub(super) fn synthesise_block<T: crate::ReadFromFS>(
 			)
 		)
 	}) {
+		let position = element.get_position();
 		checking_data.diagnostics_container.add_warning(TypeCheckWarning::Unreachable(
-			element.get_position().with_source(environment.get_source()),
+			position.with_source(environment.get_source()),
 		));
+		if checking_data.options.store_expression_type_mappings {
+			checking_data.local_type_mappings.unreachable_statements.push(position, ());
+		}
+	}
+}
+
+/// Only comments which are statements of `statements` are registered. Comments between class members
+/// are registered by [`super::classes::synthesise_class_declaration`], but comments within expressions
+/// (such as between the properties of an object literal or between arguments) are not
+fn register_suppression_comments<T: crate::ReadFromFS>(
+	statements: &[StatementOrDeclaration],
+	environment: &Environment,
+	checking_data: &mut CheckingData<T, super::EznoParser>,
+) {
+	for statement in statements {
+		if let StatementOrDeclaration::Statement(Statement::Comment(comment, position)) = statement
+		{
+			let position = position.with_source(environment.get_source());
+			register_suppression_comment(comment, position, checking_data);
+		}
+	}
+}
+
+pub(super) fn register_suppression_comment<T: crate::ReadFromFS>(
+	comment: &str,
+	position: SpanWithSource,
+	checking_data: &mut CheckingData<T, super::EznoParser>,
+) {
+	match SuppressionComment::from_comment(comment, position) {
+		Some(Ok(comment)) => checking_data.suppression_comments.push(comment),
+		Some(Err(error)) => checking_data.diagnostics_container.add_error(error),
+		None => {}
 	}
 }
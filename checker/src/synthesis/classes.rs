@@ -1,7 +1,10 @@
+use std::borrow::Cow;
+
 use parser::{
 	declarations::{classes::ClassMember, ClassDeclaration},
-	functions::MethodHeader,
+	functions::{MethodHeader, ParameterVisibility},
 	ASTNode, Decorated, Expression, PropertyKey as ParserPropertyKey, StatementPosition,
+	VariableField, VariableIdentifier,
 };
 use source_map::{Nullable, SpanWithSource};
 
@@ -28,7 +31,10 @@ use crate::{
 	CheckingData, DiagnosticsContainer, FunctionId, PropertyValue, Scope, Type, TypeId,
 };
 
-use super::{block::synthesise_block, expressions::synthesise_expression};
+use super::{
+	block::{register_suppression_comment, synthesise_block},
+	expressions::synthesise_expression,
+};
 
 /// Doesn't have any metadata yet
 ///
@@ -100,6 +106,15 @@ pub(super) fn synthesise_class_declaration<
 		}
 	}
 
+	if checking_data.options.parse_comments {
+		for member in &class.members {
+			if let ClassMember::Comment(comment, false, position) = &member.on {
+				let position = position.with_source(environment.get_source());
+				register_suppression_comment(comment, position, checking_data);
+			}
+		}
+	}
+
 	let mut properties = Vec::new();
 
 	// Property keys on `static` items
@@ -538,9 +553,33 @@ pub(super) fn register_statement_class_with_members<T: crate::ReadFromFS>(
 					None,
 				);
 			}
-			ClassMember::Constructor(_)
-			| ClassMember::StaticBlock(_)
-			| ClassMember::Comment(_, _, _) => {}
+			ClassMember::Constructor(constructor) => {
+				// Parameter properties (`constructor(public x: number)`)
+				for parameter in &constructor.parameters.parameters {
+					let VariableField::Name(VariableIdentifier::Standard(name, _)) =
+						parameter.name.get_ast_ref()
+					else {
+						continue;
+					};
+					if !parameter.visibility.is_parameter_property() {
+						continue;
+					}
+					let value = if let Some(ref type_annotation) = parameter.type_annotation {
+						synthesise_type_annotation(type_annotation, environment, checking_data)
+					} else {
+						TypeId::ANY_TYPE
+					};
+					environment.info.register_property(
+						class_type,
+						Publicity::Public,
+						PropertyKey::String(Cow::Owned(name.clone())),
+						PropertyValue::Value(value),
+						false,
+						None,
+					);
+				}
+			}
+			ClassMember::StaticBlock(_) | ClassMember::Comment(_, _, _) => {}
 		}
 	}
 }
@@ -1,8 +1,22 @@
-use parser::{declarations::VariableDeclaration, Declaration};
+use std::borrow::Cow;
+
+use parser::{
+	declarations::{
+		export::Exportable, EnumDeclaration, EnumMember, ExportDeclaration, VariableDeclaration,
+	},
+	types::namespace::Namespace,
+	Declaration, StatementOrDeclaration, VariableField, VariableIdentifier,
+};
 
 use crate::{
-	context::Environment, diagnostics::TypeCheckError, features::variables::VariableMutability,
-	CheckingData, Scope, TypeId,
+	context::{information::Publicity, Environment, VariableRegisterArguments},
+	diagnostics::TypeCheckError,
+	features::{
+		objects::ObjectBuilder,
+		variables::{VariableMutability, VariableOrImport},
+	},
+	types::{properties::PropertyKey, Constant},
+	CheckingData, PropertyValue, Scope, Type, TypeId, VariableId,
 };
 
 use super::{
@@ -60,12 +74,16 @@ pub(crate) fn synthesise_declaration<T: crate::ReadFromFS>(
 		Declaration::Class(class) => {
 			let _constructor = synthesise_class_declaration(&class.on, environment, checking_data);
 		}
+		Declaration::Enum(r#enum) => {
+			synthesise_enum(&r#enum.on, environment, checking_data, false);
+		}
+		Declaration::Namespace(namespace) => {
+			synthesise_namespace(namespace, environment, checking_data, false);
+		}
 		Declaration::DeclareVariable(_)
 		| Declaration::Function(_)
-		| Declaration::Enum(_)
 		| Declaration::Interface(_)
 		| Declaration::TypeAlias(_)
-		| Declaration::Namespace(_)
 		| Declaration::Import(_) => {}
 		Declaration::Export(exported) => match &exported.on {
 			parser::declarations::ExportDeclaration::Variable { exported, position: _ } => {
@@ -101,6 +119,12 @@ pub(crate) fn synthesise_declaration<T: crate::ReadFromFS>(
 							}
 						}
 					}
+					parser::declarations::export::Exportable::Enum(r#enum) => {
+						synthesise_enum(r#enum, environment, checking_data, true);
+					}
+					parser::declarations::export::Exportable::Namespace(namespace) => {
+						synthesise_namespace(namespace, environment, checking_data, true);
+					}
 					parser::declarations::export::Exportable::ImportAll { .. }
 					| parser::declarations::export::Exportable::ImportParts { .. }
 					| parser::declarations::export::Exportable::Function(_)
@@ -137,3 +161,189 @@ pub(crate) fn synthesise_declaration<T: crate::ReadFromFS>(
 		},
 	}
 }
+
+/// Enums are objects of their members, with reverse mappings for numeric values. Members can be
+/// referenced by name in later initialisers
+fn synthesise_enum<T: crate::ReadFromFS>(
+	r#enum: &EnumDeclaration,
+	environment: &mut Environment,
+	checking_data: &mut CheckingData<T, super::EznoParser>,
+	exported: bool,
+) {
+	let (object, _, _) = environment.new_lexical_environment_fold_into_parent(
+		Scope::Block {},
+		checking_data,
+		|environment, checking_data| {
+			let mut object =
+				ObjectBuilder::new(None, &mut checking_data.types, &mut environment.info);
+			let mut next = Some(0f64);
+			for EnumMember::Variant { name, value, position } in &r#enum.members {
+				let position = position.with_source(environment.get_source());
+				let value = if let Some(value) = value {
+					synthesise_expression(value, environment, checking_data, TypeId::ANY_TYPE)
+				} else if let Some(next) = next {
+					checking_data
+						.types
+						.new_constant_type(Constant::Number(next.try_into().unwrap()))
+				} else {
+					// TODO error. Members after non-numeric ones require an initialiser
+					TypeId::NUMBER_TYPE
+				};
+
+				next = None;
+				if let Type::Constant(Constant::Number(number)) =
+					checking_data.types.get_type_by_id(value)
+				{
+					let number = number.into_inner();
+					next = Some(number + 1.);
+					let reverse =
+						checking_data.types.new_constant_type(Constant::String(name.clone()));
+					object.append(
+						environment,
+						Publicity::Public,
+						PropertyKey::String(Cow::Owned(number.to_string())),
+						PropertyValue::Value(reverse),
+						Some(position),
+					);
+				}
+				object.append(
+					environment,
+					Publicity::Public,
+					PropertyKey::String(Cow::Owned(name.clone())),
+					PropertyValue::Value(value),
+					Some(position),
+				);
+
+				environment.register_variable_handle_error(
+					name,
+					VariableRegisterArguments {
+						constant: true,
+						space: None,
+						initial_value: Some(value),
+					},
+					position,
+					&mut checking_data.diagnostics_container,
+				);
+			}
+
+			object.build_object()
+		},
+	);
+	let id = VariableId(environment.get_source(), r#enum.position.start);
+	register_object_value(id, &r#enum.name, object, environment, exported);
+}
+
+/// Namespaces are objects of the items exported from their block. Later declarations with the
+/// same name add to the object of the first
+fn synthesise_namespace<T: crate::ReadFromFS>(
+	namespace: &Namespace,
+	environment: &mut Environment,
+	checking_data: &mut CheckingData<T, super::EznoParser>,
+	exported: bool,
+) {
+	// The binding is registered (once) in hoisting
+	let id = environment.variables.get(&namespace.name).map_or(
+		VariableId(environment.get_source(), namespace.position.start),
+		VariableOrImport::get_id,
+	);
+	let existing = environment.info.variable_current_value.get(&id).copied();
+
+	let (object, _, _) = environment.new_lexical_environment_fold_into_parent(
+		Scope::Namespace {},
+		checking_data,
+		|environment, checking_data| {
+			super::block::synthesise_block(&namespace.inner.0, environment, checking_data);
+
+			let mut object = if let Some(object) = existing {
+				ObjectBuilder { object }
+			} else {
+				ObjectBuilder::new(None, &mut checking_data.types, &mut environment.info)
+			};
+			for item in &namespace.inner.0 {
+				let StatementOrDeclaration::Declaration(Declaration::Export(export)) = item else {
+					continue;
+				};
+				let ExportDeclaration::Variable { exported, .. } = &export.on else { continue };
+				for (name, position) in exported_names(exported) {
+					let position = position.with_source(environment.get_source());
+					if let Ok(value) =
+						environment.get_variable_handle_error(&name, position, checking_data)
+					{
+						object.append(
+							environment,
+							Publicity::Public,
+							PropertyKey::String(Cow::Owned(name)),
+							PropertyValue::Value(value.1),
+							Some(position),
+						);
+					}
+				}
+			}
+
+			object.build_object()
+		},
+	);
+	if existing.is_none() {
+		register_object_value(id, &namespace.name, object, environment, exported);
+	}
+}
+
+/// Only simple names are collected from variable declarations
+fn exported_names(exported: &Exportable) -> Vec<(String, parser::Span)> {
+	match exported {
+		Exportable::Variable(declaration) => {
+			let declarations: Vec<_> = match declaration {
+				VariableDeclaration::ConstDeclaration { declarations, .. } => {
+					declarations.iter().map(|item| item.name.get_ast_ref()).collect()
+				}
+				VariableDeclaration::LetDeclaration { declarations, .. } => {
+					declarations.iter().map(|item| item.name.get_ast_ref()).collect()
+				}
+			};
+			declarations
+				.into_iter()
+				.filter_map(|field| {
+					if let VariableField::Name(VariableIdentifier::Standard(name, position)) = field
+					{
+						Some((name.clone(), *position))
+					} else {
+						None
+					}
+				})
+				.collect()
+		}
+		Exportable::Function(function) => function
+			.name
+			.identifier
+			.as_option_str()
+			.map(|name| (name.to_owned(), function.position))
+			.into_iter()
+			.collect(),
+		Exportable::Class(class) => class
+			.name
+			.identifier
+			.as_option_str()
+			.map(|name| (name.to_owned(), class.position))
+			.into_iter()
+			.collect(),
+		Exportable::Enum(r#enum) => vec![(r#enum.name.clone(), r#enum.position)],
+		Exportable::Namespace(namespace) => vec![(namespace.name.clone(), namespace.position)],
+		_ => Vec::new(),
+	}
+}
+
+/// Sets the value of the binding registered in hoisting
+fn register_object_value(
+	id: VariableId,
+	name: &str,
+	object: TypeId,
+	environment: &mut Environment,
+	exported: bool,
+) {
+	environment.register_initial_variable_declaration_value(id, object);
+	if exported {
+		if let Scope::Module { ref mut exported, .. } = environment.context_type.scope {
+			exported.named.push((name.to_owned(), (id, VariableMutability::Constant)));
+		}
+	}
+}
@@ -859,12 +859,20 @@ pub(super) fn synthesise_expression<T: crate::ReadFromFS>(
 			}
 			SpecialOperators::NonNullAssertion(_) => todo!(),
 		},
-		Expression::DynamicImport { position, .. } => {
-			checking_data.raise_unimplemented_error(
-				"dynamic import",
-				position.with_source(environment.get_source()),
-			);
-			return TypeId::ERROR_TYPE;
+		Expression::DynamicImport { path, position, .. } => {
+			let position = position.with_source(environment.get_source());
+			if let Expression::StringLiteral(path, ..) = &**path {
+				Instance::RValue(features::modules::synthesise_dynamic_import(
+					path,
+					position,
+					environment,
+					checking_data,
+				))
+			} else {
+				checking_data
+					.raise_unimplemented_error("dynamic import with non-constant path", position);
+				return TypeId::ERROR_TYPE;
+			}
 		}
 		Expression::IsExpression(is_expr) => {
 			Instance::RValue(synthesise_is_expression(is_expr, environment, checking_data))
@@ -38,6 +38,14 @@ pub(crate) fn synthesise_jsx_element<T: crate::ReadFromFS>(
 
 	let _tag_name = element.tag_name.as_str();
 
+	// Components are references to variables (`<Foo.Bar />` references `Foo`)
+	if element.tag_name.starts_with(char::is_uppercase) {
+		let name = element.tag_name.split('.').next().unwrap_or_default();
+		if let Some((_, _, variable)) = environment.get_variable_unbound(name) {
+			checking_data.usage.record_read(environment.get_source(), variable);
+		}
+	}
+
 	let tag_name_as_cst_ty =
 		checking_data.types.new_constant_type(Constant::String(element.tag_name.clone()));
 
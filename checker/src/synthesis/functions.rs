@@ -1,5 +1,7 @@
 //! Function tings. Contains parameter synthesis, function body synthesis
 
+use std::borrow::Cow;
+
 use iterator_endiate::EndiateIteratorExt;
 use parser::{
 	expressions::ExpressionOrBlock,
@@ -9,7 +11,10 @@ use parser::{
 };
 
 use crate::{
-	context::{Context, ContextType, Scope, VariableRegisterArguments},
+	context::{
+		environment::FunctionScope, information::Publicity, Context, ContextType, Scope,
+		VariableRegisterArguments,
+	},
 	features::functions::{
 		synthesise_function_default_value, FunctionBehavior, ReturnType, SynthesisableFunction,
 	},
@@ -18,6 +23,7 @@ use crate::{
 			FunctionType, SynthesisedParameter, SynthesisedParameters, SynthesisedRestParameter,
 		},
 		generics::GenericTypeParameters,
+		properties::{PropertyKey, PropertyValue},
 		Constructor, StructureGenerics, Type, TypeId,
 	},
 	CheckingData, Environment, FunctionId,
@@ -415,6 +421,28 @@ fn synthesise_function_parameters<
 				},
 			);
 
+			// `constructor(public x)` also assigns the parameter to `this`
+			if let (
+				true,
+				VariableField::Name(VariableIdentifier::Standard(name, _)),
+				Scope::Function(FunctionScope::Constructor { this_object_type, .. }),
+			) = (
+				parameter.visibility.is_parameter_property(),
+				parameter.name.get_ast_ref(),
+				&environment.context_type.scope,
+			) {
+				let this_object_type = *this_object_type;
+				let position = parameter.position.with_source(environment.get_source());
+				environment.info.register_property(
+					this_object_type,
+					Publicity::Public,
+					PropertyKey::String(Cow::Owned(name.clone())),
+					PropertyValue::Value(variable_ty),
+					true,
+					Some(position),
+				);
+			}
+
 			let name = variable_field_to_string(parameter.name.get_ast_ref());
 
 			SynthesisedParameter {
@@ -1,4 +1,4 @@
-use std::iter;
+use std::{collections::HashSet, iter};
 
 use parser::{
 	declarations::{export::Exportable, DeclareVariableDeclaration, ExportDeclaration},
@@ -33,15 +33,9 @@ pub(crate) fn hoist_statements<T: crate::ReadFromFS>(
 			match declaration {
 				parser::Declaration::DeclareVariable(_)
 				| parser::Declaration::Variable(_)
-				| parser::Declaration::Function(_) => {}
-				parser::Declaration::Enum(r#enum) => checking_data.raise_unimplemented_error(
-					"enum",
-					r#enum.on.position.with_source(environment.get_source()),
-				),
-				parser::Declaration::Namespace(ns) => checking_data.raise_unimplemented_error(
-					"namespace",
-					ns.position.with_source(environment.get_source()),
-				),
+				| parser::Declaration::Function(_)
+				| parser::Declaration::Enum(_)
+				| parser::Declaration::Namespace(_) => {}
 				parser::Declaration::Interface(interface) => {
 					let ty = environment.register_interface(
 						interface.on.name.as_option_str().unwrap_or_default(),
@@ -103,6 +97,7 @@ pub(crate) fn hoist_statements<T: crate::ReadFromFS>(
 							VariableIdentifier::Marker(..) => None,
 						}
 					});
+					let attribute_type = import.with.as_ref().and_then(import_attribute_type);
 					import_items(
 						environment,
 						import.from.get_path().unwrap(),
@@ -112,6 +107,7 @@ pub(crate) fn hoist_statements<T: crate::ReadFromFS>(
 						checking_data,
 						false,
 						import.is_type_annotation_import_only,
+						attribute_type,
 					);
 				}
 				parser::Declaration::Export(export) => {
@@ -137,6 +133,7 @@ pub(crate) fn hoist_statements<T: crate::ReadFromFS>(
 									true,
 									// TODO
 									false,
+									None,
 								);
 							}
 							Exportable::ImportParts {
@@ -153,6 +150,7 @@ pub(crate) fn hoist_statements<T: crate::ReadFromFS>(
 									checking_data,
 									true,
 									*type_definitions_only,
+									None,
 								);
 							}
 							Exportable::Interface(interface) => {
@@ -228,6 +226,9 @@ pub(crate) fn hoist_statements<T: crate::ReadFromFS>(
 		}
 	}
 
+	// Declarations of a namespace with the same name add to the same object
+	let mut namespaces = HashSet::new();
+
 	// Second stage: variables and function type hoisting
 	for item in items {
 		match item {
@@ -235,6 +236,11 @@ pub(crate) fn hoist_statements<T: crate::ReadFromFS>(
 				if let Statement::VarVariable(stmt) = stmt {
 					for declaration in &stmt.declarations {
 						crate::utilities::notify!("declaration.name {:?}", declaration.name);
+						record_declaration(
+							declaration.name.get_ast_ref(),
+							environment,
+							checking_data,
+						);
 						let constraint = get_annotation_from_declaration(
 							declaration,
 							environment,
@@ -255,17 +261,50 @@ pub(crate) fn hoist_statements<T: crate::ReadFromFS>(
 				}
 			}
 			StatementOrDeclaration::Declaration(dec) => match dec {
-				parser::Declaration::Namespace(ns) => checking_data.raise_unimplemented_error(
-					"namespace",
-					ns.position.with_source(environment.get_source()),
-				),
+				parser::Declaration::Namespace(ns) => {
+					if namespaces.insert(ns.name.as_str()) {
+						register_object_binding(&ns.name, ns.position, environment, checking_data);
+					}
+				}
 				parser::Declaration::Variable(declaration) => {
+					match declaration {
+						parser::declarations::VariableDeclaration::ConstDeclaration {
+							declarations,
+							..
+						} => {
+							for declaration in declarations {
+								record_declaration(
+									declaration.name.get_ast_ref(),
+									environment,
+									checking_data,
+								);
+							}
+						}
+						parser::declarations::VariableDeclaration::LetDeclaration {
+							declarations,
+							..
+						} => {
+							for declaration in declarations {
+								record_declaration(
+									declaration.name.get_ast_ref(),
+									environment,
+									checking_data,
+								);
+							}
+						}
+					}
 					hoist_variable_declaration(declaration, environment, checking_data);
 				}
 				parser::Declaration::Function(func) => {
-					if let Some(VariableIdentifier::Standard(name, ..)) =
+					if let Some(VariableIdentifier::Standard(name, name_position)) =
 						func.on.name.as_option_variable_identifier()
 					{
+						let source = environment.get_source();
+						checking_data.usage.declarations.push((
+							crate::VariableId(source, func.get_position().start),
+							name.clone(),
+							name_position.with_source(source),
+						));
 						environment.register_variable_handle_error(
 							name,
 							VariableRegisterArguments {
@@ -280,9 +319,11 @@ pub(crate) fn hoist_statements<T: crate::ReadFromFS>(
 					}
 				}
 				parser::Declaration::Enum(r#enum) => {
-					checking_data.raise_unimplemented_error(
-						"enum",
-						r#enum.position.with_source(environment.get_source()),
+					register_object_binding(
+						&r#enum.on.name,
+						r#enum.on.position,
+						environment,
+						checking_data,
 					);
 				}
 				parser::Declaration::Interface(interface) => {
@@ -388,6 +429,24 @@ pub(crate) fn hoist_statements<T: crate::ReadFromFS>(
 									checking_data,
 								);
 							}
+							Exportable::Enum(r#enum) => {
+								register_object_binding(
+									&r#enum.name,
+									r#enum.position,
+									environment,
+									checking_data,
+								);
+							}
+							Exportable::Namespace(ns) => {
+								if namespaces.insert(ns.name.as_str()) {
+									register_object_binding(
+										&ns.name,
+										ns.position,
+										environment,
+										checking_data,
+									);
+								}
+							}
 							Exportable::TypeAlias(_) | Exportable::ImportAll { .. } => {}
 							// TODO
 							Exportable::Parts(..) | Exportable::ImportParts { .. } => {
@@ -453,8 +512,9 @@ pub(crate) fn hoist_statements<T: crate::ReadFromFS>(
 					},
 				..
 			})) => {
+				// Registered at the function rather than the `export` keyword
 				let variable_id =
-					crate::VariableId(environment.get_source(), item.get_position().start);
+					crate::VariableId(environment.get_source(), function.get_position().start);
 
 				let is_async = function.header.is_async();
 				let is_generator = function.header.is_generator();
@@ -491,6 +551,49 @@ pub(crate) fn hoist_statements<T: crate::ReadFromFS>(
 	}
 }
 
+/// For unused declaration warnings. Only simple names are considered (as destructuring can be
+/// used to omit properties)
+fn record_declaration<T: crate::ReadFromFS>(
+	field: &parser::VariableField,
+	environment: &Environment,
+	checking_data: &mut CheckingData<T, super::EznoParser>,
+) {
+	if let parser::VariableField::Name(VariableIdentifier::Standard(name, position)) = field {
+		let source = environment.get_source();
+		checking_data.usage.declarations.push((
+			crate::VariableId(source, position.start),
+			name.clone(),
+			position.with_source(source),
+		));
+	}
+}
+
+/// Returns `"json"` from `with { type: "json" }`
+fn import_attribute_type(
+	with: &parser::expressions::object_literal::ObjectLiteral,
+) -> Option<&str> {
+	with.members.iter().find_map(|member| {
+		if let parser::expressions::object_literal::ObjectLiteralMember::Property {
+			key,
+			value: parser::Expression::StringLiteral(value, ..),
+			..
+		} = member
+		{
+			match key.get_ast_ref() {
+				parser::PropertyKey::Ident(key, ..)
+				| parser::PropertyKey::StringLiteral(key, ..)
+					if key == "type" =>
+				{
+					Some(value.as_str())
+				}
+				_ => None,
+			}
+		} else {
+			None
+		}
+	})
+}
+
 fn import_part_to_name_pair(item: &parser::declarations::ImportPart) -> Option<NamePair<'_>> {
 	match item {
 		parser::declarations::ImportPart::Name(name) => {
@@ -551,6 +654,22 @@ pub(super) fn export_part_to_name_pair(
 	}
 }
 
+/// For `enum`s and `namespace`s, which are constant objects synthesised in
+/// [`super::declarations::synthesise_declaration`]
+fn register_object_binding<T: crate::ReadFromFS>(
+	name: &str,
+	position: parser::Span,
+	environment: &mut Environment,
+	checking_data: &mut CheckingData<T, super::EznoParser>,
+) {
+	environment.register_variable_handle_error(
+		name,
+		VariableRegisterArguments { constant: true, space: None, initial_value: None },
+		position.with_source(environment.get_source()),
+		&mut checking_data.diagnostics_container,
+	);
+}
+
 pub(super) fn hoist_variable_declaration<T: ReadFromFS>(
 	declaration: &parser::declarations::VariableDeclaration,
 	environment: &mut crate::context::Context<crate::context::environment::Syntax<'_>>,
@@ -42,7 +42,8 @@ pub struct EznoParser;
 impl crate::ASTImplementation for EznoParser {
 	type ParseOptions = parser::ParseOptions;
 	type ParseError = (parser::ParseError, SourceId);
-	type ParserRequirements = ();
+	/// Base options for parsing modules. Some are overridden by [`TypeCheckOptions`](crate::TypeCheckOptions)
+	type ParserRequirements = parser::ParseOptions;
 
 	type Module<'_a> = parser::Module;
 	type OwnedModule = parser::Module;
@@ -124,16 +125,18 @@ impl crate::ASTImplementation for EznoParser {
 		synthesise_type_annotation(annotation, environment, checking_data)
 	}
 
-	fn parse_options(is_js: bool, parse_comments: bool, lsp_mode: bool) -> Self::ParseOptions {
+	fn parse_options(
+		is_js: bool,
+		parse_comments: bool,
+		lsp_mode: bool,
+		parser_requirements: &Self::ParserRequirements,
+	) -> Self::ParseOptions {
 		parser::ParseOptions {
-			comments: if parse_comments {
-				parser::Comments::JustDocumentation
-			} else {
-				parser::Comments::None
-			},
-			type_annotations: !is_js,
-			partial_syntax: lsp_mode,
-			..Default::default()
+			// Line comments are needed for suppression comments
+			comments: if parse_comments { parser::Comments::All } else { parser::Comments::None },
+			type_annotations: parser_requirements.type_annotations && !is_js,
+			partial_syntax: lsp_mode || parser_requirements.partial_syntax,
+			..*parser_requirements
 		}
 	}
 
@@ -239,6 +242,7 @@ impl From<(parser::ParseError, SourceId)> for Diagnostic {
 			reason: parse_error.0.reason,
 			position: parse_error.0.position.with_source(parse_error.1),
 			kind: crate::diagnostics::DiagnosticKind::Error,
+			code: None,
 		}
 	}
 }
@@ -270,7 +274,7 @@ impl StatementOrExpressionVariable for ExpressionPosition {
 
 /// For the REPL in Ezno's CLI
 pub mod interactive {
-	use std::{collections::HashSet, mem, path::PathBuf};
+	use std::{mem, path::PathBuf};
 
 	use source_map::{FileSystem, MapFileStore, SourceId, WithPathMap};
 
@@ -290,11 +294,15 @@ pub mod interactive {
 	impl<'a, T: crate::ReadFromFS> State<'a, T> {
 		pub fn new(
 			resolver: &'a T,
-			type_definition_files: HashSet<PathBuf>,
+			type_definition_files: Vec<PathBuf>,
 		) -> Result<Self, (DiagnosticsContainer, MapFileStore<WithPathMap>)> {
 			let mut root = RootContext::new_with_primitive_references();
-			let mut checking_data =
-				CheckingData::new(Default::default(), resolver, Default::default(), ());
+			let mut checking_data = CheckingData::new(
+				Default::default(),
+				resolver,
+				Default::default(),
+				Default::default(),
+			);
 
 			add_definition_files_to_root(type_definition_files, &mut root, &mut checking_data);
 
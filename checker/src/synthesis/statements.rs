@@ -110,6 +110,7 @@ pub(super) fn synthesise_statement<T: crate::ReadFromFS>(
 		Statement::WhileLoop(stmt) => synthesise_iteration(
 			IterationBehavior::While(&stmt.condition),
 			information.and_then(|info| info.label),
+			stmt.get_position(),
 			environment,
 			checking_data,
 			|environment, checking_data| {
@@ -119,6 +120,7 @@ pub(super) fn synthesise_statement<T: crate::ReadFromFS>(
 		Statement::DoWhileLoop(stmt) => synthesise_iteration(
 			IterationBehavior::DoWhile(&stmt.condition),
 			information.and_then(|info| info.label),
+			stmt.get_position(),
 			environment,
 			checking_data,
 			|environment, checking_data| {
@@ -136,6 +138,7 @@ pub(super) fn synthesise_statement<T: crate::ReadFromFS>(
 				synthesise_iteration(
 					IterationBehavior::ForOf { lhs: variable.get_ast_ref(), rhs: of },
 					information.and_then(|info| info.label),
+					stmt.get_position(),
 					environment,
 					checking_data,
 					|environment, checking_data| {
@@ -156,6 +159,7 @@ pub(super) fn synthesise_statement<T: crate::ReadFromFS>(
 				synthesise_iteration(
 					IterationBehavior::ForIn { lhs: variable.get_ast_ref(), rhs: r#in },
 					information.and_then(|info| info.label),
+					stmt.get_position(),
 					environment,
 					checking_data,
 					|environment, checking_data| {
@@ -175,6 +179,7 @@ pub(super) fn synthesise_statement<T: crate::ReadFromFS>(
 			} => synthesise_iteration(
 				IterationBehavior::For { initialiser, condition, afterthought },
 				information.and_then(|info| info.label),
+				stmt.get_position(),
 				environment,
 				checking_data,
 				|environment, checking_data| {
@@ -80,6 +80,10 @@ pub(super) fn synthesise_type_annotation<T: crate::ReadFromFS>(
 			"this" => todo!(), // environment.get_value_of_this(&mut checking_data.types),
 			"self" => TypeId::ANY_INFERRED_FREE_THIS,
 			name => {
+				checking_data
+					.usage
+					.referenced_type_names
+					.insert((environment.get_source(), name.to_owned()));
 				if let Some(ty) = environment.get_type_from_name(name) {
 					// Warn if it requires parameters. e.g. Array
 					if checking_data.types.get_type_by_id(ty).get_parameters().is_some() {
@@ -160,6 +164,11 @@ pub(super) fn synthesise_type_annotation<T: crate::ReadFromFS>(
 			// 	_ => {}
 			// }
 
+			checking_data
+				.usage
+				.referenced_type_names
+				.insert((environment.get_source(), name.to_owned()));
+
 			let Some(inner_type_id) = environment.get_type_from_name(name) else {
 				checking_data.diagnostics_container.add_error(TypeCheckError::CouldNotFindType(
 					name,
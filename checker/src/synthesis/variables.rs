@@ -223,6 +223,8 @@ fn assign_initial_to_fields<T: crate::ReadFromFS>(
 						VariableIdentifier::Marker(_, _) => "?".to_owned(),
 					};
 					exported.named.push((name, (id, mutability)));
+				} else if let crate::Scope::Namespace {} = environment.context_type.scope {
+					// Collected by `synthesise_namespace`
 				} else {
 					checking_data.diagnostics_container.add_error(
 						TypeCheckError::NonTopLevelExport(
@@ -31,6 +31,14 @@ pub struct TypeMappings {
 	pub variable_restrictions: HashMap<(SourceId, u32), (TypeId, SpanWithSource)>,
 	/// Temp
 	pub special_expressions: RangeMap<SpecialExpressions>,
+
+	/// Conditions which always have the same result (the position of
+	/// [`crate::diagnostics::TypeCheckWarning::DeadBranch`]). For `??` it is whether the LHS is null
+	pub dead_branches: RangeMap<bool>,
+	/// Statements which are never evaluated as they follow a `return`, `throw` etc
+	pub unreachable_statements: RangeMap<()>,
+	/// `while` and `for` loops which do not run any iterations
+	pub loops_without_iterations: RangeMap<()>,
 }
 
 #[derive(Debug)]
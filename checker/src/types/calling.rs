@@ -150,6 +150,7 @@ pub fn call_type_handle_errors<T: crate::ReadFromFS, A: crate::ASTImplementation
 								reason: warning.0,
 								position: call_site,
 								kind: crate::diagnostics::DiagnosticKind::Info,
+								code: None,
 							},
 						);
 					}
@@ -598,9 +599,10 @@ impl FunctionType {
 			let call_anyway = matches!(
 				const_fn_ident.as_str(),
 				"debug_type"
-					| "debug_type_rust" | "print_type"
-					| "print_and_debug_type"
-					| "debug_effects" | "debug_effects_rust"
+					| "debug_type_rust"
+					| "print_type" | "print_and_debug_type"
+					| "debug_effects"
+					| "debug_effects_rust"
 					| "satisfies" | "is_dependent"
 					| "bind" | "create_proxy"
 			);
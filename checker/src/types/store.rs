@@ -26,7 +26,7 @@ use super::{
 };
 
 /// Holds all the types. Eventually may be split across modules
-#[derive(Debug, binary_serialize_derive::BinarySerializable)]
+#[derive(Debug, Clone, binary_serialize_derive::BinarySerializable)]
 pub struct TypeStore {
 	/// Contains all of the types. Indexed by [`TypeId`]
 	types: Vec<Type>,
@@ -144,6 +144,20 @@ impl Default for TypeStore {
 	}
 }
 
+/// Types (and the data attached to them) registered after some point, such as after the
+/// definitions. For saving the results of modules with [`crate::incremental`]
+#[derive(binary_serialize_derive::BinarySerializable)]
+pub(crate) struct TypeStoreAdditions {
+	/// `None` for types that are not reached, so that the [`TypeId`]s of the others are kept
+	types: Vec<Option<Type>>,
+	lookup_generic_map: HashMap<TypeId, LookUpGenericMap>,
+	interface_extends: HashMap<TypeId, TypeId>,
+	interface_type_parameter_extends: HashMap<TypeId, TypeId>,
+	functions: HashMap<FunctionId, FunctionType>,
+	called_functions: HashSet<FunctionId>,
+	closure_counter: u32,
+}
+
 impl TypeStore {
 	pub fn new_constant_type(&mut self, constant: crate::Constant) -> crate::TypeId {
 		// Reuse existing ids rather than creating new types sometimes
@@ -168,6 +182,94 @@ impl TypeStore {
 		}
 	}
 
+	pub(crate) fn count_of_types(&self) -> usize {
+		self.types.len()
+	}
+
+	/// Types registered after the first `from` which `reached` (and the types and functions they
+	/// reference) contain
+	pub(crate) fn additions_reached(
+		&self,
+		from: usize,
+		mut reached: crate::serialization::Reached,
+	) -> TypeStoreAdditions {
+		use crate::serialization::{record_reached, BinarySerializable};
+
+		let (mut types, mut functions) = (HashSet::new(), HashSet::new());
+		loop {
+			let new_types: Vec<TypeId> = reached
+				.types
+				.drain()
+				.filter(|id| id.0 as usize >= from && types.insert(*id))
+				.collect();
+			let new_functions: Vec<FunctionId> = reached
+				.functions
+				.drain()
+				.filter(|id| self.functions.contains_key(id) && functions.insert(*id))
+				.collect();
+			if new_types.is_empty() && new_functions.is_empty() {
+				break;
+			}
+
+			// Serializing to find the referenced types and functions
+			((), reached) = record_reached(|| {
+				let mut buf = Vec::new();
+				for id in new_types {
+					self.get_type_by_id(id).clone().serialize(&mut buf);
+					self.lookup_generic_map.get(&id).cloned().serialize(&mut buf);
+					self.interface_extends.get(&id).copied().serialize(&mut buf);
+					self.interface_type_parameter_extends.get(&id).copied().serialize(&mut buf);
+				}
+				for id in new_functions {
+					self.functions[&id].clone().serialize(&mut buf);
+				}
+			});
+		}
+
+		let reached_by_key = |map: &HashMap<TypeId, TypeId>| {
+			map.iter().filter(|(id, _)| types.contains(id)).map(|(k, v)| (*k, *v)).collect()
+		};
+
+		TypeStoreAdditions {
+			types: self.types[from..]
+				.iter()
+				.enumerate()
+				.map(|(idx, ty)| {
+					let id = TypeId(u16::try_from(from + idx).unwrap());
+					types.contains(&id).then(|| ty.clone())
+				})
+				.collect(),
+			lookup_generic_map: self
+				.lookup_generic_map
+				.iter()
+				.filter(|(id, _)| types.contains(id))
+				.map(|(id, map)| (*id, map.clone()))
+				.collect(),
+			interface_extends: reached_by_key(&self.interface_extends),
+			interface_type_parameter_extends: reached_by_key(
+				&self.interface_type_parameter_extends,
+			),
+			functions: functions.iter().map(|id| (*id, self.functions[id].clone())).collect(),
+			called_functions: self.called_functions.intersection(&functions).copied().collect(),
+			closure_counter: self.closure_counter,
+		}
+	}
+
+	/// Types that were not reached are registered as placeholders (which nothing references)
+	pub(crate) fn extend_with(&mut self, additions: TypeStoreAdditions) {
+		let placeholder =
+			|| Type::AliasTo { to: TypeId::ERROR_TYPE, name: String::new(), parameters: None };
+		self.types.extend(additions.types.into_iter().map(|ty| ty.unwrap_or_else(placeholder)));
+		self.lookup_generic_map.extend(additions.lookup_generic_map);
+		self.interface_extends.extend(additions.interface_extends);
+		self.interface_type_parameter_extends.extend(additions.interface_type_parameter_extends);
+		for (id, function) in additions.functions {
+			self.functions.entry(id).or_insert(function);
+		}
+		self.called_functions.extend(additions.called_functions);
+		self.closure_counter = self.closure_counter.max(additions.closure_counter);
+	}
+
 	pub(crate) fn register_type(&mut self, ty: Type) -> TypeId {
 		let id = TypeId(self.types.len().try_into().expect("too many types!"));
 		self.types.push(ty);
@@ -667,6 +769,12 @@ impl TypeStore {
 		ClosureId(self.closure_counter)
 	}
 
+	/// Whether a function was synthesised at `id`
+	#[must_use]
+	pub fn has_function(&self, id: FunctionId) -> bool {
+		self.functions.contains_key(&id)
+	}
+
 	#[must_use]
 	pub fn get_function_from_id(&self, id: FunctionId) -> &FunctionType {
 		self.functions.get(&id).unwrap()
@@ -748,6 +856,16 @@ impl TypeStore {
 		self.register_type(ty)
 	}
 
+	pub fn new_promise_type(&mut self, resolved_type: TypeId, position: SpanWithSource) -> TypeId {
+		let ty = Type::Constructor(Constructor::StructureGenerics(StructureGenerics {
+			on: TypeId::PROMISE_TYPE,
+			arguments: StructureGenericArguments::ExplicitRestrictions(FromIterator::from_iter([
+				(TypeId::T_TYPE, (resolved_type, position)),
+			])),
+		}));
+		self.register_type(ty)
+	}
+
 	/// TODO WIP
 	pub fn new_open_type(&mut self, base: TypeId) -> TypeId {
 		self.register_type(Type::RootPolyType(PolyNature::Open(base)))
@@ -0,0 +1,157 @@
+//! Finding unused imports, declarations and exports. Information is collected during synthesis
+//! and checked after all modules have been synthesised (as exports can be imported by any module)
+
+use std::collections::{HashMap, HashSet};
+
+use source_map::{SourceId, Span, SpanWithSource};
+
+use crate::{
+	diagnostics::TypeCheckWarning,
+	features::{modules::SynthesisedModule, variables::VariableOrImport},
+	DiagnosticsContainer, VariableId,
+};
+
+/// A binding introduced by an import statement
+pub(crate) struct ImportedBinding {
+	pub name: String,
+	/// `None` if the import is only a type
+	pub variable: Option<VariableId>,
+	pub is_type: bool,
+	pub position: SpanWithSource,
+}
+
+/// **PROJECT WIDE**
+#[derive(Default)]
+pub(crate) struct Usage {
+	/// Under the source they are read in. Includes reads of imports
+	pub read_variables: HashSet<(SourceId, VariableId)>,
+	/// Under the source they are assigned to in
+	pub assigned_variables: HashSet<(SourceId, VariableId)>,
+	/// Type names referenced in annotations, under the source they are referenced in
+	pub referenced_type_names: HashSet<(SourceId, String)>,
+	/// Variables which are exported and have been imported by another module (the first item)
+	pub imported_variables: HashSet<(SourceId, VariableId)>,
+	/// Exports which are read through an import, re-exported or imported as a namespace. Unlike
+	/// `imported_variables`, imports which are never read are not counted
+	pub used_exports: HashSet<(SourceId, VariableId)>,
+	pub imports: Vec<ImportedBinding>,
+	/// Non exported variable and function declarations
+	pub declarations: Vec<(VariableId, String, SpanWithSource)>,
+	/// Positions of references (reads and assignments) to the variable they resolve to
+	pub references: HashMap<SpanWithSource, VariableId>,
+	/// Variables assigned in a block, loop, `try` or function other than the one they are
+	/// declared in
+	pub assigned_in_inner_scopes: HashSet<VariableId>,
+	/// In the order they were synthesised. For deterministic output
+	pub synthesised_modules: Vec<SourceId>,
+}
+
+/// Which variables are used, across all modules. For removing unused code in builds
+#[derive(Debug, Default)]
+pub struct VariableUsage {
+	pub read: HashSet<VariableId>,
+	pub assigned: HashSet<VariableId>,
+	/// Exported variables which another module reads (or re-exports)
+	pub imported: HashSet<VariableId>,
+	/// Positions of references (reads and assignments) to the variable they resolve to. For
+	/// renaming
+	pub references: HashMap<SpanWithSource, VariableId>,
+	/// Variables assigned in a block, loop, `try` or function other than the one they are
+	/// declared in. Their value after that is not tracked, so should not be treated as constant
+	pub assigned_in_inner_scopes: HashSet<VariableId>,
+}
+
+impl Usage {
+	pub(crate) fn get_variable_usage(&self) -> VariableUsage {
+		fn ids(pairs: &HashSet<(SourceId, VariableId)>) -> HashSet<VariableId> {
+			pairs.iter().map(|(_, id)| *id).collect()
+		}
+
+		VariableUsage {
+			read: ids(&self.read_variables),
+			assigned: ids(&self.assigned_variables),
+			imported: ids(&self.used_exports),
+			references: self.references.clone(),
+			assigned_in_inner_scopes: self.assigned_in_inner_scopes.clone(),
+		}
+	}
+
+	/// For `import * as x` and `export * from`
+	pub(crate) fn mark_all_imported(
+		&mut self,
+		importer: SourceId,
+		exported: &crate::features::modules::Exported,
+	) {
+		self.imported_variables.extend(exported.named.iter().map(|(_, (id, _))| (importer, *id)));
+		self.used_exports.extend(exported.named.iter().map(|(_, (id, _))| (importer, *id)));
+	}
+
+	pub(crate) fn record_read(&mut self, source: SourceId, variable: &VariableOrImport) {
+		self.read_variables.insert((source, variable.get_id()));
+		if let VariableOrImport::MutableImport { of: exported, .. }
+		| VariableOrImport::ConstantImport { to: Some(exported), .. } = variable
+		{
+			self.used_exports.insert((source, *exported));
+		}
+	}
+
+	/// Names starting with `_` are treated as intentionally unused
+	pub(crate) fn report<M>(
+		&self,
+		modules: &HashMap<SourceId, SynthesisedModule<M>>,
+		entry_points: &HashSet<SourceId>,
+		diagnostics: &mut DiagnosticsContainer,
+	) {
+		let read_variables: HashSet<VariableId> =
+			self.read_variables.iter().map(|(_, id)| *id).collect();
+		let imported_variables: HashSet<VariableId> =
+			self.imported_variables.iter().map(|(_, id)| *id).collect();
+
+		for import in &self.imports {
+			if import.name.starts_with('_') {
+				continue;
+			}
+			let used_as_variable = import.variable.is_some_and(|id| read_variables.contains(&id));
+			let used_as_type = import.is_type
+				&& self
+					.referenced_type_names
+					.contains(&(import.position.source, import.name.clone()));
+
+			if !used_as_variable && !used_as_type {
+				diagnostics.add_warning(TypeCheckWarning::UnusedImport {
+					name: import.name.clone(),
+					position: import.position,
+				});
+			}
+		}
+
+		for (id, name, position) in &self.declarations {
+			if !name.starts_with('_') && !read_variables.contains(id) {
+				diagnostics.add_warning(TypeCheckWarning::UnusedDeclaration {
+					name: name.clone(),
+					position: *position,
+				});
+			}
+		}
+
+		for source in &self.synthesised_modules {
+			// Exports of entry points are the public interface
+			if entry_points.contains(source) {
+				continue;
+			}
+			let Some(module) = modules.get(source) else { continue };
+			// TODO type exports (requires positions)
+			for (name, (id, _)) in &module.exported.named {
+				if name.starts_with('_') || imported_variables.contains(id) {
+					continue;
+				}
+				// Position of the variable, rather than the export
+				#[allow(clippy::cast_possible_truncation)]
+				let position = Span { start: id.1, end: id.1 + name.len() as u32, source: () }
+					.with_source(id.0);
+				diagnostics
+					.add_warning(TypeCheckWarning::UnusedExport { name: name.clone(), position });
+			}
+		}
+	}
+}
@@ -0,0 +1,10 @@
+/// Reads from the `(path, content)` pairs, for use as the file reader of `check_project`
+pub fn read_from<'a>(
+	files: &'a [(&'a str, &'a str)],
+) -> impl Fn(&std::path::Path) -> Option<Vec<u8>> + 'a {
+	move |path| {
+		files.iter().find_map(|(name, content)| {
+			(std::path::Path::new(name) == path).then(|| content.as_bytes().to_owned())
+		})
+	}
+}
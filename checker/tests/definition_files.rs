@@ -0,0 +1,124 @@
+mod common;
+
+#[cfg(feature = "ezno-parser")]
+#[test]
+fn multiple_definition_files_are_merged_in_order() {
+	use ezno_checker::{check_project, synthesis, TypeCheckOptions};
+
+	let files = [
+		("main.ts", "const a: number = globalCounter.count;\nconst b: string = globalCounter.label;\nconst c: string = globalCounter.count;"),
+		("first.d.ts", "interface Counter { count: number }"),
+		("second.d.ts", "interface Counter { label: string }\ndeclare const globalCounter: Counter;"),
+	];
+
+	let type_definition_files = vec![
+		ezno_checker::INTERNAL_DEFINITION_FILE_PATH.into(),
+		"first.d.ts".into(),
+		"second.d.ts".into(),
+	];
+
+	let result = check_project::<_, synthesis::EznoParser>(
+		vec!["main.ts".into()],
+		type_definition_files,
+		common::read_from(&files),
+		TypeCheckOptions::default(),
+		Default::default(),
+		None,
+	);
+
+	let diagnostics: Vec<_> =
+		result.diagnostics.into_iter().map(|diagnostic| diagnostic.reason().to_owned()).collect();
+
+	assert_eq!(diagnostics, vec!["Type number is not assignable to type string".to_owned()]);
+}
+
+#[cfg(feature = "ezno-parser")]
+#[test]
+fn definition_files_can_be_packed_into_a_cache() {
+	use ezno_checker::{check_project, generate_cache, synthesis, TypeCheckOptions};
+
+	let definitions = [
+		("first.d.ts", "interface Counter { count: number }"),
+		(
+			"second.d.ts",
+			"interface Counter { label: string }\ndeclare const globalCounter: Counter;",
+		),
+	];
+	let read_definition = common::read_from(&definitions);
+
+	let cache = generate_cache::<_, synthesis::EznoParser>(
+		&[
+			ezno_checker::INTERNAL_DEFINITION_FILE_PATH.into(),
+			"first.d.ts".into(),
+			"second.d.ts".into(),
+		],
+		&read_definition,
+		Default::default(),
+	)
+	.unwrap_or_else(|_| panic!("definition files have errors"));
+
+	let main = "const a: number = globalCounter.count;\nconst b: string = globalCounter.label;\nconst c: string = globalCounter.count;";
+
+	let result = check_project::<_, synthesis::EznoParser>(
+		vec!["main.ts".into()],
+		vec!["definitions.bin".into()],
+		|path: &std::path::Path| match path.to_str() {
+			Some("main.ts") => Some(main.as_bytes().to_owned()),
+			Some("definitions.bin") => Some(cache.clone()),
+			_ => None,
+		},
+		TypeCheckOptions::default(),
+		Default::default(),
+		None,
+	);
+
+	let diagnostics: Vec<_> =
+		result.diagnostics.into_iter().map(|diagnostic| diagnostic.reason().to_owned()).collect();
+
+	assert_eq!(diagnostics, vec!["Type number is not assignable to type string".to_owned()]);
+}
+
+#[cfg(feature = "ezno-parser")]
+#[test]
+fn root_context_snapshot_is_versioned() {
+	use ezno_checker::{generate_cache, source_map::MapFileStore, synthesis, RootContext};
+
+	let snapshot = generate_cache::<_, synthesis::EznoParser>(
+		&[ezno_checker::INTERNAL_DEFINITION_FILE_PATH.into()],
+		&|_: &std::path::Path| None::<Vec<u8>>,
+		Default::default(),
+	)
+	.unwrap_or_else(|_| panic!("internal definitions have errors"));
+
+	assert!(RootContext::deserialize(&snapshot, &mut MapFileStore::default()).is_ok());
+
+	let header_length = b"EZNO\0CONTEXT\0FILE".len();
+	let mut other_version = snapshot.clone();
+	other_version[header_length] += 1;
+	let Err(reason) = RootContext::deserialize(&other_version, &mut MapFileStore::default()) else {
+		panic!("expected version mismatch")
+	};
+	assert_eq!(reason, "context file has format version 3, expected 2");
+
+	let Err(reason) = RootContext::deserialize(b"not a snapshot", &mut MapFileStore::default())
+	else {
+		panic!("expected missing header")
+	};
+	assert_eq!(reason, "not a context file (missing header)");
+
+	let Err(reason) =
+		RootContext::deserialize(&snapshot[..snapshot.len() - 10], &mut MapFileStore::default())
+	else {
+		panic!("expected truncated")
+	};
+	assert_eq!(reason, "context file is truncated");
+
+	let mut corrupted = snapshot.clone();
+	let last = corrupted.len() - 1;
+	corrupted[last] ^= 0xff;
+	corrupted[last / 2] ^= 0xff;
+	let Err(reason) = RootContext::deserialize(&corrupted, &mut MapFileStore::default()) else {
+		panic!("expected corrupted")
+	};
+	assert_eq!(reason, "context file is corrupted");
+}
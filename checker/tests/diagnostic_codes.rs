@@ -0,0 +1,31 @@
+#[cfg(feature = "ezno-parser")]
+#[test]
+fn diagnostics_have_stable_codes() {
+	use ezno_checker::{check_project, synthesis, DiagnosticCode, TypeCheckOptions};
+
+	let source = "const a: string = 2;\nconst b = c;";
+
+	let result = check_project::<_, synthesis::EznoParser>(
+		vec!["main.ts".into()],
+		vec![ezno_checker::INTERNAL_DEFINITION_FILE_PATH.into()],
+		|path: &std::path::Path| {
+			(path == std::path::Path::new("main.ts")).then(|| source.to_owned())
+		},
+		TypeCheckOptions::default(),
+		Default::default(),
+		None,
+	);
+
+	let mut diagnostics = result.diagnostics;
+	let codes: Vec<_> = diagnostics
+		.iter()
+		.map(|diagnostic| diagnostic.code().map(|code| code.to_string()))
+		.collect();
+
+	assert_eq!(codes, vec![Some("E0201".to_owned()), Some("E0004".to_owned())]);
+
+	let suppressed: [DiagnosticCode; 2] = ["E0201".parse().unwrap(), "E0004".parse().unwrap()];
+	diagnostics
+		.retain(|diagnostic| diagnostic.code().is_none_or(|code| !suppressed.contains(&code)));
+	assert!(!diagnostics.has_error());
+}
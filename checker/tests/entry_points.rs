@@ -0,0 +1,37 @@
+mod common;
+
+#[cfg(feature = "ezno-parser")]
+#[test]
+fn entry_points_are_checked_as_one_program() {
+	use ezno_checker::{check_project, synthesis, TypeCheckOptions};
+
+	let files = [
+		("a.ts", "import { value } from \"./shared\";\nexport const a: number = value;"),
+		("b.ts", "import { value } from \"./shared\";\nconst b: string = value;"),
+		("shared.ts", "export const value: number = 2;"),
+	];
+
+	let result = check_project::<_, synthesis::EznoParser>(
+		vec!["a.ts".into(), "b.ts".into(), "shared.ts".into()],
+		vec![ezno_checker::INTERNAL_DEFINITION_FILE_PATH.into()],
+		common::read_from(&files),
+		TypeCheckOptions::default(),
+		Default::default(),
+		None,
+	);
+
+	let diagnostics: Vec<_> =
+		result.diagnostics.iter().map(|diagnostic| diagnostic.reason().to_owned()).collect();
+	assert_eq!(diagnostics, vec!["Type 2 is not assignable to type string".to_owned()]);
+
+	// `shared.ts` is synthesised once, when first imported
+	let statistics: Vec<_> = result
+		.entry_points
+		.iter()
+		.map(|entry| (entry.path.display().to_string(), entry.modules, entry.diagnostics))
+		.collect();
+	assert_eq!(
+		statistics,
+		vec![("a.ts".to_owned(), 2, 0), ("b.ts".to_owned(), 1, 1), ("shared.ts".to_owned(), 0, 0)]
+	);
+}
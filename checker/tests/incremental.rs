@@ -0,0 +1,81 @@
+mod common;
+
+#[cfg(feature = "ezno-parser")]
+#[test]
+fn unchanged_modules_are_restored() {
+	use ezno_checker::{check_project_incremental, synthesis, TypeCheckOptions};
+
+	fn run(
+		files: &[(&str, &str)],
+		previous: Option<&[u8]>,
+	) -> (Vec<String>, ezno_checker::IncrementalCache) {
+		let (output, cache) = check_project_incremental::<_, synthesis::EznoParser>(
+			vec!["main.ts".into()],
+			vec![ezno_checker::INTERNAL_DEFINITION_FILE_PATH.into()],
+			common::read_from(files),
+			TypeCheckOptions::default(),
+			Default::default(),
+			previous,
+		);
+		let diagnostics = output
+			.diagnostics
+			.into_iter()
+			.map(|diagnostic| diagnostic.reason().to_owned())
+			.collect();
+		(diagnostics, cache)
+	}
+
+	let main = "import { value } from \"./other\";\nconst a: string = value;\nconsole.log(a);";
+
+	let (first, cache) =
+		run(&[("main.ts", main), ("other.ts", "export const value: number = 2;")], None);
+	assert_eq!(cache.restored_modules, 0);
+
+	let (second, cache) = run(
+		&[("main.ts", main), ("other.ts", "export const value: number = 2;")],
+		Some(&cache.bytes),
+	);
+	assert_eq!(cache.restored_modules, 2);
+	assert_eq!(first, second);
+	assert_eq!(second, vec!["Type 2 is not assignable to type string".to_owned()]);
+
+	// Changing a dependency invalidates its dependants
+	let (third, cache) = run(
+		&[("main.ts", main), ("other.ts", "export const value: string = \"hi\";")],
+		Some(&cache.bytes),
+	);
+	assert_eq!(cache.restored_modules, 0);
+	assert!(third.is_empty(), "{third:?}");
+
+	// Creating a file that the import now resolves to invalidates the importer
+	let with_js = [("main.ts", main), ("other.js", "export const value = 2;")];
+	let (_, cache) = run(&with_js, None);
+	let (_, cache) = run(&with_js, Some(&cache.bytes));
+	assert_eq!(cache.restored_modules, 2);
+
+	let (fourth, cache) = run(
+		&[
+			("main.ts", main),
+			("other.js", "export const value = 2;"),
+			("other.ts", "export const value: string = \"hi\";"),
+		],
+		Some(&cache.bytes),
+	);
+	assert_eq!(cache.restored_modules, 0);
+	assert!(fourth.is_empty(), "{fourth:?}");
+
+	// A truncated or corrupted cache is a miss rather than a crash
+	let files = [("main.ts", main), ("other.ts", "export const value: number = 2;")];
+	let (_, cache) = run(&files, None);
+	let mut bytes = cache.bytes;
+
+	let (truncated, cache) = run(&files, Some(&bytes[..bytes.len() / 2]));
+	assert_eq!(cache.restored_modules, 0);
+	assert_eq!(truncated, first);
+
+	let last = bytes.len() - 1;
+	bytes[last] ^= 0xff;
+	let (corrupted, cache) = run(&files, Some(&bytes));
+	assert_eq!(cache.restored_modules, 0);
+	assert_eq!(corrupted, first);
+}
@@ -2,7 +2,6 @@
 #[test]
 fn type_mappings() {
 	use ezno_checker::{check_project, synthesis, TypeCheckOptions};
-	use std::collections::HashSet;
 
 	// Below source has several issues
 	let text = "let x: 2 = 5 + ;
@@ -13,7 +12,7 @@ fn type_mappings() {
 	func(b)";
 
 	let definition_file = ezno_checker::INTERNAL_DEFINITION_FILE_PATH.into();
-	let type_definition_files = HashSet::from_iter([definition_file]);
+	let type_definition_files = vec![definition_file];
 
 	// `lsp_mode` <=> partial syntax
 	let options = TypeCheckOptions { lsp_mode: true, ..Default::default() };
@@ -25,7 +24,7 @@ fn type_mappings() {
 		type_definition_files,
 		|_path: &std::path::Path| Some(text.to_owned()),
 		options,
-		(),
+		Default::default(),
 		None,
 	);
 
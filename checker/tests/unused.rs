@@ -0,0 +1,56 @@
+mod common;
+
+#[cfg(feature = "ezno-parser")]
+fn check_files(files: &[(&str, &str)], report_unused: bool) -> Vec<String> {
+	use ezno_checker::{check_project, synthesis, TypeCheckOptions};
+
+	let definition_file = ezno_checker::INTERNAL_DEFINITION_FILE_PATH.into();
+	let type_definition_files = vec![definition_file];
+
+	let options = TypeCheckOptions { report_unused, ..Default::default() };
+
+	let result = check_project::<_, synthesis::EznoParser>(
+		vec![files[0].0.into()],
+		type_definition_files,
+		common::read_from(files),
+		options,
+		Default::default(),
+		None,
+	);
+
+	result.diagnostics.into_iter().map(|diagnostic| diagnostic.reason().to_owned()).collect()
+}
+
+#[cfg(feature = "ezno-parser")]
+#[test]
+fn unused_imports_declarations_and_exports() {
+	let files = [
+		(
+			"main.ts",
+			"import { a, b } from \"./other\";
+import type { Unused, Used } from \"./other\";
+const x = 2, _ignored = 3;
+function y() { return a }
+const z: Used = y();
+console.log(z);",
+		),
+		(
+			"other.ts",
+			"export const a = 1, b = 2, c = 3;
+export type Used = number;
+export type Unused = string;",
+		),
+	];
+
+	assert_eq!(
+		check_files(&files, true),
+		vec![
+			"Unused import 'b'",
+			"Unused import 'Unused'",
+			"'x' is declared but never read",
+			"Export 'c' is not imported by any checked module"
+		]
+	);
+
+	assert!(check_files(&files, false).is_empty());
+}
@@ -36,7 +36,8 @@ impl StatementOrDeclaration {
 						on: ExportDeclaration::Default { .. }
 							| ExportDeclaration::Variable {
 								exported: Exportable::ImportAll { .. }
-									| Exportable::ImportParts { .. } | Exportable::Parts { .. },
+									| Exportable::ImportParts { .. }
+									| Exportable::Parts { .. },
 								..
 							},
 						..
@@ -204,6 +205,8 @@ impl Visitable for Block {
 		chain: &mut temporary_annex::Annex<crate::visiting::Chain>,
 	) {
 		if options.visit_nested_blocks || chain.is_empty() {
+			let mut chain = chain.push_annex(crate::visiting::ChainVariable::Block(self.1));
+			let chain = &mut chain;
 			{
 				visitors.visit_block(&crate::block::BlockLike { items: &self.0 }, data, chain);
 			}
@@ -224,6 +227,8 @@ impl Visitable for Block {
 		chain: &mut temporary_annex::Annex<crate::visiting::Chain>,
 	) {
 		if options.visit_nested_blocks || chain.is_empty() {
+			let mut chain = chain.push_annex(crate::visiting::ChainVariable::Block(self.1));
+			let chain = &mut chain;
 			{
 				visitors.visit_block_mut(
 					&mut crate::block::BlockLikeMut { items: &mut self.0 },
@@ -395,6 +400,21 @@ pub fn statements_and_declarations_to_string<T: source_map::ToString>(
 			}
 		}
 
+		// Skipped rather than leaving an empty line
+		match item {
+			StatementOrDeclaration::Statement(Statement::Comment(..))
+				if !options.should_add_comment(false) =>
+			{
+				continue;
+			}
+			StatementOrDeclaration::Statement(Statement::MultiLineComment(comment, _))
+				if !options.should_add_comment(comment.starts_with('*')) =>
+			{
+				continue;
+			}
+			_ => {}
+		}
+
 		if let (false, StatementOrDeclaration::Declaration(dec)) =
 			(options.include_type_annotations, item)
 		{
@@ -410,6 +430,11 @@ pub fn statements_and_declarations_to_string<T: source_map::ToString>(
 		}
 
 		options.add_indent(local.depth, buf);
+		// Generated items (such as from transformers) may not have a position
+		let position = item.get_position();
+		if !source_map::Nullable::is_null(&position) {
+			buf.add_mapping(&position.with_source(local.under));
+		}
 		item.to_string_from_buffer(buf, options, local);
 		if (!at_end || options.trailing_semicolon) && item.requires_semi_colon() {
 			buf.push(';');
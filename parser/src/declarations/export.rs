@@ -6,8 +6,8 @@ use crate::{
 };
 
 use super::{
-	variable::VariableDeclaration, ClassDeclaration, ImportExportName, ImportLocation,
-	InterfaceDeclaration, StatementFunction, TypeAlias,
+	variable::VariableDeclaration, ClassDeclaration, EnumDeclaration, ImportExportName,
+	ImportLocation, InterfaceDeclaration, StatementFunction, TypeAlias,
 };
 
 use get_field_by_type::GetFieldByType;
@@ -50,9 +50,19 @@ pub enum Exportable {
 	Variable(VariableDeclaration),
 	Interface(InterfaceDeclaration),
 	TypeAlias(TypeAlias),
+	Enum(EnumDeclaration),
+	#[cfg(feature = "full-typescript")]
+	Namespace(crate::types::namespace::Namespace),
 	Parts(Vec<ExportPart>),
-	ImportAll { r#as: Option<VariableIdentifier>, from: ImportLocation },
-	ImportParts { parts: Vec<ExportPart>, from: ImportLocation, type_definitions_only: bool },
+	ImportAll {
+		r#as: Option<VariableIdentifier>,
+		from: ImportLocation,
+	},
+	ImportParts {
+		parts: Vec<ExportPart>,
+		from: ImportLocation,
+		type_definitions_only: bool,
+	},
 }
 
 impl ASTNode for ExportDeclaration {
@@ -145,7 +155,27 @@ impl ASTNode for ExportDeclaration {
 				let position = start.union(class_declaration.get_position());
 				Ok(Self::Variable { exported: Exportable::Class(class_declaration), position })
 			}
+			Token(TSXToken::Keyword(TSXKeyword::Enum), _) => {
+				let enum_declaration = EnumDeclaration::from_reader(reader, state, options)?;
+				let position = start.union(enum_declaration.get_position());
+				Ok(Self::Variable { exported: Exportable::Enum(enum_declaration), position })
+			}
+			#[cfg(feature = "full-typescript")]
+			Token(TSXToken::Keyword(TSXKeyword::Namespace), _) => {
+				let namespace =
+					crate::types::namespace::Namespace::from_reader(reader, state, options)?;
+				let position = start.union(namespace.get_position());
+				Ok(Self::Variable { exported: Exportable::Namespace(namespace), position })
+			}
 			Token(TSXToken::Keyword(TSXKeyword::Const | TSXKeyword::Let), _) => {
+				if let Some(Token(TSXToken::Keyword(TSXKeyword::Enum), _)) = reader.peek_n(1) {
+					let enum_declaration = EnumDeclaration::from_reader(reader, state, options)?;
+					let position = start.union(enum_declaration.get_position());
+					return Ok(Self::Variable {
+						exported: Exportable::Enum(enum_declaration),
+						position,
+					});
+				}
 				let variable_declaration =
 					VariableDeclaration::from_reader(reader, state, options)?;
 				let position = start.union(variable_declaration.get_position());
@@ -270,6 +300,7 @@ impl ASTNode for ExportDeclaration {
 					TSXToken::Keyword(TSXKeyword::Let),
 					TSXToken::Keyword(TSXKeyword::Interface),
 					TSXToken::Keyword(TSXKeyword::Type),
+					TSXToken::Keyword(TSXKeyword::Enum),
 					TSXToken::OpenBrace,
 				],
 			),
@@ -301,6 +332,13 @@ impl ASTNode for ExportDeclaration {
 					Exportable::TypeAlias(type_alias) => {
 						type_alias.to_string_from_buffer(buf, options, local);
 					}
+					Exportable::Enum(enum_declaration) => {
+						enum_declaration.to_string_from_buffer(buf, options, local);
+					}
+					#[cfg(feature = "full-typescript")]
+					Exportable::Namespace(namespace) => {
+						namespace.to_string_from_buffer(buf, options, local);
+					}
 					Exportable::Parts(parts) => {
 						buf.push('{');
 						options.push_gap_optionally(buf);
@@ -73,12 +73,24 @@ impl Declaration {
 		options: &ParseOptions,
 	) -> bool {
 		let Some(Token(token, _)) = reader.peek() else { return false };
+
+		// `import(...)` and `import.meta` are expressions
+		if let TSXToken::Keyword(TSXKeyword::Import) = token {
+			return !matches!(
+				reader.peek_n(1),
+				Some(Token(TSXToken::OpenParentheses | TSXToken::Dot, _))
+			);
+		}
+
 		let result = matches!(
 			token,
 			TSXToken::Keyword(
 				TSXKeyword::Let
-					| TSXKeyword::Const | TSXKeyword::Function
-					| TSXKeyword::Class | TSXKeyword::Import
+					| TSXKeyword::Const
+					| TSXKeyword::Enum
+					| TSXKeyword::Function
+					| TSXKeyword::Class
+					| TSXKeyword::Import
 					| TSXKeyword::Export
 			) | TSXToken::At,
 		);
@@ -194,7 +206,7 @@ impl crate::ASTNode for Declaration {
 		match reader.peek().ok_or_else(parse_lexing_error)?.0 {
 			// Const can be either variable declaration or const enum
 			TSXToken::Keyword(TSXKeyword::Const) => {
-				let after_const = reader.peek_n(2);
+				let after_const = reader.peek_n(1);
 				if let Some(Token(TSXToken::Keyword(TSXKeyword::Enum), _)) = after_const {
 					EnumDeclaration::from_reader(reader, state, options)
 						.map(|on| Declaration::Enum(Decorated::new(decorators, on)))
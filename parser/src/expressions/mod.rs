@@ -301,7 +301,7 @@ impl Expression {
 				} else {
 					None
 				};
-				let end = reader.expect_next(TSXToken::OpenParentheses)?;
+				let end = reader.expect_next(TSXToken::CloseParentheses)?;
 				Expression::DynamicImport {
 					path: Box::new(path),
 					options,
@@ -1492,9 +1492,14 @@ impl Expression {
 			Self::NewTarget(..) => {
 				buf.push_str("new.target");
 			}
-			Self::DynamicImport { path, .. } => {
+			Self::DynamicImport { path, options: import_options, .. } => {
 				buf.push_str("import(");
 				path.to_string_from_buffer(buf, options, local);
+				if let Some(import_options) = import_options {
+					buf.push(',');
+					options.push_gap_optionally(buf);
+					import_options.to_string_from_buffer(buf, options, local);
+				}
 				buf.push(')');
 			}
 			Self::PropertyAccess { parent, property, is_optional, position, .. } => {
@@ -1576,11 +1581,9 @@ impl Expression {
 				{
 					to_string_bracketed(type_arguments, ('<', '>'), buf, options, local);
 				}
+				// Arguments are kept (even if there are none) as `new X.y` would construct `X.y`
 				if let Some(arguments) = arguments {
-					// Constructor calls can drop arguments if none
-					if !arguments.is_empty() || options.pretty {
-						arguments_to_string(arguments, buf, options, local);
-					}
+					arguments_to_string(arguments, buf, options, local);
 				}
 			}
 			Self::ArrayLiteral(values, _) => {
@@ -48,9 +48,11 @@ impl crate::Visitable for ObjectLiteralMember {
 		chain: &mut temporary_annex::Annex<crate::Chain>,
 	) {
 		match self {
-			ObjectLiteralMember::Shorthand(_, _)
-			| ObjectLiteralMember::Property { .. }
-			| ObjectLiteralMember::Spread(_, _) => {}
+			ObjectLiteralMember::Shorthand(_, _) => {}
+			ObjectLiteralMember::Property { value: expression, .. }
+			| ObjectLiteralMember::Spread(expression, _) => {
+				expression.visit(visitors, data, options, chain);
+			}
 			ObjectLiteralMember::Method(method) => method.visit(visitors, data, options, chain),
 		}
 	}
@@ -63,9 +65,11 @@ impl crate::Visitable for ObjectLiteralMember {
 		chain: &mut temporary_annex::Annex<crate::Chain>,
 	) {
 		match self {
-			ObjectLiteralMember::Property { .. }
-			| ObjectLiteralMember::Spread(_, _)
-			| ObjectLiteralMember::Shorthand(_, _) => {}
+			ObjectLiteralMember::Shorthand(_, _) => {}
+			ObjectLiteralMember::Property { value: expression, .. }
+			| ObjectLiteralMember::Spread(expression, _) => {
+				expression.visit_mut(visitors, data, options, chain);
+			}
 			ObjectLiteralMember::Method(method) => method.visit_mut(visitors, data, options, chain),
 		}
 	}
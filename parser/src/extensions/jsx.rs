@@ -174,7 +174,16 @@ fn parse_jsx_children(
 		) {
 			return Ok(children);
 		}
-		children.push(JSXNode::from_reader(reader, state, options)?);
+		let mut node = JSXNode::from_reader(reader, state, options)?;
+		// Indentation is not part of the text, but whitespace following an element or expression is
+		if let (JSXNode::TextNode(text, _), Some(JSXNode::LineBreak)) = (&mut node, children.last())
+		{
+			let trimmed = text.trim_start();
+			if trimmed.len() != text.len() {
+				*text = trimmed.to_owned();
+			}
+		}
+		children.push(node);
 	}
 }
 
@@ -252,8 +261,7 @@ impl ASTNode for JSXNode {
 		match token {
 			Token(TSXToken::JSXContent(content), start) => {
 				let position = start.with_length(content.len());
-				// TODO `trim` debatable
-				Ok(JSXNode::TextNode(content.trim_start().into(), position))
+				Ok(JSXNode::TextNode(content, position))
 			}
 			Token(TSXToken::JSXExpressionStart, pos) => {
 				let expression = FunctionArgument::from_reader(reader, state, options)?;
@@ -400,10 +408,11 @@ impl JSXElement {
 					let attribute = if let Some(Token(TSXToken::Spread, _)) = reader.peek() {
 						let spread_token = reader.next().unwrap();
 						let expr = Expression::from_reader(reader, state, options)?;
-						reader.expect_next(TSXToken::CloseBrace)?;
+						reader.expect_next(TSXToken::JSXExpressionEnd)?;
 						JSXAttribute::Spread(expr, spread_token.get_span())
 					} else {
 						let expr = Expression::from_reader(reader, state, options)?;
+						reader.expect_next(TSXToken::JSXExpressionEnd)?;
 						JSXAttribute::Shorthand(expr)
 					};
 					attributes.push(attribute);
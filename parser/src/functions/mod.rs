@@ -264,8 +264,10 @@ where
 		// self.header.visit(visitors, data, options, chain);
 		T::visit_name(&self.name, visitors, data, options, chain);
 		if options.visit_nested_blocks || chain.is_empty() {
-			self.parameters.visit(visitors, data, options, chain);
-			self.body.visit(visitors, data, options, chain);
+			let mut chain =
+				chain.push_annex(crate::visiting::ChainVariable::Function(self.position));
+			self.parameters.visit(visitors, data, options, &mut chain);
+			self.body.visit(visitors, data, options, &mut chain);
 		}
 	}
 
@@ -280,8 +282,10 @@ where
 		// self.header.visit_mut(visitors, data, options, chain);
 		T::visit_name_mut(&mut self.name, visitors, data, options, chain);
 		if options.visit_nested_blocks || chain.is_empty() {
-			self.parameters.visit_mut(visitors, data, options, chain);
-			self.body.visit_mut(visitors, data, options, chain);
+			let mut chain =
+				chain.push_annex(crate::visiting::ChainVariable::Function(self.position));
+			self.parameters.visit_mut(visitors, data, options, &mut chain);
+			self.body.visit_mut(visitors, data, options, &mut chain);
 		}
 	}
 }
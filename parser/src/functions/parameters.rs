@@ -32,6 +32,11 @@ pub trait ParameterVisibility: Send + Sync + Sized + Debug + PartialEq + Clone +
 		state: &mut crate::ParsingState,
 		options: &crate::ParseOptions,
 	) -> Self;
+
+	/// Whether the parameter is also a property of `this` (`constructor(public x)`)
+	fn is_parameter_property(&self) -> bool {
+		false
+	}
 }
 
 impl ParameterVisibility for () {
@@ -64,6 +69,10 @@ impl ParameterVisibility for Option<crate::types::Visibility> {
 			None
 		}
 	}
+
+	fn is_parameter_property(&self) -> bool {
+		self.is_some()
+	}
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Visitable)]
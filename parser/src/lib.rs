@@ -282,6 +282,12 @@ pub struct LocalToStringInformation {
 }
 
 impl LocalToStringInformation {
+	/// For printing nodes from different sources into the same buffer (such as when bundling)
+	#[must_use]
+	pub fn new_under(under: SourceId) -> LocalToStringInformation {
+		LocalToStringInformation { under, depth: 0 }
+	}
+
 	pub(crate) fn next_level(self) -> LocalToStringInformation {
 		LocalToStringInformation { under: self.under, depth: self.depth + 1 }
 	}
@@ -165,7 +165,12 @@ impl<U: PropertyKeyKind> ASTNode for PropertyKey<U> {
 		local: crate::LocalToStringInformation,
 	) {
 		match self {
-			Self::Ident(ident, _pos, _) => buf.push_str(ident.as_str()),
+			Self::Ident(ident, _pos, kind) => {
+				if U::is_private(kind) {
+					buf.push('#');
+				}
+				buf.push_str(ident.as_str());
+			}
 			Self::NumberLiteral(number, _) => buf.push_str(&number.to_string()),
 			Self::StringLiteral(string, quoted, _) => {
 				buf.push(quoted.as_char());
@@ -5,7 +5,7 @@ use crate::{derive_ASTNode, Block};
 
 /// While `Block`, only some items are allowed
 #[apply(derive_ASTNode)]
-#[derive(Debug, Clone, PartialEq, get_field_by_type::GetFieldByType)]
+#[derive(Debug, Clone, PartialEq, Eq, get_field_by_type::GetFieldByType)]
 #[get_field_by_type_target(Span)]
 pub struct Namespace {
 	pub name: String,
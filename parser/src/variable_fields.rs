@@ -402,9 +402,9 @@ impl Visitable for VariableField {
 	) {
 		match self {
 			VariableField::Name(identifier) => {
-				if let VariableIdentifier::Standard(name, _span) = identifier {
+				if let VariableIdentifier::Standard(name, span) = identifier {
 					visitors.visit_variable_mut(
-						&mut MutableVariableOrProperty::VariableFieldName(name),
+						&mut MutableVariableOrProperty::VariableFieldName(name, span),
 						data,
 						chain,
 					);
@@ -316,6 +316,17 @@ mod structures {
 			}
 		}
 
+		/// The position of the innermost function or block. `None` if at the top level of the module
+		#[must_use]
+		pub fn get_innermost_scope(&self) -> Option<Span> {
+			self.0.iter().rev().find_map(|item| match item {
+				ChainVariable::Function(position) | ChainVariable::Block(position) => {
+					Some(*position)
+				}
+				ChainVariable::Module(_) => None,
+			})
+		}
+
 		// TODO get function root. Aka last thing before in top level scope or
 	}
 
@@ -350,7 +361,7 @@ mod structures {
 
 	#[derive(Debug)]
 	pub enum MutableVariableOrProperty<'a> {
-		VariableFieldName(&'a mut String),
+		VariableFieldName(&'a mut String, &'a Span),
 		// TODO these should maybe only be the spread variables
 		ArrayDestructuringMember(&'a mut ArrayDestructuringField),
 		ObjectDestructuringMember(&'a mut WithComment<ObjectDestructuringField>),
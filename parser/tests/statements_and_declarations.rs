@@ -130,6 +130,19 @@ import { a, } from "module-name"
 	Module::from_string(input.to_owned(), Default::default()).unwrap();
 }
 
+#[test]
+fn dynamic_imports() {
+	let input = r#"
+const module = import("./module");
+import("./module").then(console.log);
+import("./module", { with: { type: "json" } })"#
+		.trim_start();
+
+	let module = Module::from_string(input.to_owned(), Default::default()).unwrap();
+	let output = module.to_string(&ToStringOptions::typescript());
+	assert_eq!(output, input);
+}
+
 #[test]
 fn exports() {
 	// Taken from MDN
@@ -291,3 +304,20 @@ let a, b, a1, b1, c, d, rest, pop, push;
 	let output = module.to_string(&ezno_parser::ToStringOptions::typescript());
 	assert_eq!(output, input);
 }
+
+#[test]
+fn statements_are_mapped() {
+	use ezno_parser::source_map::{FileSystem, MapFileStore, NoPathMap};
+
+	let input = "const a = 1;\nconsole.log(a);";
+
+	let mut files = MapFileStore::<NoPathMap>::default();
+	let source = files.new_source_id("main.ts".into(), input.to_owned());
+	let module = Module::from_string(input.to_owned(), Default::default()).unwrap();
+	let (output, source_map) =
+		module.to_string_with_source_map(&ToStringOptions::default(), source, &files);
+
+	assert_eq!(output, "const a = 1;\nconsole.log(a)");
+	// Each statement is mapped to the start of its line, references are mapped to their position
+	assert_eq!(source_map.unwrap().mappings, "AAAA,MAAM;AACN,AAAA,AAAA,YAAY");
+}
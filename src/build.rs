@@ -1,4 +1,5 @@
 use std::{
+	collections::HashMap,
 	mem,
 	path::{Path, PathBuf},
 };
@@ -13,6 +14,7 @@ use parser::{
 pub struct Output {
 	pub output_path: PathBuf,
 	pub content: String,
+	/// Source map JSON. Empty if [`BuildConfig::source_maps`] is [`SourceMaps::None`]
 	pub mappings: String,
 }
 
@@ -24,6 +26,9 @@ pub struct BuildOutput {
 	/// TODO serde
 	#[cfg_attr(target_family = "wasm", serde(skip))]
 	pub fs: MapFileStore<WithPathMap>,
+	/// For `--timings`
+	#[cfg_attr(target_family = "wasm", serde(skip))]
+	pub entry_points: Vec<checker::EntryPointStatistics>,
 }
 
 #[cfg_attr(target_family = "wasm", derive(serde::Serialize, tsify::Tsify))]
@@ -33,12 +38,158 @@ pub struct FailedBuildOutput {
 	/// TODO serde
 	#[cfg_attr(target_family = "wasm", serde(skip))]
 	pub fs: MapFileStore<WithPathMap>,
+	/// For `--timings`
+	#[cfg_attr(target_family = "wasm", serde(skip))]
+	pub entry_points: Vec<checker::EntryPointStatistics>,
 }
 
+#[derive(Default)]
 #[cfg_attr(target_family = "wasm", derive(serde::Deserialize))]
 pub struct BuildConfig {
 	#[cfg_attr(target_family = "wasm", serde(default))]
 	pub strip_whitespace: bool,
+	#[cfg_attr(target_family = "wasm", serde(default))]
+	pub type_check_options: TypeCheckOptions,
+	#[cfg_attr(target_family = "wasm", serde(default))]
+	pub parse_options: parser::ParseOptions,
+	#[cfg_attr(target_family = "wasm", serde(default))]
+	pub source_maps: SourceMaps,
+	#[cfg_attr(target_family = "wasm", serde(default))]
+	pub output_format: OutputFormat,
+	#[cfg_attr(target_family = "wasm", serde(default))]
+	pub no_comments: bool,
+	/// Enables is-expressions, `server` function headers and special JSX attributes
+	#[cfg_attr(target_family = "wasm", serde(default))]
+	pub non_standard_syntax: bool,
+	/// Runs [`DeadCodeEliminator`], [`ConstantFolder`], [`FunctionInliner`], [`ExpressionOptimiser`]
+	/// and [`StatementOptimiser`] after the given transformers
+	///
+	/// [`DeadCodeEliminator`]: crate::transformers::dead_code::DeadCodeEliminator
+	/// [`ConstantFolder`]: crate::transformers::constant_folding::ConstantFolder
+	/// [`FunctionInliner`]: crate::transformers::inlining::FunctionInliner
+	/// [`ExpressionOptimiser`]: crate::transformers::optimisations::ExpressionOptimiser
+	/// [`StatementOptimiser`]: crate::transformers::optimisations::StatementOptimiser
+	#[cfg_attr(target_family = "wasm", serde(default))]
+	pub optimise: bool,
+	#[cfg_attr(target_family = "wasm", serde(default))]
+	pub jsx_runtime: JSXRuntime,
+	/// Where the JSX runtime functions are imported from. Defaults to `react`
+	#[cfg_attr(target_family = "wasm", serde(default))]
+	pub jsx_import_source: Option<String>,
+	#[cfg_attr(target_family = "wasm", serde(default))]
+	pub target: Target,
+	/// Shortens local names with [`crate::transformers::mangle::Mangler`]
+	#[cfg_attr(target_family = "wasm", serde(default))]
+	pub mangle: bool,
+	/// Also shortens `#private` class member names when mangling
+	#[cfg_attr(target_family = "wasm", serde(default))]
+	pub mangle_private_members: bool,
+}
+
+/// How modules are combined. Chunks split off at dynamic `import()`s are always ES modules
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(clippy::upper_case_acronyms)]
+pub enum OutputFormat {
+	/// Keeps the exports of the entry points
+	#[default]
+	ESM,
+	/// Wrapped in a function which is called immediately. Exports are dropped
+	IIFE,
+}
+
+impl std::str::FromStr for OutputFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"esm" => Ok(Self::ESM),
+			"iife" => Ok(Self::IIFE),
+			s => Err(format!("unknown output format '{s}', expected esm or iife")),
+		}
+	}
+}
+
+/// How JSX is turned into function calls. See [`crate::transformers::jsx::JSXTransformer`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JSXRuntime {
+	/// `jsx(...)` and `jsxs(...)` from `<import source>/jsx-runtime`
+	#[default]
+	Automatic,
+	/// `createElement(...)` from `<import source>`
+	Classic,
+}
+
+impl std::str::FromStr for JSXRuntime {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"automatic" => Ok(Self::Automatic),
+			"classic" => Ok(Self::Classic),
+			s => Err(format!("unknown JSX runtime '{s}', expected automatic or classic")),
+		}
+	}
+}
+
+/// The version of ECMAScript the output runs on. Newer syntax is rewritten by
+/// [`crate::transformers::downlevel::Downleveller`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Target {
+	ES2015,
+	ES2016,
+	ES2017,
+	ES2018,
+	ES2019,
+	ES2020,
+	ES2021,
+	ES2022,
+	/// Nothing is lowered
+	#[default]
+	ESNext,
+}
+
+impl std::str::FromStr for Target {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"es2015" | "es6" => Ok(Self::ES2015),
+			"es2016" => Ok(Self::ES2016),
+			"es2017" => Ok(Self::ES2017),
+			"es2018" => Ok(Self::ES2018),
+			"es2019" => Ok(Self::ES2019),
+			"es2020" => Ok(Self::ES2020),
+			"es2021" => Ok(Self::ES2021),
+			"es2022" => Ok(Self::ES2022),
+			"esnext" => Ok(Self::ESNext),
+			s => Err(format!("unknown target '{s}', expected es2015 to es2022 or esnext")),
+		}
+	}
+}
+
+/// [Source maps (v3)](https://sourcemaps.info/spec.html) point back to the original positions,
+/// including of nodes rewritten by transformers (which keep the position of what they replace)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(target_family = "wasm", derive(serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum SourceMaps {
+	#[default]
+	None,
+	/// Written to [`source_map_path`], which the output references
+	External,
+	/// Appended to the output as a data URL
+	Inline,
+}
+
+/// `out.js` -> `out.js.map`
+#[must_use]
+pub fn source_map_path(output_path: &Path) -> PathBuf {
+	let mut path = output_path.as_os_str().to_owned();
+	path.push(".map");
+	path.into()
 }
 
 pub type EznoParsePostCheckVisitors =
@@ -53,45 +204,118 @@ pub struct CheckingOutputWithoutDiagnostics {
 			<checker::synthesis::EznoParser as checker::ASTImplementation>::OwnedModule,
 		>,
 	>,
+	pub variable_usage: checker::VariableUsage,
+	/// Exports of these are kept
+	pub entry_points: Vec<parser::SourceId>,
 }
 
 impl CheckingOutputWithoutDiagnostics {
+	/// Functions added by transformers were never seen by the checker, so are treated as called
 	#[must_use]
 	pub fn is_function_called(&self, function_id: checker::FunctionId) -> bool {
-		self.types.called_functions.contains(&function_id)
+		!self.types.has_function(function_id) || self.types.called_functions.contains(&function_id)
 	}
 }
 
+#[allow(clippy::result_large_err)]
 pub fn build<T: crate::ReadFromFS>(
 	input_paths: Vec<PathBuf>,
 	fs_resolver: &T,
-	type_definition_module: Option<&Path>,
+	definition_files: &[PathBuf],
 	output_path: &Path,
 	config: &BuildConfig,
 	transformers: Option<EznoParsePostCheckVisitors>,
 ) -> Result<BuildOutput, FailedBuildOutput> {
-	// TODO parse options + non_standard_library & non_standard_syntax
 	let type_check_options =
-		TypeCheckOptions { store_expression_type_mappings: true, ..Default::default() };
+		TypeCheckOptions { store_expression_type_mappings: true, ..config.type_check_options };
 
-	let result = crate::check(input_paths, fs_resolver, type_definition_module, type_check_options);
+	let mut parse_options = config.parse_options;
+	if config.non_standard_syntax {
+		parse_options.is_expressions = true;
+		parse_options.custom_function_headers = true;
+		parse_options.special_jsx_attributes = true;
+	}
+
+	let result =
+		crate::check(input_paths, fs_resolver, definition_files, type_check_options, parse_options);
+
+	let entry_points = result
+		.entry_points
+		.iter()
+		.filter_map(|entry_point| result.module_contents.get_source_at_path(&entry_point.path))
+		.collect();
 
 	let mut data = CheckingOutputWithoutDiagnostics {
 		module_contents: result.module_contents,
 		modules: result.modules,
 		types: result.types,
+		variable_usage: result.variable_usage,
+		entry_points,
 	};
 
 	if !result.diagnostics.has_error() {
-		// TODO For all modules
 		let keys = data.modules.keys().cloned().collect::<Vec<_>>();
 
 		let null_module =
 			parser::Module { items: Default::default(), span: parser::source_map::Nullable::NULL };
 
-		let mut outputs = Vec::new();
-
 		let mut transformers = transformers.unwrap_or_default();
+		let jsx_transformer = || crate::transformers::jsx::JSXTransformer {
+			runtime: config.jsx_runtime,
+			import_source: config.jsx_import_source.clone().unwrap_or_else(|| "react".to_owned()),
+		};
+		transformers.expression_visitors_mut.push(Box::new(jsx_transformer()));
+		transformers.block_visitors_mut.push(Box::new(jsx_transformer()));
+		transformers
+			.expression_visitors_mut
+			.push(Box::new(crate::transformers::typescript::TypeScriptTransformer));
+		transformers
+			.statement_visitors_mut
+			.push(Box::new(crate::transformers::typescript::TypeScriptTransformer));
+		transformers
+			.block_visitors_mut
+			.push(Box::new(crate::transformers::typescript::TypeScriptTransformer));
+		transformers
+			.expression_visitors_mut
+			.push(Box::new(crate::transformers::compile_out::CompileOutRemover));
+		transformers
+			.block_visitors_mut
+			.push(Box::new(crate::transformers::compile_out::CompileOutRemover));
+		if config.optimise {
+			use crate::transformers::{
+				constant_folding::ConstantFolder,
+				dead_code::DeadCodeEliminator,
+				inlining::FunctionInliner,
+				optimisations::{ExpressionOptimiser, StatementOptimiser},
+			};
+
+			transformers.expression_visitors_mut.push(Box::new(DeadCodeEliminator));
+			transformers.block_visitors_mut.push(Box::new(DeadCodeEliminator));
+			transformers.expression_visitors_mut.push(Box::new(ConstantFolder));
+			transformers.expression_visitors_mut.push(Box::new(FunctionInliner));
+			transformers.block_visitors_mut.push(Box::new(FunctionInliner));
+			transformers.expression_visitors_mut.push(Box::new(ExpressionOptimiser));
+			transformers.statement_visitors_mut.push(Box::new(StatementOptimiser));
+			transformers.block_visitors_mut.push(Box::new(StatementOptimiser));
+		}
+		// A separate pass so that the other transformers see (and the checker's positions match)
+		// the original syntax
+		let mut downleveller = (config.target != Target::ESNext).then(|| {
+			let mut visitors = EznoParsePostCheckVisitors::default();
+			visitors
+				.block_visitors_mut
+				.push(Box::new(crate::transformers::downlevel::Downleveller::new(config.target)));
+			visitors
+		});
+		// Last, so that it sees the names the other transformers add
+		let mut mangler = config.mangle.then(|| {
+			let mut visitors = EznoParsePostCheckVisitors::default();
+			visitors.block_visitors_mut.push(Box::new(crate::transformers::mangle::Mangler::new(
+				config.mangle_private_members,
+			)));
+			visitors
+		});
+		let mut modules = HashMap::with_capacity(keys.len());
 
 		for source in keys {
 			// Remove the module
@@ -100,8 +324,6 @@ pub fn build<T: crate::ReadFromFS>(
 				null_module.clone(),
 			);
 
-			// TODO bundle using main_module.imports
-
 			module.visit_mut::<CheckingOutputWithoutDiagnostics>(
 				&mut transformers,
 				&mut data,
@@ -109,24 +331,233 @@ pub fn build<T: crate::ReadFromFS>(
 				source,
 			);
 
-			let to_string_options = if config.strip_whitespace {
-				ToStringOptions::minified()
-			} else {
-				ToStringOptions::default()
-			};
+			if let Some(ref mut downleveller) = downleveller {
+				module.visit_mut::<CheckingOutputWithoutDiagnostics>(
+					downleveller,
+					&mut data,
+					&parser::visiting::VisitOptions::default(),
+					source,
+				);
+			}
 
-			let content = parser::ASTNode::to_string(&module, &to_string_options);
+			if let Some(ref mut mangler) = mangler {
+				module.visit_mut::<CheckingOutputWithoutDiagnostics>(
+					mangler,
+					&mut data,
+					&parser::visiting::VisitOptions::default(),
+					source,
+				);
+			}
 
-			outputs.push(Output {
-				output_path: output_path.to_path_buf(),
-				content,
-				// TODO module.to_string_with_map
-				mappings: String::new(),
-			})
+			modules.insert(source, module);
 		}
 
-		Ok(BuildOutput { outputs, diagnostics: result.diagnostics, fs: data.module_contents })
+		let chunks = crate::bundler::bundle(
+			modules,
+			&data.entry_points,
+			&data.module_contents,
+			output_path,
+			config.output_format,
+		);
+
+		let outputs = chunks
+			.into_iter()
+			.map(|chunk| {
+				let mut to_string_options = if config.strip_whitespace {
+					ToStringOptions::minified()
+				} else {
+					ToStringOptions::default()
+				};
+				if config.no_comments {
+					to_string_options.comments = parser::Comments::None;
+				}
+				let with_source_map = !matches!(config.source_maps, SourceMaps::None);
+				let (mut content, source_map) =
+					chunk.to_string(to_string_options, with_source_map, &data.module_contents);
+				let mappings = if let Some(source_map) = source_map {
+					let mappings =
+						source_map_to_json(&source_map, &chunk.output_path, &data.module_contents);
+					append_source_map_url(
+						&mut content,
+						&mappings,
+						&chunk.output_path,
+						config.source_maps,
+					);
+					mappings
+				} else {
+					String::new()
+				};
+				Output { output_path: chunk.output_path, content, mappings }
+			})
+			.collect();
+
+		Ok(BuildOutput {
+			outputs,
+			diagnostics: result.diagnostics,
+			fs: data.module_contents,
+			entry_points: result.entry_points,
+		})
 	} else {
-		Err(FailedBuildOutput { diagnostics: result.diagnostics, fs: data.module_contents })
+		Err(FailedBuildOutput {
+			diagnostics: result.diagnostics,
+			fs: data.module_contents,
+			entry_points: result.entry_points,
+		})
+	}
+}
+
+/// Unlike [`parser::source_map::SourceMap::to_json`], sources are relative to the output (as the
+/// map is next to it) and are sorted, so that maps of bundles are the same between builds
+fn source_map_to_json(
+	source_map: &parser::source_map::SourceMap,
+	output_path: &Path,
+	files: &MapFileStore<WithPathMap>,
+) -> String {
+	use parser::source_map::FileSystem;
+
+	let output_directory = output_path.parent().unwrap_or(Path::new(""));
+	let mut sources: Vec<_> = source_map
+		.sources
+		.iter()
+		.enumerate()
+		.map(|(index, source)| {
+			let (path, content) = files.get_file_path_and_content(*source);
+			let path = relative_path(&path, output_directory);
+			(path.display().to_string().replace('\\', "/"), content, index)
+		})
+		.collect();
+	sources.sort();
+
+	let mut new_indexes = vec![0; sources.len()];
+	for (new_index, (_, _, index)) in sources.iter().enumerate() {
+		new_indexes[*index] = new_index;
+	}
+	let (sources, sources_content): (Vec<_>, Vec<_>) =
+		sources.into_iter().map(|(path, content, _)| (path, content)).unzip();
+
+	serde_json::json!({
+		"version": 3,
+		"file": output_path.file_name().map(|name| name.to_string_lossy()),
+		"sources": sources,
+		"sourcesContent": sources_content,
+		"names": [],
+		"mappings": relative_source_indexes(&source_map.mappings, &new_indexes),
+	})
+	.to_string()
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+	b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The mappings built by [`parser::source_map::SourceMapBuilder`] hold the index of the source of
+/// each segment, where the format expects the difference from the source of the previous segment
+/// (which only matters when there is more than one source). Indexes are also mapped through
+/// `new_indexes`
+fn relative_source_indexes(mappings: &str, new_indexes: &[usize]) -> String {
+	let mut buf = String::with_capacity(mappings.len());
+	let mut last_source = 0;
+	for (line_index, line) in mappings.split(';').enumerate() {
+		if line_index != 0 {
+			buf.push(';');
+		}
+		for (segment_index, segment) in line.split(',').filter(|s| !s.is_empty()).enumerate() {
+			if segment_index != 0 {
+				buf.push(',');
+			}
+			let mut fields = decode_vlq(segment);
+			if let Some(source) = fields.get_mut(1) {
+				#[allow(clippy::cast_possible_wrap)]
+				let new_source = new_indexes[usize::try_from(*source).unwrap()] as isize;
+				*source = new_source - last_source;
+				last_source = new_source;
+			}
+			for field in fields {
+				encode_vlq(field, &mut buf);
+			}
+		}
+	}
+	buf
+}
+
+fn decode_vlq(segment: &str) -> Vec<isize> {
+	let mut fields = Vec::new();
+	let (mut value, mut shift) = (0, 0);
+	for byte in segment.bytes() {
+		#[allow(clippy::cast_possible_wrap)]
+		let digit = BASE64_ALPHABET.iter().position(|c| *c == byte).unwrap() as isize;
+		value += (digit & 0b1_1111) << shift;
+		if digit & 0b10_0000 == 0 {
+			fields.push(if value & 1 == 1 { -(value >> 1) } else { value >> 1 });
+			(value, shift) = (0, 0);
+		} else {
+			shift += 5;
+		}
+	}
+	fields
+}
+
+fn encode_vlq(value: isize, buf: &mut String) {
+	let mut value = if value < 0 { (-value << 1) | 1 } else { value << 1 };
+	loop {
+		let mut digit = value & 0b1_1111;
+		value >>= 5;
+		if value > 0 {
+			digit |= 0b10_0000;
+		}
+		#[allow(clippy::cast_sign_loss)]
+		buf.push(char::from(BASE64_ALPHABET[digit as usize]));
+		if value == 0 {
+			break;
+		}
+	}
+}
+
+fn append_source_map_url(
+	content: &mut String,
+	source_map: &str,
+	output_path: &Path,
+	kind: SourceMaps,
+) {
+	use std::fmt::Write;
+
+	match kind {
+		SourceMaps::None => {}
+		SourceMaps::External => {
+			let map_path = source_map_path(output_path);
+			let name = map_path.file_name().unwrap_or_default().to_string_lossy();
+			let _ = write!(content, "\n//# sourceMappingURL={name}");
+		}
+		SourceMaps::Inline => {
+			use base64::Engine;
+			let encoded = base64::engine::general_purpose::STANDARD.encode(source_map);
+			let _ =
+				write!(content, "\n//# sourceMappingURL=data:application/json;base64,{encoded}");
+		}
 	}
 }
+
+/// Both paths are relative to the same directory (or absolute). `..`s are kept
+fn relative_path(path: &Path, from_directory: &Path) -> PathBuf {
+	use std::path::Component;
+
+	fn normal(path: &Path) -> Vec<Component<'_>> {
+		path.components().filter(|component| !matches!(component, Component::CurDir)).collect()
+	}
+
+	let (path_components, from_components) = (normal(path), normal(from_directory));
+	if path.is_absolute() != from_directory.is_absolute() {
+		return path.to_path_buf();
+	}
+
+	let common = path_components
+		.iter()
+		.zip(&from_components)
+		.take_while(|(left, right)| left == right)
+		.count();
+
+	from_components[common..]
+		.iter()
+		.map(|_| Component::ParentDir)
+		.chain(path_components[common..].iter().copied())
+		.collect()
+}
@@ -0,0 +1,1386 @@
+//! Combining modules into a single output. Modules which are dynamically imported (and not
+//! statically imported by the entry points) are split into separate chunks
+//!
+//! Top level bindings which collide with those of other modules are renamed. References in
+//! nested scopes which declare the same name are left alone
+
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+	path::{Component, Path, PathBuf},
+};
+
+use parser::{
+	declarations::{
+		export::{ExportPart, Exportable},
+		import::ImportedItems,
+		ClassDeclaration, ExportDeclaration, ImportDeclaration, ImportExportName, ImportLocation,
+		ImportPart, StatementFunction, VariableDeclaration, VariableDeclarationItem,
+	},
+	expressions::{
+		assignments::{LHSOfAssignment, VariableOrPropertyAccess},
+		object_literal::ObjectLiteralMember,
+	},
+	functions::FunctionBody,
+	property_key::AlwaysPublic,
+	source_map::{FileSystem, MapFileStore, Nullable, SourceMap, ToString as _, WithPathMap},
+	visiting::{
+		Chain, ImmutableVariableOrProperty, MutableVariableOrProperty, VisitOptions,
+		VisitorMutReceiver, VisitorReceiver,
+	},
+	ASTNode, ArrayDestructuringField, Declaration, Decorated, Expression, LocalToStringInformation,
+	Module, ObjectDestructuringField, PropertyKey, SourceId, Span, StatementOrDeclaration,
+	StatementPosition, ToStringOptions, VariableField, VariableIdentifier, WithComment,
+};
+
+use crate::build::OutputFormat;
+
+/// An output file
+pub(crate) struct Chunk {
+	pub output_path: PathBuf,
+	/// Wrap `body` in an immediately invoked function
+	iife: bool,
+	/// Imports of external modules and other chunks
+	header: Option<Module>,
+	body: Vec<Piece>,
+	/// Exports of the entry points and of bindings used by other chunks
+	footer: Option<Module>,
+}
+
+enum Piece {
+	Module(SourceId, Module),
+	/// Namespace objects for `import * as` and dynamic imports within the chunk
+	Generated(Module),
+}
+
+impl Chunk {
+	pub(crate) fn to_string(
+		&self,
+		mut options: ToStringOptions,
+		with_source_map: bool,
+		files: &MapFileStore<WithPathMap>,
+	) -> (String, Option<SourceMap>) {
+		// Modules are followed by others, so every statement needs to be terminated
+		options.trailing_semicolon = true;
+		let mut buf = parser::source_map::StringWithOptionalSourceMap::new(with_source_map);
+
+		let mut first = true;
+		let mut separate = |buf: &mut parser::source_map::StringWithOptionalSourceMap| {
+			if !std::mem::take(&mut first) && options.pretty {
+				buf.push_new_line();
+			}
+		};
+
+		if let Some(ref header) = self.header {
+			separate(&mut buf);
+			buf.push_str_contains_new_line(&header.to_string(&options));
+		}
+		if self.iife {
+			separate(&mut buf);
+			buf.push_str(if options.pretty { "(() => {" } else { "(()=>{" });
+		}
+		for piece in &self.body {
+			separate(&mut buf);
+			match piece {
+				Piece::Module(source, module) => module.to_string_from_buffer(
+					&mut buf,
+					&options,
+					LocalToStringInformation::new_under(*source),
+				),
+				Piece::Generated(module) => {
+					buf.push_str_contains_new_line(&module.to_string(&options));
+				}
+			}
+		}
+		if self.iife {
+			separate(&mut buf);
+			buf.push_str("})();");
+		}
+		if let Some(ref footer) = self.footer {
+			separate(&mut buf);
+			buf.push_str_contains_new_line(&footer.to_string(&options));
+		}
+		buf.build(files)
+	}
+}
+
+/// `modules` should have already been transformed. Modules not reachable from `entry_points` are
+/// not included. The first chunk is written to `output_path` and the others next to it
+pub(crate) fn bundle(
+	modules: HashMap<SourceId, Module>,
+	entry_points: &[SourceId],
+	files: &MapFileStore<WithPathMap>,
+	output_path: &Path,
+	format: OutputFormat,
+) -> Vec<Chunk> {
+	let paths: HashMap<PathBuf, SourceId> =
+		modules.keys().map(|source| (normalise(&files.get_file_path(*source)), *source)).collect();
+
+	let (analysed, mut modules): (HashMap<_, _>, HashMap<_, _>) = modules
+		.into_iter()
+		.map(|(source, module)| {
+			let (analysed, module) = analyse(source, module, files, &paths);
+			((source, analysed), (source, module))
+		})
+		.unzip();
+
+	let plans = split(entry_points, &analysed, files, output_path, format);
+
+	let mut bundler = Bundler::new(&analysed, &plans, format);
+	let mut states: Vec<ChunkState> = plans.iter().map(|_| ChunkState::default()).collect();
+	let mut bodies = Vec::with_capacity(plans.len());
+
+	for (index, plan) in plans.iter().enumerate() {
+		let mut body = Vec::new();
+		for source in &plan.modules {
+			let info = &analysed[source];
+			let mut renamer = Renamer {
+				names: HashMap::new(),
+				nested: &info.nested,
+				dynamic_imports: HashMap::new(),
+				referenced: HashSet::new(),
+			};
+
+			for name in &info.top_level {
+				let final_name = &bundler.final_names[&(*source, name.clone())];
+				if final_name != name {
+					renamer.names.insert(name.clone(), final_name.clone());
+				}
+			}
+			let mut externals = Vec::new();
+			for (local, dependency, imported) in &info.imports {
+				let Some(binding) =
+					bundler.resolve_import(dependency, imported, &mut HashSet::new())
+				else {
+					continue;
+				};
+				if let Binding::External(ref specifier, ref imported) = binding {
+					externals.push((local, specifier.clone(), imported.clone()));
+				}
+				let name = bundler.binding_name(binding, index, &mut states);
+				renamer.names.insert(local.clone(), name);
+			}
+			for (specifier, target) in &info.dynamic_imports {
+				let replacement = match bundler.chunk_of_entry.get(target) {
+					Some(&chunk) if chunk != index && !plan.modules.contains(target) => {
+						let file_name = plans[chunk].output_path.file_name().unwrap_or_default();
+						Expression::StringLiteral(
+							format!("./{}", file_name.to_string_lossy()),
+							parser::Quoted::Double,
+							Span::NULL,
+						)
+					}
+					_ => {
+						let name =
+							bundler.binding_name(Binding::Namespace(*target), index, &mut states);
+						promise_resolve(name)
+					}
+				};
+				renamer.dynamic_imports.insert(specifier.clone(), replacement);
+			}
+
+			let mut module = if bundler.is_duplicated {
+				modules[source].clone()
+			} else {
+				modules.remove(source).unwrap()
+			};
+			module.visit_mut(&mut renamer, &mut (), &VisitOptions::default(), *source);
+
+			let state = &mut states[index];
+			for specifier in &info.externals {
+				state.add_specifier(specifier);
+			}
+			for (local, specifier, imported) in externals {
+				if renamer.referenced.contains(local.as_str()) {
+					let name = bundler.external_name(&specifier, &imported);
+					state.add_external(specifier, imported, name);
+				}
+			}
+
+			body.push(Piece::Module(*source, module));
+			if bundler.namespaces_needed.contains(source) {
+				let namespace = bundler.namespace_object(*source, index, &mut states);
+				body.push(Piece::Generated(namespace));
+			}
+		}
+		bodies.push(body);
+	}
+
+	// Exports are added last as chunks can use bindings from chunks after them
+	for (index, plan) in plans.iter().enumerate() {
+		if plan.format == OutputFormat::ESM {
+			for entry in &plan.entries {
+				for name in bundler.export_names(*entry) {
+					if let Some(binding) =
+						bundler.resolve_export(*entry, &name, &mut HashSet::new())
+					{
+						if let Binding::External(ref specifier, ref imported) = binding {
+							let local = bundler.external_name(specifier, imported);
+							states[index].add_external(specifier.clone(), imported.clone(), local);
+						}
+						let local = bundler.binding_name(binding, index, &mut states);
+						states[index].exports.entry(name).or_insert(local);
+					}
+				}
+				for dependency in &analysed[entry].star_exports {
+					if let Dependency::External(specifier) = dependency {
+						states[index].star_exports.insert(specifier.clone());
+					}
+				}
+			}
+		}
+	}
+
+	plans
+		.into_iter()
+		.zip(bodies)
+		.zip(states)
+		.map(|((plan, body), state)| {
+			let (header, footer) = state.into_header_and_footer(&bundler.file_names);
+			Chunk {
+				output_path: plan.output_path,
+				iife: plan.format == OutputFormat::IIFE,
+				header,
+				body,
+				footer,
+			}
+		})
+		.collect()
+}
+
+/// `Promise.resolve(name)`, for dynamic imports of modules in the same chunk
+fn promise_resolve(name: String) -> Expression {
+	let function = Expression::PropertyAccess {
+		parent: Box::new(Expression::VariableReference("Promise".to_owned(), Span::NULL)),
+		property: parser::PropertyReference::Standard {
+			property: "resolve".to_owned(),
+			is_private: false,
+		},
+		is_optional: false,
+		position: Span::NULL,
+	};
+	Expression::FunctionCall {
+		function: Box::new(function),
+		type_arguments: None,
+		arguments: vec![parser::expressions::FunctionArgument::Standard(
+			Expression::VariableReference(name, Span::NULL),
+		)],
+		is_optional: false,
+		position: Span::NULL,
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Imported {
+	Name(String),
+	Namespace,
+}
+
+enum Dependency {
+	Bundled(SourceId),
+	External(String),
+}
+
+enum Export {
+	Local(String),
+	From(Dependency, Imported),
+}
+
+enum Binding {
+	Local(SourceId, String),
+	Namespace(SourceId),
+	External(String, Imported),
+}
+
+#[derive(Default)]
+struct Analysed {
+	/// Local name, from and what
+	imports: Vec<(String, Dependency, Imported)>,
+	/// Imported and re-exported from, in order
+	dependencies: Vec<SourceId>,
+	externals: Vec<String>,
+	exports: Vec<(String, Export)>,
+	star_exports: Vec<Dependency>,
+	dynamic_imports: Vec<(String, SourceId)>,
+	top_level: Vec<String>,
+	/// Names declared in functions and blocks, along with the position of the function or block
+	nested: Vec<(Span, String)>,
+	/// For generated names
+	stem: String,
+}
+
+/// Removes imports and exports (recording them) and collects declared names
+fn analyse(
+	source: SourceId,
+	module: Module,
+	files: &MapFileStore<WithPathMap>,
+	paths: &HashMap<PathBuf, SourceId>,
+) -> (Analysed, Module) {
+	let path = files.get_file_path(source);
+	let resolve = |specifier: &str| resolve(&path, specifier, paths);
+
+	let mut analysed = Analysed { stem: identifier_from_path(&path), ..Default::default() };
+	let mut items = Vec::with_capacity(module.items.len());
+	for item in module.items {
+		match item {
+			StatementOrDeclaration::Declaration(Declaration::Import(import)) => {
+				analysed.add_import(import, &resolve);
+			}
+			StatementOrDeclaration::Declaration(Declaration::Export(Decorated {
+				decorators,
+				on,
+				position,
+			})) => {
+				let declaration = analysed.add_export(on, decorators, position, &resolve);
+				items.extend(declaration.map(StatementOrDeclaration::Declaration));
+			}
+			// Left over from the `;` after exports
+			StatementOrDeclaration::Statement(parser::Statement::Empty(_)) => {}
+			item => items.push(item),
+		}
+	}
+
+	let module = Module { items, span: module.span };
+	let mut declarations = Declarations::default();
+	module.visit(&mut declarations, &mut (), &VisitOptions::default(), source);
+
+	analysed.top_level = declarations.top_level;
+	analysed.nested = declarations.nested;
+	for specifier in declarations.dynamic_imports {
+		if let Dependency::Bundled(target) = resolve(&specifier) {
+			analysed.dynamic_imports.push((specifier, target));
+		}
+	}
+
+	(analysed, module)
+}
+
+impl Analysed {
+	fn add_dependency(&mut self, dependency: &Dependency) {
+		match dependency {
+			Dependency::Bundled(source) => {
+				if !self.dependencies.contains(source) {
+					self.dependencies.push(*source);
+				}
+			}
+			Dependency::External(specifier) => {
+				if !self.externals.contains(specifier) {
+					self.externals.push(specifier.clone());
+				}
+			}
+		}
+	}
+
+	fn add_import(&mut self, import: ImportDeclaration, resolve: &impl Fn(&str) -> Dependency) {
+		if import.is_type_annotation_import_only {
+			return;
+		}
+		let ImportLocation::Quoted(ref specifier, _) = import.from else { return };
+		let dependency = resolve(specifier);
+		self.add_dependency(&dependency);
+
+		let mut add = |local: String, imported: Imported| {
+			let dependency = match dependency {
+				Dependency::Bundled(source) => Dependency::Bundled(source),
+				Dependency::External(ref specifier) => Dependency::External(specifier.clone()),
+			};
+			self.imports.push((local, dependency, imported));
+		};
+
+		if let Some(VariableIdentifier::Standard(local, _)) = import.default {
+			add(local, Imported::Name("default".to_owned()));
+		}
+		match import.items {
+			ImportedItems::All { under: VariableIdentifier::Standard(local, _) } => {
+				add(local, Imported::Namespace);
+			}
+			ImportedItems::Parts(Some(parts)) => {
+				for (imported, local) in parts.iter().filter_map(import_part_names) {
+					add(local, Imported::Name(imported));
+				}
+			}
+			ImportedItems::All { .. } | ImportedItems::Parts(None) => {}
+		}
+	}
+
+	/// Returns what is left of the declaration
+	fn add_export(
+		&mut self,
+		export: ExportDeclaration,
+		decorators: Vec<parser::Decorator>,
+		position: Span,
+		resolve: &impl Fn(&str) -> Dependency,
+	) -> Option<Declaration> {
+		match export {
+			ExportDeclaration::Variable { exported, .. } => match exported {
+				Exportable::Variable(declaration) => {
+					let mut names = Vec::new();
+					match declaration {
+						VariableDeclaration::ConstDeclaration { ref declarations, .. } => {
+							for item in declarations {
+								variable_field_names(item.name.get_ast_ref(), &mut names);
+							}
+						}
+						VariableDeclaration::LetDeclaration { ref declarations, .. } => {
+							for item in declarations {
+								variable_field_names(item.name.get_ast_ref(), &mut names);
+							}
+						}
+					}
+					self.exports
+						.extend(names.into_iter().map(|name| (name.clone(), Export::Local(name))));
+					Some(Declaration::Variable(declaration))
+				}
+				Exportable::Function(function) => {
+					if let Some(name) = function.name.identifier.as_option_str() {
+						self.exports.push((name.to_owned(), Export::Local(name.to_owned())));
+					}
+					Some(Declaration::Function(Decorated { decorators, on: function, position }))
+				}
+				Exportable::Class(class) => {
+					if let Some(name) = class.name.identifier.as_option_str() {
+						self.exports.push((name.to_owned(), Export::Local(name.to_owned())));
+					}
+					Some(Declaration::Class(Decorated { decorators, on: class, position }))
+				}
+				Exportable::Interface(interface) => {
+					Some(Declaration::Interface(Decorated { decorators, on: interface, position }))
+				}
+				Exportable::TypeAlias(alias) => Some(Declaration::TypeAlias(alias)),
+				// These are lowered by `transformers::typescript` before bundling
+				Exportable::Enum(r#enum) => {
+					self.exports.push((r#enum.name.clone(), Export::Local(r#enum.name.clone())));
+					Some(Declaration::Enum(Decorated { decorators, on: r#enum, position }))
+				}
+				Exportable::Namespace(namespace) => {
+					self.exports
+						.push((namespace.name.clone(), Export::Local(namespace.name.clone())));
+					Some(Declaration::Namespace(namespace))
+				}
+				Exportable::Parts(parts) => {
+					for (local, exported) in parts.iter().filter_map(export_part_names) {
+						self.exports.push((exported, Export::Local(local)));
+					}
+					None
+				}
+				Exportable::ImportAll { r#as, from: ImportLocation::Quoted(specifier, _) } => {
+					let dependency = resolve(&specifier);
+					self.add_dependency(&dependency);
+					if let Some(VariableIdentifier::Standard(name, _)) = r#as {
+						self.exports.push((name, Export::From(dependency, Imported::Namespace)));
+					} else {
+						self.star_exports.push(dependency);
+					}
+					None
+				}
+				Exportable::ImportParts {
+					parts,
+					from: ImportLocation::Quoted(specifier, _),
+					type_definitions_only: false,
+				} => {
+					for (imported, exported) in parts.iter().filter_map(export_part_names) {
+						let dependency = resolve(&specifier);
+						self.add_dependency(&dependency);
+						self.exports
+							.push((exported, Export::From(dependency, Imported::Name(imported))));
+					}
+					None
+				}
+				Exportable::ImportAll { .. } | Exportable::ImportParts { .. } => None,
+			},
+			ExportDeclaration::Default { expression, position } => match *expression {
+				Expression::VariableReference(name, _) => {
+					self.exports.push(("default".to_owned(), Export::Local(name)));
+					None
+				}
+				// Named functions and classes can be referenced in the module
+				Expression::ExpressionFunction(function) if function.name.0.is_some() => {
+					let identifier = function.name.0.unwrap();
+					let name = identifier.as_option_str().unwrap_or_default().to_owned();
+					self.exports.push(("default".to_owned(), Export::Local(name)));
+					let function = StatementFunction {
+						header: function.header,
+						name: StatementPosition { identifier, declare: false },
+						type_parameters: function.type_parameters,
+						parameters: function.parameters,
+						return_type: function.return_type,
+						body: FunctionBody(Some(function.body)),
+						position: function.position,
+					};
+					Some(Declaration::Function(Decorated { decorators, on: function, position }))
+				}
+				Expression::ClassExpression(class) if class.name.0.is_some() => {
+					let identifier = class.name.0.unwrap();
+					let name = identifier.as_option_str().unwrap_or_default().to_owned();
+					self.exports.push(("default".to_owned(), Export::Local(name)));
+					let class = ClassDeclaration {
+						name: StatementPosition { identifier, declare: false },
+						type_parameters: class.type_parameters,
+						extends: class.extends,
+						implements: class.implements,
+						members: class.members,
+						position: class.position,
+					};
+					Some(Declaration::Class(Decorated { decorators, on: class, position }))
+				}
+				expression => {
+					let name = format!("{}_default", self.stem);
+					self.exports.push(("default".to_owned(), Export::Local(name.clone())));
+					let item = VariableDeclarationItem {
+						name: WithComment::None(VariableField::Name(VariableIdentifier::Standard(
+							name, position,
+						))),
+						type_annotation: None,
+						expression,
+						position,
+					};
+					Some(Declaration::Variable(VariableDeclaration::ConstDeclaration {
+						declarations: vec![item],
+						position,
+					}))
+				}
+			},
+			// Only a type
+			ExportDeclaration::DefaultFunction { .. } => None,
+		}
+	}
+}
+
+/// (imported, local)
+pub(crate) fn import_part_names(part: &ImportPart) -> Option<(String, String)> {
+	match part {
+		ImportPart::Name(VariableIdentifier::Standard(name, _)) => {
+			Some((name.clone(), name.clone()))
+		}
+		ImportPart::NameWithAlias {
+			name,
+			alias: ImportExportName::Reference(alias) | ImportExportName::Quoted(alias, _),
+			..
+		} => Some((alias.clone(), name.clone())),
+		ImportPart::PrefixComment(_, Some(part), _) | ImportPart::PostfixComment(part, _, _) => {
+			import_part_names(part)
+		}
+		_ => None,
+	}
+}
+
+/// (local, exported)
+pub(crate) fn export_part_names(part: &ExportPart) -> Option<(String, String)> {
+	match part {
+		ExportPart::Name(VariableIdentifier::Standard(name, _)) => {
+			Some((name.clone(), name.clone()))
+		}
+		ExportPart::NameWithAlias {
+			name,
+			alias: ImportExportName::Reference(alias) | ImportExportName::Quoted(alias, _),
+			..
+		} => Some((name.clone(), alias.clone())),
+		ExportPart::PrefixComment(_, Some(part), _) | ExportPart::PostfixComment(part, _, _) => {
+			export_part_names(part)
+		}
+		_ => None,
+	}
+}
+
+pub(crate) fn variable_field_names(field: &VariableField, names: &mut Vec<String>) {
+	match field {
+		VariableField::Name(VariableIdentifier::Standard(name, _)) => names.push(name.clone()),
+		VariableField::Name(VariableIdentifier::Marker(..)) => {}
+		VariableField::Array(items, _) => {
+			for item in items {
+				match item.get_ast_ref() {
+					ArrayDestructuringField::Spread(field, _)
+					| ArrayDestructuringField::Name(field, _) => {
+						variable_field_names(field, names);
+					}
+					ArrayDestructuringField::Comment { .. } | ArrayDestructuringField::None => {}
+				}
+			}
+		}
+		VariableField::Object(items, _) => {
+			for item in items {
+				match item.get_ast_ref() {
+					ObjectDestructuringField::Name(VariableIdentifier::Standard(name, _), ..)
+					| ObjectDestructuringField::Spread(VariableIdentifier::Standard(name, _), _) => {
+						names.push(name.clone());
+					}
+					ObjectDestructuringField::Map { name, .. } => {
+						variable_field_names(name.get_ast_ref(), names);
+					}
+					ObjectDestructuringField::Name(..) | ObjectDestructuringField::Spread(..) => {}
+				}
+			}
+		}
+	}
+}
+
+/// Only relative specifiers are bundled, using the same resolution as the checker
+fn resolve(importer: &Path, specifier: &str, paths: &HashMap<PathBuf, SourceId>) -> Dependency {
+	let bundled = specifier.starts_with('.').then(|| {
+		let path = normalise(&importer.parent().unwrap_or(Path::new("")).join(specifier));
+		if path.extension().is_some() {
+			paths.get(&path).copied()
+		} else {
+			["ts", "tsx", "js"].iter().find_map(|ext| paths.get(&path.with_extension(ext)).copied())
+		}
+	});
+	match bundled.flatten() {
+		Some(source) => Dependency::Bundled(source),
+		None => Dependency::External(specifier.to_owned()),
+	}
+}
+
+/// Removes `.` and resolves `..` where possible
+fn normalise(path: &Path) -> PathBuf {
+	let mut normalised = PathBuf::new();
+	for component in path.components() {
+		match component {
+			Component::CurDir => {}
+			Component::ParentDir
+				if matches!(normalised.components().next_back(), Some(Component::Normal(_))) =>
+			{
+				normalised.pop();
+			}
+			component => normalised.push(component),
+		}
+	}
+	normalised
+}
+
+fn identifier_from_path(path: &Path) -> String {
+	let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+	let mut identifier: String = stem
+		.chars()
+		.map(|chr| if chr.is_alphanumeric() || chr == '$' { chr } else { '_' })
+		.collect();
+	if identifier.is_empty() || identifier.starts_with(|chr: char| chr.is_ascii_digit()) {
+		identifier.insert(0, '_');
+	}
+	identifier
+}
+
+#[derive(Default)]
+struct Declarations {
+	top_level: Vec<String>,
+	nested: Vec<(Span, String)>,
+	dynamic_imports: Vec<String>,
+}
+
+impl VisitorReceiver<()> for Declarations {
+	fn visit_variable(
+		&mut self,
+		variable: &ImmutableVariableOrProperty,
+		_data: &mut (),
+		chain: &Chain,
+	) {
+		let name = match variable {
+			// Not visited otherwise
+			ImmutableVariableOrProperty::ArrayDestructuringMember(
+				ArrayDestructuringField::Spread(
+					VariableField::Name(VariableIdentifier::Standard(name, _)),
+					_,
+				),
+			) => name.as_str(),
+			variable => {
+				let Some(name) = variable.get_variable_name() else { return };
+				name
+			}
+		};
+		if let Some(scope) = chain.get_innermost_scope() {
+			self.nested.push((scope, name.to_owned()));
+		} else if !self.top_level.iter().any(|existing| existing == name) {
+			self.top_level.push(name.to_owned());
+		}
+	}
+
+	fn visit_expression(&mut self, expression: &Expression, _data: &mut (), _chain: &Chain) {
+		if let Expression::DynamicImport { path, .. } = expression {
+			if let Expression::StringLiteral(specifier, ..) = &**path {
+				self.dynamic_imports.push(specifier.clone());
+			}
+		}
+	}
+}
+
+/// Renames top level declarations and references to them
+struct Renamer<'a> {
+	names: HashMap<String, String>,
+	nested: &'a [(Span, String)],
+	/// Specifier to the new specifier (a chunk path) or the expression replacing the `import(...)`
+	dynamic_imports: HashMap<String, Expression>,
+	/// Names (before renaming) which are referenced
+	referenced: HashSet<String>,
+}
+
+impl Renamer<'_> {
+	fn rename_reference(&mut self, name: &mut String, position: Span) {
+		let shadowed = self.nested.iter().any(|(scope, declared)| {
+			declared == name && scope.start <= position.start && position.end <= scope.end
+		});
+		if !shadowed {
+			if let Some(new_name) = self.names.get(name.as_str()) {
+				self.referenced.insert(std::mem::replace(name, new_name.clone()));
+			} else {
+				self.referenced.insert(name.clone());
+			}
+		}
+	}
+
+	fn rename_declaration(&self, name: &mut String) {
+		if let Some(new_name) = self.names.get(name.as_str()) {
+			name.clone_from(new_name);
+		}
+	}
+}
+
+impl VisitorMutReceiver<()> for Renamer<'_> {
+	fn visit_expression_mut(
+		&mut self,
+		expression: &mut Expression,
+		_data: &mut (),
+		_chain: &Chain,
+	) {
+		match expression {
+			Expression::VariableReference(name, position)
+			| Expression::Assignment {
+				lhs:
+					LHSOfAssignment::VariableOrPropertyAccess(VariableOrPropertyAccess::Variable(
+						name,
+						position,
+					)),
+				..
+			}
+			| Expression::BinaryAssignmentOperation {
+				lhs: VariableOrPropertyAccess::Variable(name, position),
+				..
+			}
+			| Expression::UnaryPrefixAssignmentOperation {
+				operand: VariableOrPropertyAccess::Variable(name, position),
+				..
+			}
+			| Expression::UnaryPostfixAssignmentOperation {
+				operand: VariableOrPropertyAccess::Variable(name, position),
+				..
+			} => {
+				let position = *position;
+				self.rename_reference(name, position);
+			}
+			Expression::ObjectLiteral(literal) => {
+				// Expanded so the value can be renamed (when visiting it after this)
+				for member in &mut literal.members {
+					if let ObjectLiteralMember::Shorthand(name, position) = member {
+						if self.names.contains_key(name.as_str()) {
+							let (name, position) = (name.clone(), *position);
+							*member = ObjectLiteralMember::Property {
+								key: WithComment::None(PropertyKey::Ident(
+									name.clone(),
+									position,
+									AlwaysPublic,
+								)),
+								assignment: false,
+								value: Expression::VariableReference(name, position),
+								position,
+							};
+						} else {
+							self.referenced.insert(name.clone());
+						}
+					}
+				}
+			}
+			Expression::DynamicImport { path, .. } => {
+				if let Expression::StringLiteral(specifier, ..) = &**path {
+					match self.dynamic_imports.get(specifier) {
+						// Chunk path, so keep the `import(...)`
+						Some(replacement @ Expression::StringLiteral(..)) => {
+							**path = replacement.clone();
+						}
+						Some(replacement) => {
+							*expression = replacement.clone();
+						}
+						None => {}
+					}
+				}
+			}
+			_ => {}
+		}
+	}
+
+	fn visit_variable_mut(
+		&mut self,
+		variable: &mut MutableVariableOrProperty,
+		_data: &mut (),
+		chain: &Chain,
+	) {
+		if chain.get_innermost_scope().is_some() {
+			return;
+		}
+		match variable {
+			MutableVariableOrProperty::VariableFieldName(name, _) => self.rename_declaration(name),
+			MutableVariableOrProperty::FunctionName(Some(VariableIdentifier::Standard(
+				name,
+				_,
+			)))
+			| MutableVariableOrProperty::ClassName(Some(VariableIdentifier::Standard(name, _)))
+			| MutableVariableOrProperty::ArrayDestructuringMember(
+				ArrayDestructuringField::Spread(
+					VariableField::Name(VariableIdentifier::Standard(name, _)),
+					_,
+				),
+			) => self.rename_declaration(name),
+			MutableVariableOrProperty::ObjectDestructuringMember(member) => {
+				match member.get_ast_mut() {
+					ObjectDestructuringField::Name(
+						VariableIdentifier::Standard(name, name_position),
+						default_value,
+						position,
+					) => {
+						// Expanded to `{ name: new_name }`. The new name is not visited again
+						if let Some(new_name) = self.names.get(name.as_str()) {
+							let field = ObjectDestructuringField::Map {
+								from: PropertyKey::Ident(
+									name.clone(),
+									*name_position,
+									AlwaysPublic,
+								),
+								name: WithComment::None(VariableField::Name(
+									VariableIdentifier::Standard(new_name.clone(), *name_position),
+								)),
+								default_value: default_value.take(),
+								position: *position,
+							};
+							*member.get_ast_mut() = field;
+						}
+					}
+					ObjectDestructuringField::Spread(VariableIdentifier::Standard(name, _), _) => {
+						self.rename_declaration(name);
+					}
+					_ => {}
+				}
+			}
+			_ => {}
+		}
+	}
+}
+
+struct ChunkPlan {
+	/// Exports of these are kept
+	entries: Vec<SourceId>,
+	/// In the order they are output
+	modules: Vec<SourceId>,
+	output_path: PathBuf,
+	format: OutputFormat,
+}
+
+/// The first chunk contains the entry points and the modules they statically import. Each
+/// dynamically imported module not already in a chunk starts a new chunk. For [`OutputFormat::ESM`]
+/// modules are only in one chunk (other chunks import from it). For [`OutputFormat::IIFE`], which
+/// cannot export, modules are duplicated across chunks
+fn split(
+	entry_points: &[SourceId],
+	analysed: &HashMap<SourceId, Analysed>,
+	files: &MapFileStore<WithPathMap>,
+	output_path: &Path,
+	format: OutputFormat,
+) -> Vec<ChunkPlan> {
+	fn order(
+		source: SourceId,
+		analysed: &HashMap<SourceId, Analysed>,
+		skip: &HashMap<SourceId, usize>,
+		visited: &mut HashSet<SourceId>,
+		modules: &mut Vec<SourceId>,
+	) {
+		if skip.contains_key(&source) || !visited.insert(source) {
+			return;
+		}
+		if let Some(info) = analysed.get(&source) {
+			for dependency in &info.dependencies {
+				order(*dependency, analysed, skip, visited, modules);
+			}
+			modules.push(source);
+		}
+	}
+
+	let shared = format == OutputFormat::ESM;
+	let mut plans = vec![ChunkPlan {
+		entries: entry_points
+			.iter()
+			.copied()
+			.filter(|entry| analysed.contains_key(entry))
+			.collect(),
+		modules: Vec::new(),
+		output_path: output_path.to_owned(),
+		format,
+	}];
+	let mut owners = HashMap::<SourceId, usize>::new();
+	let mut file_names: HashSet<PathBuf> = HashSet::from([output_path.to_owned()]);
+
+	let mut index = 0;
+	while index < plans.len() {
+		let mut modules = Vec::new();
+		let mut visited = HashSet::new();
+		let no_skip = HashMap::new();
+		for entry in &plans[index].entries {
+			order(
+				*entry,
+				analysed,
+				if shared { &owners } else { &no_skip },
+				&mut visited,
+				&mut modules,
+			);
+		}
+		for source in &modules {
+			owners.entry(*source).or_insert(index);
+		}
+
+		for source in &modules {
+			for (_, target) in &analysed[source].dynamic_imports {
+				let in_chunk =
+					if shared { owners.contains_key(target) } else { modules.contains(target) };
+				let has_chunk = plans.iter().any(|plan| plan.entries.first() == Some(target));
+				if !in_chunk && !has_chunk {
+					let output_path = chunk_path(output_path, files, *target, &mut file_names);
+					plans.push(ChunkPlan {
+						entries: vec![*target],
+						modules: Vec::new(),
+						output_path,
+						format: OutputFormat::ESM,
+					});
+				}
+			}
+		}
+		plans[index].modules = modules;
+		index += 1;
+	}
+
+	// Entry was statically imported by an earlier chunk
+	plans.retain(|plan| !plan.modules.is_empty() || plan.output_path == output_path);
+	plans
+}
+
+/// Next to the output, named after the module
+fn chunk_path(
+	output_path: &Path,
+	files: &MapFileStore<WithPathMap>,
+	source: SourceId,
+	taken: &mut HashSet<PathBuf>,
+) -> PathBuf {
+	let directory = output_path.parent().unwrap_or(Path::new(""));
+	let extension = output_path.extension().map_or("js".into(), |ext| ext.to_string_lossy());
+	let stem = identifier_from_path(&files.get_file_path(source));
+
+	let mut path = directory.join(format!("{stem}.{extension}"));
+	let mut counter = 0;
+	while !taken.insert(path.clone()) {
+		counter += 1;
+		path = directory.join(format!("{stem}-{counter}.{extension}"));
+	}
+	path
+}
+
+/// Names are unique across all chunks
+struct Bundler<'a> {
+	analysed: &'a HashMap<SourceId, Analysed>,
+	/// The chunk each module is output in. For duplicated modules, the first
+	owners: HashMap<SourceId, usize>,
+	chunk_of_entry: HashMap<SourceId, usize>,
+	file_names: Vec<String>,
+	is_duplicated: bool,
+	used: HashSet<String>,
+	final_names: HashMap<(SourceId, String), String>,
+	external_names: HashMap<(String, Imported), String>,
+	namespace_names: HashMap<SourceId, String>,
+	namespaces_needed: HashSet<SourceId>,
+}
+
+impl<'a> Bundler<'a> {
+	fn new(
+		analysed: &'a HashMap<SourceId, Analysed>,
+		plans: &[ChunkPlan],
+		format: OutputFormat,
+	) -> Self {
+		let mut owners = HashMap::new();
+		for (index, plan) in plans.iter().enumerate() {
+			for source in &plan.modules {
+				owners.entry(*source).or_insert(index);
+			}
+		}
+		let chunk_of_entry = plans
+			.iter()
+			.enumerate()
+			.skip(1)
+			.map(|(index, plan)| (plan.entries[0], index))
+			.collect();
+		let file_names = plans
+			.iter()
+			.map(|plan| {
+				plan.output_path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+			})
+			.collect();
+
+		let mut bundler = Self {
+			analysed,
+			owners,
+			chunk_of_entry,
+			file_names,
+			is_duplicated: format == OutputFormat::IIFE,
+			used: HashSet::new(),
+			final_names: HashMap::new(),
+			external_names: HashMap::new(),
+			namespace_names: HashMap::new(),
+			namespaces_needed: HashSet::new(),
+		};
+
+		// Namespace objects (for `import * as` and dynamic imports in the same chunk)
+		for (index, plan) in plans.iter().enumerate() {
+			for source in &plan.modules {
+				let info = &analysed[source];
+				for (_, dependency, imported) in &info.imports {
+					if let (Dependency::Bundled(source), Imported::Namespace) =
+						(dependency, imported)
+					{
+						bundler.namespaces_needed.insert(*source);
+					}
+				}
+				for (_, export) in &info.exports {
+					if let Export::From(Dependency::Bundled(source), Imported::Namespace) = export {
+						bundler.namespaces_needed.insert(*source);
+					}
+				}
+				for (_, target) in &info.dynamic_imports {
+					let is_other_chunk = bundler
+						.chunk_of_entry
+						.get(target)
+						.is_some_and(|chunk| *chunk != index && !plan.modules.contains(target));
+					if !is_other_chunk {
+						bundler.namespaces_needed.insert(*target);
+					}
+				}
+			}
+		}
+
+		bundler.allocate_names(plans);
+		bundler
+	}
+
+	/// Modules are visited from the entry points, so they keep their names
+	fn allocate_names(&mut self, plans: &[ChunkPlan]) {
+		let mut importers_nested: HashMap<SourceId, HashSet<&str>> = HashMap::new();
+		for (source, info) in self.analysed {
+			for dependency in &info.dependencies {
+				importers_nested
+					.entry(*dependency)
+					.or_default()
+					.extend(info.nested.iter().map(|(_, name)| name.as_str()));
+			}
+			importers_nested.entry(*source).or_default();
+		}
+
+		let mut allocated = HashSet::new();
+		for plan in plans {
+			for source in plan.modules.iter().rev() {
+				if !allocated.insert(*source) {
+					continue;
+				}
+				let info = &self.analysed[source];
+				let own: HashSet<&str> = info
+					.top_level
+					.iter()
+					.chain(info.imports.iter().map(|(local, ..)| local))
+					.map(String::as_str)
+					.collect();
+				let nested = &importers_nested[source];
+
+				for name in &info.top_level {
+					let final_name = unique(name, &mut self.used, |candidate| {
+						(candidate != name && own.contains(candidate)) || nested.contains(candidate)
+					});
+					self.final_names.insert((*source, name.clone()), final_name);
+				}
+				for (local, dependency, imported) in &info.imports {
+					if let Dependency::External(specifier) = dependency {
+						let key = (specifier.clone(), imported.clone());
+						if !self.external_names.contains_key(&key) {
+							let name = unique(local, &mut self.used, |candidate| {
+								(candidate != local && own.contains(candidate))
+									|| nested.contains(candidate)
+							});
+							self.external_names.insert(key, name);
+						}
+					}
+				}
+			}
+		}
+	}
+
+	fn resolve_export(
+		&self,
+		source: SourceId,
+		name: &str,
+		seen: &mut HashSet<(SourceId, String)>,
+	) -> Option<Binding> {
+		if !seen.insert((source, name.to_owned())) {
+			return None;
+		}
+		let info = self.analysed.get(&source)?;
+		if let Some((_, export)) = info.exports.iter().find(|(exported, _)| exported == name) {
+			match export {
+				Export::Local(local) => {
+					if let Some((_, dependency, imported)) =
+						info.imports.iter().find(|(imported_as, ..)| imported_as == local)
+					{
+						self.resolve_import(dependency, imported, seen)
+					} else {
+						// Otherwise is a type
+						info.top_level
+							.contains(local)
+							.then(|| Binding::Local(source, local.clone()))
+					}
+				}
+				Export::From(dependency, imported) => {
+					self.resolve_import(dependency, imported, seen)
+				}
+			}
+		} else if name == "default" {
+			None
+		} else {
+			info.star_exports.iter().find_map(|dependency| match dependency {
+				Dependency::Bundled(source) => self.resolve_export(*source, name, seen),
+				Dependency::External(_) => None,
+			})
+		}
+	}
+
+	fn resolve_import(
+		&self,
+		dependency: &Dependency,
+		imported: &Imported,
+		seen: &mut HashSet<(SourceId, String)>,
+	) -> Option<Binding> {
+		match (dependency, imported) {
+			(Dependency::Bundled(source), Imported::Name(name)) => {
+				self.resolve_export(*source, name, seen)
+			}
+			(Dependency::Bundled(source), Imported::Namespace) => Some(Binding::Namespace(*source)),
+			(Dependency::External(specifier), imported) => {
+				Some(Binding::External(specifier.clone(), imported.clone()))
+			}
+		}
+	}
+
+	/// Exported names, including those from `export * from`
+	fn export_names(&self, source: SourceId) -> Vec<String> {
+		fn collect(
+			bundler: &Bundler,
+			source: SourceId,
+			names: &mut Vec<String>,
+			visited: &mut HashSet<SourceId>,
+		) {
+			let Some(info) = bundler.analysed.get(&source).filter(|_| visited.insert(source))
+			else {
+				return;
+			};
+			for (name, _) in &info.exports {
+				if !names.contains(name) {
+					names.push(name.clone());
+				}
+			}
+			for dependency in &info.star_exports {
+				if let Dependency::Bundled(source) = dependency {
+					let mut from = Vec::new();
+					collect(bundler, *source, &mut from, visited);
+					names.extend(from.into_iter().filter(|name| name != "default"));
+				}
+			}
+		}
+
+		let mut names = Vec::new();
+		collect(self, source, &mut names, &mut HashSet::new());
+		let mut seen = HashSet::new();
+		names.retain(|name| seen.insert(name.clone()));
+		names
+	}
+
+	/// Name of `binding` in chunk `index`. Adds an import (and export) if declared in another chunk
+	fn binding_name(
+		&mut self,
+		binding: Binding,
+		index: usize,
+		states: &mut [ChunkState],
+	) -> String {
+		let (source, name) = match binding {
+			Binding::Local(source, local) => {
+				let name = self.final_names.get(&(source, local.clone())).cloned().unwrap_or(local);
+				(source, name)
+			}
+			Binding::Namespace(source) => (source, self.namespace_name(source)),
+			Binding::External(specifier, imported) => {
+				return self.external_name(&specifier, &imported);
+			}
+		};
+		if let Some(&owner) = self.owners.get(&source).filter(|_| !self.is_duplicated) {
+			if owner != index {
+				states[index].imports_from.entry(owner).or_default().insert(name.clone());
+				states[owner].exports.entry(name.clone()).or_insert_with(|| name.clone());
+			}
+		}
+		name
+	}
+
+	fn external_name(&mut self, specifier: &str, imported: &Imported) -> String {
+		let key = (specifier.to_owned(), imported.clone());
+		if let Some(name) = self.external_names.get(&key) {
+			return name.clone();
+		}
+		let base = match imported {
+			Imported::Name(name) if name != "default" => name.clone(),
+			_ => identifier_from_path(Path::new(specifier)),
+		};
+		let name = unique(&base, &mut self.used, |_| false);
+		self.external_names.insert(key, name.clone());
+		name
+	}
+
+	fn namespace_name(&mut self, source: SourceId) -> String {
+		if let Some(name) = self.namespace_names.get(&source) {
+			return name.clone();
+		}
+		let base = format!("{}_namespace", self.analysed[&source].stem);
+		let name = unique(&base, &mut self.used, |_| false);
+		self.namespace_names.insert(source, name.clone());
+		name
+	}
+
+	/// Getters keep bindings live
+	fn namespace_object(
+		&mut self,
+		source: SourceId,
+		index: usize,
+		states: &mut [ChunkState],
+	) -> Module {
+		let name = self.namespace_name(source);
+		let mut properties = Vec::new();
+		for export in self.export_names(source) {
+			let Some(binding) = self.resolve_export(source, &export, &mut HashSet::new()) else {
+				continue;
+			};
+			if let Binding::External(ref specifier, ref imported) = binding {
+				let local = self.external_name(specifier, imported);
+				states[index].add_external(specifier.clone(), imported.clone(), local);
+			}
+			let local = self.binding_name(binding, index, states);
+			properties.push(format!("get {}() {{ return {local} }}", property_key(&export)));
+		}
+		let code = format!("const {name} = {{ {} }}", properties.join(", "));
+		Module::from_string(code, Default::default()).expect("generated namespace")
+	}
+}
+
+pub(crate) fn unique(
+	name: &str,
+	used: &mut HashSet<String>,
+	avoid: impl Fn(&str) -> bool,
+) -> String {
+	let mut candidate = name.to_owned();
+	let mut counter = 0;
+	while used.contains(&candidate) || avoid(&candidate) {
+		counter += 1;
+		candidate = format!("{name}${counter}");
+	}
+	used.insert(candidate.clone());
+	candidate
+}
+
+fn property_key(name: &str) -> String {
+	let is_identifier =
+		name.chars().next().is_some_and(|chr| chr.is_alphabetic() || chr == '_' || chr == '$')
+			&& name.chars().all(|chr| chr.is_alphanumeric() || chr == '_' || chr == '$');
+	if is_identifier {
+		name.to_owned()
+	} else {
+		quoted(name)
+	}
+}
+
+fn quoted(value: &str) -> String {
+	format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[derive(Default)]
+struct ChunkState {
+	/// In order of first import
+	specifiers: Vec<String>,
+	externals: BTreeMap<String, BTreeSet<(Imported, String)>>,
+	/// Chunk index to names
+	imports_from: BTreeMap<usize, BTreeSet<String>>,
+	/// Exported name to local name
+	exports: BTreeMap<String, String>,
+	star_exports: BTreeSet<String>,
+}
+
+impl ChunkState {
+	fn add_specifier(&mut self, specifier: &str) {
+		if !self.specifiers.iter().any(|existing| existing == specifier) {
+			self.specifiers.push(specifier.to_owned());
+		}
+	}
+
+	fn add_external(&mut self, specifier: String, imported: Imported, local: String) {
+		self.add_specifier(&specifier);
+		self.externals.entry(specifier).or_default().insert((imported, local));
+	}
+
+	fn into_header_and_footer(self, file_names: &[String]) -> (Option<Module>, Option<Module>) {
+		let mut header = Vec::new();
+		for specifier in &self.specifiers {
+			let from = quoted(specifier);
+			let Some(bindings) = self.externals.get(specifier) else {
+				header.push(format!("import {from};"));
+				continue;
+			};
+			let mut parts = Vec::new();
+			for (imported, local) in bindings {
+				match imported {
+					Imported::Namespace => header.push(format!("import * as {local} from {from};")),
+					Imported::Name(name) if name == local => parts.push(local.clone()),
+					Imported::Name(name) => {
+						parts.push(format!("{} as {local}", property_key(name)));
+					}
+				}
+			}
+			if !parts.is_empty() {
+				header.push(format!("import {{ {} }} from {from};", parts.join(", ")));
+			}
+		}
+		for (chunk, names) in &self.imports_from {
+			let names: Vec<&str> = names.iter().map(String::as_str).collect();
+			let from = quoted(&format!("./{}", file_names[*chunk]));
+			header.push(format!("import {{ {} }} from {from};", names.join(", ")));
+		}
+
+		let mut footer = Vec::new();
+		if !self.exports.is_empty() {
+			let parts: Vec<String> = self
+				.exports
+				.iter()
+				.map(|(exported, local)| {
+					if exported == local {
+						local.clone()
+					} else {
+						format!("{local} as {}", property_key(exported))
+					}
+				})
+				.collect();
+			footer.push(format!("export {{ {} }};", parts.join(", ")));
+		}
+		for specifier in &self.star_exports {
+			footer.push(format!("export * from {};", quoted(specifier)));
+		}
+
+		let parse = |lines: Vec<String>| {
+			(!lines.is_empty()).then(|| {
+				Module::from_string(lines.join("\n"), Default::default())
+					.expect("generated imports and exports")
+			})
+		};
+		(parse(header), parse(footer))
+	}
+}
@@ -1,23 +1,99 @@
 use checker::CheckOutput;
-use std::{
-	collections::HashSet,
-	path::{Path, PathBuf},
-};
+use parser::source_map::{MapFileStore, WithPathMap};
+use std::path::{Path, PathBuf};
 
+/// `definition_files` are layered on top of the internal definitions (unless it is included explicitly
+/// or the first file is a cache)
 pub fn check<T: crate::ReadFromFS>(
 	entry_points: Vec<PathBuf>,
 	read_from_filesystem: &T,
-	type_definition_module: Option<&Path>,
+	definition_files: &[PathBuf],
 	type_check_options: checker::TypeCheckOptions,
+	parse_options: parser::ParseOptions,
 ) -> CheckOutput<checker::synthesis::EznoParser> {
-	let definitions = if let Some(tdm) = type_definition_module {
-		HashSet::from_iter(std::iter::once(tdm.into()))
+	let definitions = with_internal_definitions(definition_files);
+
+	let read_from_fs = |path: &Path| read_source_or_cache(read_from_filesystem, path);
+
+	checker::check_project(
+		entry_points,
+		definitions,
+		read_from_fs,
+		type_check_options,
+		parse_options,
+		None,
+	)
+}
+
+/// Reuses the results of modules which have not changed since the run that created `previous_cache`.
+/// See [`checker::check_project_incremental`]
+pub fn check_incremental<T: crate::ReadFromFS>(
+	entry_points: Vec<PathBuf>,
+	read_from_filesystem: &T,
+	definition_files: &[PathBuf],
+	type_check_options: checker::TypeCheckOptions,
+	parse_options: parser::ParseOptions,
+	previous_cache: Option<&[u8]>,
+) -> (CheckOutput<checker::synthesis::EznoParser>, checker::IncrementalCache) {
+	let definitions = with_internal_definitions(definition_files);
+
+	let read_from_fs = |path: &Path| read_source_or_cache(read_from_filesystem, path);
+
+	checker::check_project_incremental(
+		entry_points,
+		definitions,
+		read_from_fs,
+		type_check_options,
+		parse_options,
+		previous_cache,
+	)
+}
+
+/// Caches are binary, so are read directly rather than through `read_from_filesystem` (which reads text)
+fn read_source_or_cache<T: crate::ReadFromFS>(
+	read_from_filesystem: &T,
+	path: &Path,
+) -> Option<Vec<u8>> {
+	#[cfg(not(target_family = "wasm"))]
+	if path.extension().is_some_and(|ext| ext == "bin") {
+		return std::fs::read(path).ok();
+	}
+	read_from_filesystem.get_content_at_path(path).map(String::into_bytes)
+}
+
+/// Merges `definition_files` into a cache that can be used as the first definition file.
+/// See [`checker::generate_cache`]
+pub fn pack<T: crate::ReadFromFS>(
+	definition_files: &[PathBuf],
+	read_from_filesystem: &T,
+	include_internal_definitions: bool,
+) -> Result<Vec<u8>, (checker::DiagnosticsContainer, MapFileStore<WithPathMap>)> {
+	let definitions = if include_internal_definitions {
+		with_internal_definitions(definition_files)
 	} else {
-		HashSet::from_iter(std::iter::once(checker::INTERNAL_DEFINITION_FILE_PATH.into()))
+		definition_files.to_vec()
 	};
 
-	let read_from_fs =
-		|path: &Path| read_from_filesystem.get_content_at_path(path).map(String::into_bytes);
+	let read_from_fs = |path: &Path| read_source_or_cache(read_from_filesystem, path);
 
-	checker::check_project(entry_points, definitions, read_from_fs, type_check_options, (), None)
+	checker::generate_cache::<_, checker::synthesis::EznoParser>(
+		&definitions,
+		&read_from_fs,
+		Default::default(),
+	)
+}
+
+/// Caches (from `ezno pack`) already contain the internal definitions, so they are only added
+/// if the first file is not a cache
+pub(crate) fn with_internal_definitions(definition_files: &[PathBuf]) -> Vec<PathBuf> {
+	let internal = PathBuf::from(checker::INTERNAL_DEFINITION_FILE_PATH);
+	let first_is_cache = definition_files
+		.first()
+		.is_some_and(|path| path.extension().is_some_and(|ext| ext == "bin"));
+
+	if first_is_cache || definition_files.contains(&internal) {
+		definition_files.to_vec()
+	} else {
+		std::iter::once(internal).chain(definition_files.iter().cloned()).collect()
+	}
 }
@@ -10,13 +10,17 @@ use std::{
 
 use crate::{
 	build::{build, BuildOutput, FailedBuildOutput},
-	build::{BuildConfig, EznoParsePostCheckVisitors},
+	build::{source_map_path, BuildConfig, JSXRuntime, OutputFormat, SourceMaps, Target},
 	check::check,
-	reporting::emit_diagnostics,
+	config::{read_project_config, resolve_definition_files, resolve_entry_points, Suppressions},
+	reporting::{emit_diagnostics, report_diagnostics, DiagnosticsFormat},
 	utilities::print_to_cli,
 };
 use argh::FromArgs;
-use checker::CheckOutput;
+use checker::{
+	source_map::{MapFileStore, WithPathMap},
+	CheckOutput,
+};
 
 /// The Ezno Type-checker & compiler
 #[derive(FromArgs, Debug)]
@@ -33,9 +37,8 @@ enum CompilerSubCommand {
 	Check(CheckArguments),
 	Experimental(ExperimentalArguments),
 	Repl(crate::repl::ReplArguments),
+	Pack(PackArguments),
 	// Run(RunArguments),
-	// #[cfg(debug_assertions)]
-	// Pack(Pack),
 }
 
 /// Display Ezno information
@@ -58,44 +61,90 @@ pub(crate) enum ExperimentalSubcommand {
 	Format(FormatArguments),
 }
 
-// TODO definition file as list
 /// Build project
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "build")]
 // TODO: Can be refactored with bit to reduce memory
 #[allow(clippy::struct_excessive_bools)]
 pub(crate) struct BuildArguments {
-	/// path to input file
+	/// paths or glob patterns (such as src/**/*.ts) of entry points (defaults to the entry points
+	/// in the project configuration)
 	#[argh(positional)]
-	pub input: PathBuf,
+	pub input: Vec<PathBuf>,
 	/// path to output
-	#[argh(positional)]
+	#[argh(option, short = 'o')]
 	pub output: Option<PathBuf>,
-	/// paths to definition files
+	/// glob pattern (such as `**/node_modules/**`) of files to skip when expanding entry points. Can
+	/// be repeated
+	#[argh(option)]
+	pub ignore: Vec<String>,
+	/// paths to definition files, merged in order on top of the internal definitions
 	#[argh(option, short = 'd')]
-	pub definition_file: Option<PathBuf>,
+	pub definition_file: Vec<PathBuf>,
 
 	/// whether to minify build output
 	#[argh(switch, short = 'm')]
 	pub minify: bool,
-	/// whether to include comments in the output
+	/// do not minify, even if the project configuration does
+	#[argh(switch)]
+	pub no_minify: bool,
+	/// with --minify, also shorten private (#) class member names
+	#[argh(switch)]
+	pub mangle_private_members: bool,
+	/// do not shorten private class member names, even if the project configuration does
+	#[argh(switch)]
+	pub no_mangle_private_members: bool,
+	/// remove comments from the output
 	#[argh(switch)]
 	pub no_comments: bool,
-	/// build source maps
+	/// keep comments, even if the project configuration removes them
+	#[argh(switch)]
+	pub comments: bool,
+	/// write source maps next to the output (as `<output>.map`)
 	#[argh(switch)]
 	pub source_maps: bool,
+	/// append source maps to the output as data URLs
+	#[argh(switch)]
+	pub inline_source_maps: bool,
+	/// do not emit source maps, even if the project configuration does
+	#[argh(switch)]
+	pub no_source_maps: bool,
+	/// output format: esm (default) or iife. Dynamically imported modules are written to separate
+	/// files next to the output
+	#[argh(option)]
+	pub output_format: Option<OutputFormat>,
+	/// how JSX is compiled: automatic (default, `jsx` calls from `<source>/jsx-runtime`) or
+	/// classic (`createElement` calls from `<source>`)
+	#[argh(option)]
+	pub jsx_runtime: Option<JSXRuntime>,
+	/// module that JSX functions are imported from (defaults to react)
+	#[argh(option)]
+	pub jsx_import_source: Option<String>,
+	/// ECMAScript version to lower newer syntax to: es2015 to es2022 or esnext (default, nothing
+	/// is lowered)
+	#[argh(option)]
+	pub target: Option<Target>,
 	/// compact diagnostics
 	#[argh(switch)]
 	pub compact_diagnostics: bool,
+	/// diagnostics format: pretty (default), json, sarif or github
+	#[argh(option, default = "Default::default()")]
+	pub format: DiagnosticsFormat,
+	/// diagnostic code (such as E0012) to not report. Can be repeated
+	#[argh(option)]
+	pub suppress: Vec<String>,
 	/// enable non standard syntax
 	#[argh(switch)]
 	pub non_standard_syntax: bool,
-	/// enable non standard library
+	/// disable non standard syntax, even if the project configuration enables it
 	#[argh(switch)]
-	pub non_standard_library: bool,
+	pub no_non_standard_syntax: bool,
 	/// enable optimising transforms (warning can break code)
 	#[argh(switch)]
 	pub optimise: bool,
+	/// disable optimising transforms, even if the project configuration enables them
+	#[argh(switch)]
+	pub no_optimise: bool,
 
 	#[cfg(not(target_family = "wasm"))]
 	/// whether to display compile times
@@ -110,12 +159,17 @@ pub(crate) struct BuildArguments {
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "check")]
 pub(crate) struct CheckArguments {
-	/// path to input file
+	/// paths or glob patterns of entry points (defaults to the entry points in the project
+	/// configuration)
 	#[argh(positional)]
-	pub input: PathBuf,
-	/// paths to definition files
+	pub input: Vec<PathBuf>,
+	/// glob pattern (such as `**/node_modules/**`) of files to skip when expanding entry points. Can
+	/// be repeated
+	#[argh(option)]
+	pub ignore: Vec<String>,
+	/// paths to definition files, merged in order on top of the internal definitions
 	#[argh(option, short = 'd')]
-	pub definition_file: Option<PathBuf>,
+	pub definition_file: Vec<PathBuf>,
 	/// whether to re-check on file changes
 	#[argh(switch)]
 	pub watch: bool,
@@ -128,6 +182,41 @@ pub(crate) struct CheckArguments {
 	/// compact diagnostics
 	#[argh(switch)]
 	pub compact_diagnostics: bool,
+	/// diagnostics format: pretty (default), json, sarif or github
+	#[argh(option, default = "Default::default()")]
+	pub format: DiagnosticsFormat,
+	/// diagnostic code (such as E0012) to not report. Can be repeated
+	#[argh(option)]
+	pub suppress: Vec<String>,
+	/// warn about unused imports, declarations and exports
+	#[argh(switch)]
+	pub unused_warnings: bool,
+	/// do not warn about unused imports, declarations and exports, even if the project
+	/// configuration does
+	#[argh(switch)]
+	pub no_unused_warnings: bool,
+	/// file to reuse the results of unchanged modules from (updated after checking)
+	#[cfg(not(target_family = "wasm"))]
+	#[argh(option)]
+	pub cache: Option<PathBuf>,
+}
+
+/// Build a cache from definition files. Use it by passing the output as the first definition file
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "pack")]
+pub(crate) struct PackArguments {
+	/// paths to definition files, merged in order on top of the internal definitions
+	#[argh(positional)]
+	pub input: Vec<PathBuf>,
+	/// path to output. Must have a `.bin` extension
+	#[argh(option, short = 'o')]
+	pub output: PathBuf,
+	/// do not include the internal definitions
+	#[argh(switch)]
+	pub no_internal_definitions: bool,
+	/// compact diagnostics
+	#[argh(switch)]
+	pub compact_diagnostics: bool,
 }
 
 /// Formats file in-place
@@ -168,6 +257,119 @@ fn file_system_resolver(path: &Path) -> Option<String> {
 	}
 }
 
+/// Prints diagnostics followed by a summary (if the format is pretty)
+fn report_check_diagnostics(
+	diagnostics: checker::DiagnosticsContainer,
+	module_contents: &MapFileStore<WithPathMap>,
+	format: DiagnosticsFormat,
+	compact_diagnostics: bool,
+) -> ExitCode {
+	let has_error = diagnostics.has_error();
+	// May be warnings or information here
+	report_diagnostics(diagnostics, module_contents, format, compact_diagnostics).unwrap();
+	if has_error {
+		ExitCode::FAILURE
+	} else {
+		if format.is_pretty() {
+			print_to_cli(format_args!("No type errors found 🎉"));
+		}
+		ExitCode::SUCCESS
+	}
+}
+
+/// A switch or its `--no-` counterpart given on the CLI takes precedence over the configuration
+fn resolve_switch(on: bool, off: bool, config: bool) -> bool {
+	!off && (on || config)
+}
+
+/// For `--timings`
+#[cfg(not(target_family = "wasm"))]
+fn print_entry_point_statistics(entry_points: &[checker::EntryPointStatistics]) {
+	for checker::EntryPointStatistics { path, modules, diagnostics, duration } in entry_points {
+		eprintln!(
+			"  {}: {:?}, {modules} new modules, {diagnostics} diagnostics",
+			path.display(),
+			duration.unwrap_or_default()
+		);
+	}
+}
+
+/// Checks whenever a file in the module graph (or a definition file) changes. Results of
+/// unchanged modules are reused between cycles
+#[cfg(not(target_family = "wasm"))]
+#[allow(clippy::too_many_arguments)]
+fn watch_check<T: crate::ReadFromFS>(
+	entry_points: &[PathBuf],
+	read_file: &T,
+	definition_files: &[PathBuf],
+	type_check_options: &checker::TypeCheckOptions,
+	parse_options: parser::ParseOptions,
+	cache: Option<&Path>,
+	suppressions: &Suppressions,
+	format: DiagnosticsFormat,
+	compact_diagnostics: bool,
+) -> ExitCode {
+	use checker::source_map::FileSystem;
+
+	let mut watcher = match crate::watch::FileWatcher::new() {
+		Ok(watcher) => watcher,
+		Err(err) => {
+			print_to_cli(format_args!("could not watch files: {err}"));
+			return ExitCode::FAILURE;
+		}
+	};
+
+	let mut previous = cache.and_then(|cache| std::fs::read(cache).ok());
+
+	loop {
+		crate::watch::clear_terminal();
+
+		let start = std::time::Instant::now();
+		let (output, incremental_cache) = crate::check::check_incremental(
+			entry_points.to_vec(),
+			read_file,
+			definition_files,
+			type_check_options.clone(),
+			parse_options,
+			previous.as_deref(),
+		);
+		let elapsed = start.elapsed();
+
+		let CheckOutput { mut diagnostics, module_contents, modules, imported_paths, .. } = output;
+		suppressions.filter(&mut diagnostics, &module_contents);
+
+		// Files which failed to parse are not in `modules`, but will have diagnostics. Imported
+		// paths include JSON files and unresolved imports (so creating the file re-checks)
+		let paths: Vec<PathBuf> = modules
+			.keys()
+			.copied()
+			.chain(diagnostics.sources())
+			.map(|source| module_contents.get_file_path(source))
+			.chain(imported_paths)
+			.chain(entry_points.iter().cloned())
+			.chain(definition_files.iter().cloned())
+			.collect();
+
+		report_check_diagnostics(diagnostics, &module_contents, format, compact_diagnostics);
+		eprintln!(
+			"Checked in {elapsed:?} ({} unchanged modules reused). Watching for changes...",
+			incremental_cache.restored_modules
+		);
+
+		if let Some(cache) = cache {
+			if let Err(err) = std::fs::write(cache, &incremental_cache.bytes) {
+				print_to_cli(format_args!("could not write cache {}: {err}", cache.display()));
+			}
+		}
+		previous = Some(incremental_cache.bytes);
+
+		if let Err(err) = watcher.wait_for_change(paths) {
+			print_to_cli(format_args!("could not watch files: {err}"));
+			return ExitCode::FAILURE;
+		}
+	}
+}
+
 pub fn run_cli<T: crate::ReadFromFS, U: crate::WriteToFS, V: crate::CLIInputResolver>(
 	cli_arguments: &[&str],
 	read_file: &T,
@@ -190,83 +392,240 @@ pub fn run_cli<T: crate::ReadFromFS, U: crate::WriteToFS, V: crate::CLIInputReso
 		CompilerSubCommand::Check(check_arguments) => {
 			let CheckArguments {
 				input,
-				watch: _,
+				ignore,
+				watch,
 				definition_file,
 				timings,
 				count_diagnostics,
 				compact_diagnostics,
+				format,
+				suppress,
+				unused_warnings,
+				no_unused_warnings,
+				#[cfg(not(target_family = "wasm"))]
+				cache,
 			} = check_arguments;
-			let entry_points = vec![input];
+
+			let mut config = match read_project_config(read_file) {
+				Ok(config) => config,
+				Err(err) => {
+					print_to_cli(format_args!("{err}"));
+					return ExitCode::FAILURE;
+				}
+			};
+			let entry_points = match resolve_entry_points(input, ignore, &mut config) {
+				Ok(entry_points) => entry_points,
+				Err(err) => {
+					print_to_cli(format_args!("{err}"));
+					return ExitCode::FAILURE;
+				}
+			};
+			let definition_files = resolve_definition_files(definition_file, &mut config);
+			let suppressions =
+				match Suppressions::new(suppress, std::mem::take(&mut config.diagnostics)) {
+					Ok(suppressions) => suppressions,
+					Err(err) => {
+						print_to_cli(format_args!("{err}"));
+						return ExitCode::FAILURE;
+					}
+				};
+
+			let mut type_check_options = config.type_check;
+			type_check_options.report_unused = resolve_switch(
+				unused_warnings,
+				no_unused_warnings,
+				type_check_options.report_unused,
+			);
+
+			#[cfg(not(target_family = "wasm"))]
+			if watch {
+				return watch_check(
+					&entry_points,
+					read_file,
+					&definition_files,
+					&type_check_options,
+					config.parse,
+					cache.as_deref(),
+					&suppressions,
+					format,
+					compact_diagnostics,
+				);
+			}
 
 			#[cfg(not(target_family = "wasm"))]
 			let start = timings.then(std::time::Instant::now);
 
-			let type_check_options = Default::default();
+			#[cfg(not(target_family = "wasm"))]
+			let (
+				CheckOutput { mut diagnostics, module_contents, entry_points, .. },
+				restored_modules,
+			) = if let Some(cache) = cache {
+				let previous = std::fs::read(&cache).ok();
+				let (output, incremental_cache) = crate::check::check_incremental(
+					entry_points,
+					read_file,
+					&definition_files,
+					type_check_options,
+					config.parse,
+					previous.as_deref(),
+				);
+				if let Err(err) = std::fs::write(&cache, incremental_cache.bytes) {
+					print_to_cli(format_args!("could not write cache {}: {err}", cache.display()));
+				}
+				(output, Some(incremental_cache.restored_modules))
+			} else {
+				let output = check(
+					entry_points,
+					read_file,
+					&definition_files,
+					type_check_options,
+					config.parse,
+				);
+				(output, None)
+			};
 
-			let CheckOutput { diagnostics, module_contents, .. } =
-				check(entry_points, read_file, definition_file.as_deref(), type_check_options);
+			#[cfg(target_family = "wasm")]
+			let CheckOutput { mut diagnostics, module_contents, .. } =
+				check(entry_points, read_file, &definition_files, type_check_options, config.parse);
+
+			suppressions.filter(&mut diagnostics, &module_contents);
 
 			#[cfg(not(target_family = "wasm"))]
 			if let Some(start) = start {
 				eprintln!("Checked in {:?}", start.elapsed());
+				print_entry_point_statistics(&entry_points);
+				if let Some(restored_modules) = restored_modules {
+					eprintln!("Restored {restored_modules} unchanged modules from cache");
+				}
 			};
 
-			if diagnostics.has_error() {
-				if count_diagnostics {
-					let count = diagnostics.into_iter().count();
-					print_to_cli(format_args!("Found {count} type errors and warnings 😬"))
-				} else {
-					emit_diagnostics(diagnostics, &module_contents, compact_diagnostics).unwrap();
-				}
+			if diagnostics.has_error() && count_diagnostics {
+				let count = diagnostics.into_iter().count();
+				print_to_cli(format_args!("Found {count} type errors and warnings 😬"));
 				ExitCode::FAILURE
 			} else {
-				// May be warnings or information here
-				emit_diagnostics(diagnostics, &module_contents, compact_diagnostics).unwrap();
-				print_to_cli(format_args!("No type errors found 🎉"));
-				ExitCode::SUCCESS
+				report_check_diagnostics(diagnostics, &module_contents, format, compact_diagnostics)
 			}
 		}
 		CompilerSubCommand::Experimental(ExperimentalArguments {
 			nested: ExperimentalSubcommand::Build(build_config),
 		}) => {
-			let output_path = build_config.output.unwrap_or("ezno_output.js".into());
-
-			// TODO
-			let default_builders = EznoParsePostCheckVisitors {
-				expression_visitors_mut: vec![Box::new(
-					crate::transformers::optimisations::ExpressionOptimiser,
-				)],
-				statement_visitors_mut: vec![Box::new(
-					crate::transformers::optimisations::StatementOptimiser,
-				)],
-				variable_visitors_mut: Default::default(),
-				block_visitors_mut: Default::default(),
+			let mut config = match read_project_config(read_file) {
+				Ok(config) => config,
+				Err(err) => {
+					print_to_cli(format_args!("{err}"));
+					return ExitCode::FAILURE;
+				}
 			};
+			let input_paths =
+				match resolve_entry_points(build_config.input, build_config.ignore, &mut config) {
+					Ok(entry_points) => entry_points,
+					Err(err) => {
+						print_to_cli(format_args!("{err}"));
+						return ExitCode::FAILURE;
+					}
+				};
+			let definition_files =
+				resolve_definition_files(build_config.definition_file, &mut config);
 
-			let input_paths = vec![build_config.input];
+			let output_path =
+				build_config.output.or(config.build.output).unwrap_or("ezno_output.js".into());
+			let minify =
+				resolve_switch(build_config.minify, build_config.no_minify, config.build.minify);
+			let source_maps = if build_config.no_source_maps {
+				SourceMaps::None
+			} else if build_config.inline_source_maps || config.build.inline_source_maps {
+				SourceMaps::Inline
+			} else if build_config.source_maps || config.build.source_maps {
+				SourceMaps::External
+			} else {
+				SourceMaps::None
+			};
+			let suppressions = match Suppressions::new(
+				build_config.suppress,
+				std::mem::take(&mut config.diagnostics),
+			) {
+				Ok(suppressions) => suppressions,
+				Err(err) => {
+					print_to_cli(format_args!("{err}"));
+					return ExitCode::FAILURE;
+				}
+			};
+
+			#[cfg(not(target_family = "wasm"))]
+			let start = build_config.timings.then(std::time::Instant::now);
 
 			let output = build(
 				input_paths,
 				read_file,
-				build_config.definition_file.as_deref(),
+				&definition_files,
 				&output_path,
-				&BuildConfig { strip_whitespace: build_config.minify },
-				Some(default_builders),
+				&BuildConfig {
+					strip_whitespace: minify,
+					type_check_options: config.type_check,
+					parse_options: config.parse,
+					source_maps,
+					output_format: build_config.output_format.unwrap_or(config.build.output_format),
+					no_comments: resolve_switch(
+						build_config.no_comments,
+						build_config.comments,
+						config.build.no_comments,
+					),
+					non_standard_syntax: resolve_switch(
+						build_config.non_standard_syntax,
+						build_config.no_non_standard_syntax,
+						config.build.non_standard_syntax,
+					),
+					optimise: resolve_switch(
+						build_config.optimise,
+						build_config.no_optimise,
+						config.build.optimise,
+					),
+					jsx_runtime: build_config.jsx_runtime.unwrap_or(config.build.jsx_runtime),
+					jsx_import_source: build_config
+						.jsx_import_source
+						.or(config.build.jsx_import_source),
+					target: build_config.target.unwrap_or(config.build.target),
+					mangle: minify,
+					mangle_private_members: resolve_switch(
+						build_config.mangle_private_members,
+						build_config.no_mangle_private_members,
+						config.build.mangle_private_members,
+					),
+				},
+				// The optimisers are added by `build` under `--optimise`
+				None,
 			);
 
 			let compact_diagnostics = build_config.compact_diagnostics;
+			let format = build_config.format;
+
+			#[cfg(not(target_family = "wasm"))]
+			if let Some(start) = start {
+				eprintln!("Built in {:?}", start.elapsed());
+				let (Ok(BuildOutput { entry_points, .. })
+				| Err(FailedBuildOutput { entry_points, .. })) = &output;
+				print_entry_point_statistics(entry_points);
+			}
 
 			match output {
-				Ok(BuildOutput { diagnostics, fs, outputs }) => {
+				Ok(BuildOutput { mut diagnostics, fs, outputs, .. }) => {
 					for output in outputs {
+						if let SourceMaps::External = source_maps {
+							write_file(&source_map_path(&output.output_path), output.mappings);
+						}
 						write_file(output.output_path.as_path(), output.content);
 					}
-					emit_diagnostics(diagnostics, &fs, compact_diagnostics).unwrap();
-					print_to_cli(format_args!("Project built successfully 🎉"));
+					suppressions.filter(&mut diagnostics, &fs);
+					report_diagnostics(diagnostics, &fs, format, compact_diagnostics).unwrap();
+					if format.is_pretty() {
+						print_to_cli(format_args!("Project built successfully 🎉"));
+					}
 					ExitCode::SUCCESS
 				}
-				Err(FailedBuildOutput { fs, diagnostics }) => {
-					emit_diagnostics(diagnostics, &fs, compact_diagnostics).unwrap();
+				Err(FailedBuildOutput { fs, mut diagnostics, .. }) => {
+					suppressions.filter(&mut diagnostics, &fs);
+					report_diagnostics(diagnostics, &fs, format, compact_diagnostics).unwrap();
 					ExitCode::FAILURE
 				}
 			}
@@ -311,6 +670,54 @@ pub fn run_cli<T: crate::ReadFromFS, U: crate::WriteToFS, V: crate::CLIInputReso
 			crate::repl::run_repl(cli_input_resolver, argument);
 			// TODO not always true
 			ExitCode::SUCCESS
+		}
+		CompilerSubCommand::Pack(PackArguments {
+			input,
+			output,
+			no_internal_definitions,
+			compact_diagnostics,
+		}) => {
+			if input.is_empty() {
+				print_to_cli(format_args!("expected at least one definition file"));
+				return ExitCode::FAILURE;
+			}
+			if output.extension().is_none_or(|ext| ext != "bin") {
+				print_to_cli(format_args!(
+					"{} must have a .bin extension to be read as a cache",
+					output.display()
+				));
+				return ExitCode::FAILURE;
+			}
+
+			match crate::check::pack(&input, read_file, !no_internal_definitions) {
+				Ok(cache) => {
+					#[cfg(not(target_family = "wasm"))]
+					let result = std::fs::write(&output, cache).map_err(|err| err.to_string());
+					#[cfg(target_family = "wasm")]
+					let result: Result<(), String> = {
+						drop(cache);
+						Err("binary output is not supported on this platform".to_owned())
+					};
+
+					match result {
+						Ok(()) => {
+							print_to_cli(format_args!("Packed into {}", output.display()));
+							ExitCode::SUCCESS
+						}
+						Err(err) => {
+							print_to_cli(format_args!(
+								"could not write {}: {err}",
+								output.display()
+							));
+							ExitCode::FAILURE
+						}
+					}
+				}
+				Err((diagnostics, files)) => {
+					emit_diagnostics(diagnostics, &files, compact_diagnostics).unwrap();
+					ExitCode::FAILURE
+				}
+			}
 		} // CompilerSubCommand::Run(run_arguments) => {
 		  // 	let build_arguments = BuildArguments {
 		  // 		input: run_arguments.input,
@@ -332,17 +739,5 @@ pub fn run_cli<T: crate::ReadFromFS, U: crate::WriteToFS, V: crate::CLIInputReso
 		  // 			.unwrap();
 		  // 	}
 		  // }
-		  // #[cfg(debug_assertions)]
-		  // CompilerSubCommand::Pack(Pack { input, output }) => {
-		  // 	let file = checker::definition_file_to_buffer(
-		  // 		&file_system_resolver,
-		  // 		&env::current_dir().unwrap(),
-		  // 		&input,
-		  // 	)
-		  // 	.unwrap();
-
-		  // 	let _root_ctx = checker::root_context_from_bytes(file);
-		  // 	println!("Registered {} types", _root_ctx.types.len();
-		  // }
 	}
 }
@@ -0,0 +1,162 @@
+//! Project configuration. Read from `ezno.json` or the `"ezno"` field of `package.json`.
+//! Options passed through the CLI take precedence over those in the configuration
+
+use std::{
+	collections::HashMap,
+	path::{Component, Path, PathBuf},
+};
+
+use checker::{
+	source_map::{FileSystem, MapFileStore, PathMap},
+	DiagnosticCode, DiagnosticsContainer,
+};
+
+pub(crate) const CONFIGURATION_FILE: &str = "ezno.json";
+pub(crate) const PACKAGE_JSON: &str = "package.json";
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ProjectConfig {
+	/// Paths or glob patterns of entry points for `ezno check`
+	pub entry_points: Vec<PathBuf>,
+	/// Glob patterns (such as `**/node_modules/**`) of files to skip when expanding entry points
+	pub ignore: Vec<String>,
+	/// Merged in order on top of the internal definitions
+	pub definition_files: Vec<PathBuf>,
+	pub type_check: checker::TypeCheckOptions,
+	pub parse: parser::ParseOptions,
+	pub build: BuildOptions,
+	pub diagnostics: DiagnosticsOptions,
+}
+
+/// Codes such as `"E0012"` which are not reported
+#[derive(Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct DiagnosticsOptions {
+	pub suppress: Vec<String>,
+	/// Codes suppressed only in the file at the path
+	pub suppress_in: HashMap<PathBuf, Vec<String>>,
+}
+
+/// Mirrors the switches of `ezno experimental build`
+#[derive(Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+// TODO: Can be refactored with bit to reduce memory
+#[allow(clippy::struct_excessive_bools)]
+pub(crate) struct BuildOptions {
+	pub output: Option<PathBuf>,
+	pub minify: bool,
+	pub mangle_private_members: bool,
+	pub no_comments: bool,
+	pub source_maps: bool,
+	pub inline_source_maps: bool,
+	pub output_format: crate::build::OutputFormat,
+	pub non_standard_syntax: bool,
+	pub optimise: bool,
+	pub jsx_runtime: crate::build::JSXRuntime,
+	pub jsx_import_source: Option<String>,
+	pub target: crate::build::Target,
+}
+
+/// `ezno.json` is preferred over `package.json`. Returns the default configuration if neither
+/// file exists (or `package.json` does not have a `"ezno"` field)
+pub(crate) fn read_project_config<T: crate::ReadFromFS>(
+	read_file: &T,
+) -> Result<ProjectConfig, String> {
+	if let Some(content) = read_file.get_content_at_path(Path::new(CONFIGURATION_FILE)) {
+		serde_json::from_str(&content).map_err(|err| format!("{CONFIGURATION_FILE}: {err}"))
+	} else if let Some(content) = read_file.get_content_at_path(Path::new(PACKAGE_JSON)) {
+		let mut package: serde_json::Value =
+			serde_json::from_str(&content).map_err(|err| format!("{PACKAGE_JSON}: {err}"))?;
+
+		match package.get_mut("ezno").map(serde_json::Value::take) {
+			Some(section) => serde_json::from_value(section)
+				.map_err(|err| format!("\"ezno\" in {PACKAGE_JSON}: {err}")),
+			None => Ok(ProjectConfig::default()),
+		}
+	} else {
+		Ok(ProjectConfig::default())
+	}
+}
+
+/// CLI entry points replace the configured ones. Ignore patterns are combined
+pub(crate) fn resolve_entry_points(
+	input: Vec<PathBuf>,
+	ignore: Vec<String>,
+	config: &mut ProjectConfig,
+) -> Result<Vec<PathBuf>, String> {
+	let patterns = if !input.is_empty() {
+		input
+	} else if !config.entry_points.is_empty() {
+		std::mem::take(&mut config.entry_points)
+	} else {
+		return Err(format!(
+			"no entry point. Pass a path or add \"entry_points\" to {CONFIGURATION_FILE}"
+		));
+	};
+	let ignore: Vec<String> =
+		ignore.into_iter().chain(std::mem::take(&mut config.ignore)).collect();
+	crate::glob::expand(patterns, &ignore)
+}
+
+/// CLI definition files replace the configured ones
+pub(crate) fn resolve_definition_files(
+	cli: Vec<PathBuf>,
+	config: &mut ProjectConfig,
+) -> Vec<PathBuf> {
+	if cli.is_empty() {
+		std::mem::take(&mut config.definition_files)
+	} else {
+		cli
+	}
+}
+
+/// Suppressed codes from the configuration and the CLI
+pub(crate) struct Suppressions {
+	everywhere: Vec<DiagnosticCode>,
+	in_files: HashMap<PathBuf, Vec<DiagnosticCode>>,
+}
+
+impl Suppressions {
+	pub(crate) fn new(cli: Vec<String>, config: DiagnosticsOptions) -> Result<Self, String> {
+		fn parse(codes: &[String]) -> Result<Vec<DiagnosticCode>, String> {
+			codes.iter().map(|code| code.parse()).collect()
+		}
+
+		let everywhere = parse(&[cli, config.suppress].concat())?;
+		let in_files = config
+			.suppress_in
+			.into_iter()
+			.map(|(path, codes)| Ok((normalise(&path), parse(&codes)?)))
+			.collect::<Result<_, String>>()?;
+		Ok(Self { everywhere, in_files })
+	}
+
+	/// Diagnostics without a code are always kept. Suppressing in a file is decided by the file
+	/// of the primary position
+	pub(crate) fn filter<T: PathMap>(
+		&self,
+		diagnostics: &mut DiagnosticsContainer,
+		fs: &MapFileStore<T>,
+	) {
+		if self.everywhere.is_empty() && self.in_files.is_empty() {
+			return;
+		}
+		diagnostics.retain(|diagnostic| {
+			let Some(code) = diagnostic.code() else { return true };
+			if self.everywhere.contains(&code) {
+				return false;
+			}
+			diagnostic.sources().next().is_none_or(|source| {
+				self.in_files
+					.get(&normalise(&fs.get_file_path(source)))
+					.is_none_or(|codes| !codes.contains(&code))
+			})
+		});
+	}
+}
+
+/// So `./src/a.ts` and `src/a.ts` are equal
+pub(crate) fn normalise(path: &Path) -> PathBuf {
+	path.components().filter(|component| !matches!(component, Component::CurDir)).collect()
+}
@@ -0,0 +1,189 @@
+//! Expanding entry points such as `src/**/*.ts`. Supports `*`, `?`, `**` (any number of
+//! directories) and `{a,b}` alternatives
+
+use std::{
+	collections::HashSet,
+	path::{Path, PathBuf},
+};
+
+use crate::config::normalise;
+
+pub(crate) fn is_glob(pattern: &str) -> bool {
+	pattern.contains(['*', '?', '{'])
+}
+
+/// Paths without glob characters are kept as is. Matches of each pattern are sorted and files
+/// matching any of `ignore` are skipped
+pub(crate) fn expand(patterns: Vec<PathBuf>, ignore: &[String]) -> Result<Vec<PathBuf>, String> {
+	let ignore: Vec<String> = ignore.iter().flat_map(|pattern| expand_braces(pattern)).collect();
+
+	let mut seen = HashSet::new();
+	let mut paths = Vec::new();
+	for pattern in patterns {
+		let Some(pattern_str) = pattern.to_str().filter(|pattern| is_glob(pattern)) else {
+			if seen.insert(normalise(&pattern)) {
+				paths.push(pattern);
+			}
+			continue;
+		};
+
+		let alternatives = expand_braces(pattern_str);
+		let mut matched = Vec::new();
+		for alternative in &alternatives {
+			let segments = split(alternative);
+			let base = Path::new(base(alternative));
+			walk(base, &ignore, &mut |path| {
+				let path_str = path.to_string_lossy();
+				if matches(&segments, &split(&path_str)) {
+					matched.push(path.to_path_buf());
+				}
+			});
+		}
+
+		if matched.is_empty() {
+			return Err(format!("'{pattern_str}' does not match any files"));
+		}
+		matched.sort();
+		paths.extend(matched.into_iter().filter(|path| seen.insert(path.clone())));
+	}
+	Ok(paths)
+}
+
+/// Calls `cb` with every file under `directory` (which may be empty for the current directory)
+fn walk(directory: &Path, ignore: &[String], cb: &mut impl FnMut(&Path)) {
+	let read_from = if directory.as_os_str().is_empty() { Path::new(".") } else { directory };
+	let Ok(entries) = std::fs::read_dir(read_from) else { return };
+
+	let mut entries: Vec<_> = entries.filter_map(Result::ok).collect();
+	entries.sort_by_key(std::fs::DirEntry::file_name);
+
+	for entry in entries {
+		let path = directory.join(entry.file_name());
+		let path_str = path.to_string_lossy();
+		let segments = split(&path_str);
+		let Ok(file_type) = entry.file_type() else { continue };
+
+		if file_type.is_dir() {
+			if !ignore.iter().any(|pattern| ignores_directory(&split(pattern), &segments)) {
+				walk(&path, ignore, cb);
+			}
+		} else if !ignore.iter().any(|pattern| matches(&split(pattern), &segments)) {
+			cb(&path);
+		}
+	}
+}
+
+/// For `node_modules/**`, skips reading the directory rather than each file in it
+fn ignores_directory(pattern: &[&str], directory: &[&str]) -> bool {
+	matches!(pattern.split_last(), Some((&"**", rest)) if matches(rest, directory))
+}
+
+fn matches(pattern: &[&str], path: &[&str]) -> bool {
+	match pattern.split_first() {
+		None => path.is_empty(),
+		Some((&"**", rest)) => (0..=path.len()).any(|skip| matches(rest, &path[skip..])),
+		Some((segment, rest)) => path.split_first().is_some_and(|(name, path)| {
+			let segment: Vec<char> = segment.chars().collect();
+			let name: Vec<char> = name.chars().collect();
+			matches_segment(&segment, &name) && matches(rest, path)
+		}),
+	}
+}
+
+fn matches_segment(pattern: &[char], name: &[char]) -> bool {
+	match pattern.split_first() {
+		None => name.is_empty(),
+		Some(('*', rest)) => (0..=name.len()).any(|skip| matches_segment(rest, &name[skip..])),
+		Some(('?', rest)) => !name.is_empty() && matches_segment(rest, &name[1..]),
+		Some((chr, rest)) => name.first() == Some(chr) && matches_segment(rest, &name[1..]),
+	}
+}
+
+/// `src/*.{ts,tsx}` -> `src/*.ts`, `src/*.tsx`. Does not support nesting
+fn expand_braces(pattern: &str) -> Vec<String> {
+	let Some((start, end)) =
+		pattern.find('{').and_then(|start| Some((start, start + pattern[start..].find('}')?)))
+	else {
+		return vec![pattern.to_owned()];
+	};
+
+	let (before, after) = (&pattern[..start], &pattern[end + 1..]);
+	pattern[start + 1..end]
+		.split(',')
+		.flat_map(|alternative| expand_braces(&format!("{before}{alternative}{after}")))
+		.collect()
+}
+
+/// The part of `pattern` before the directory containing the first glob character. Unlike the
+/// segments, this keeps a root or prefix (such as `/` or `C:\`)
+fn base(pattern: &str) -> &str {
+	let glob_start = pattern.find(['*', '?', '{']).unwrap_or(pattern.len());
+	pattern[..glob_start].rfind(['/', '\\']).map_or("", |end| &pattern[..=end])
+}
+
+fn split(path: &str) -> Vec<&str> {
+	path.split(['/', '\\']).filter(|segment| !segment.is_empty() && *segment != ".").collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::{Path, PathBuf};
+
+	use super::expand;
+
+	/// A new directory containing `files`
+	fn directory_with(name: &str, files: &[&str]) -> PathBuf {
+		let root = std::env::temp_dir().join(format!("ezno-glob-{name}-{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&root);
+		for file in files {
+			let path = root.join(file);
+			std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+			std::fs::write(path, "").unwrap();
+		}
+		root
+	}
+
+	fn expand_one(pattern: &Path, ignore: &[&str]) -> Result<Vec<PathBuf>, String> {
+		let ignore: Vec<String> = ignore.iter().map(|pattern| (*pattern).to_owned()).collect();
+		expand(vec![pattern.to_path_buf()], &ignore)
+	}
+
+	#[test]
+	fn relative() {
+		assert_eq!(
+			expand_one(Path::new("tests/common/*.rs"), &[]),
+			Ok(vec![PathBuf::from("tests/common/mod.rs")])
+		);
+		assert_eq!(
+			expand_one(Path::new("./tests/commo?/mod.{rs,ts}"), &[]),
+			Ok(vec![PathBuf::from("./tests/common/mod.rs")])
+		);
+	}
+
+	#[test]
+	fn absolute() {
+		let root = directory_with("absolute", &["src/a.ts", "src/b.ts", "src/c.js"]);
+		let matched = expand_one(&root.join("src/*.ts"), &[]);
+		assert_eq!(matched, Ok(vec![root.join("src/a.ts"), root.join("src/b.ts")]));
+	}
+
+	#[test]
+	fn any_directories() {
+		let root = directory_with(
+			"any-directories",
+			&["a.ts", "nested/deep/b.ts", "nested/c.js", "node_modules/d.ts"],
+		);
+		let matched = expand_one(&root.join("**/*.ts"), &["**/node_modules/**"]);
+		assert_eq!(matched, Ok(vec![root.join("a.ts"), root.join("nested/deep/b.ts")]));
+	}
+
+	#[test]
+	fn no_match() {
+		let root = directory_with("no-match", &["a.js"]);
+		let pattern = root.join("*.ts");
+		assert_eq!(
+			expand_one(&pattern, &[]),
+			Err(format!("'{}' does not match any files", pattern.display()))
+		);
+	}
+}
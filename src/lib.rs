@@ -1,16 +1,23 @@
 mod ast_explorer;
 mod build;
+mod bundler;
 mod check;
+mod config;
+mod glob;
 mod repl;
 mod reporting;
+#[cfg(not(target_family = "wasm"))]
+mod watch;
 
 pub(crate) mod utilities;
 
 pub mod cli;
 pub mod transformers;
 
-pub use build::build;
-pub use check::check;
+pub use build::{
+	build, BuildConfig, BuildOutput, JSXRuntime, Output, OutputFormat, SourceMaps, Target,
+};
+pub use check::{check, check_incremental};
 pub use checker::{Diagnostic, DiagnosticKind};
 
 pub use parser::{source_map, ASTNode, ToStringOptions};
@@ -15,9 +15,9 @@ pub(crate) struct ReplArguments {
 	/// use mutable variables everywhere
 	#[argh(switch)]
 	const_as_let: bool,
-	/// a definition file to check with
+	/// definition files to check with, merged in order on top of the internal definitions
 	#[argh(option, short = 'd')]
-	type_definition_module: Option<PathBuf>,
+	type_definition_module: Vec<PathBuf>,
 }
 
 #[allow(unused)]
@@ -39,11 +39,7 @@ pub(crate) fn run_repl<U: crate::CLIInputResolver>(
 ) {
 	print_to_cli(format_args!("Entering REPL. Exit with `close()`"));
 
-	let definitions = if let Some(tdm) = type_definition_module {
-		std::iter::once(tdm).collect()
-	} else {
-		std::iter::once(checker::INTERNAL_DEFINITION_FILE_PATH.into()).collect()
-	};
+	let definitions = crate::check::with_internal_definitions(&type_definition_module);
 
 	let state = checker::synthesis::interactive::State::new(&file_system_resolver, definitions);
 
@@ -1,5 +1,6 @@
 use codespan_reporting::{
 	diagnostic::{Diagnostic, Label, Severity},
+	files::Files,
 	term::{
 		emit,
 		termcolor::{ColorChoice, StandardStream},
@@ -7,7 +8,7 @@ use codespan_reporting::{
 	},
 };
 use parser::{
-	source_map::{FileSystem, MapFileStore, PathMap},
+	source_map::{FileSystem, MapFileStore, PathMap, SpanWithSource},
 	SourceId,
 };
 
@@ -26,14 +27,14 @@ fn checker_diagnostic_to_codespan_diagnostic(
 	compact: bool,
 ) -> Diagnostic<SourceId> {
 	match diagnostic {
-		checker::Diagnostic::Global { reason, kind } => Diagnostic {
+		checker::Diagnostic::Global { reason, kind, code } => Diagnostic {
 			severity: ezno_diagnostic_to_severity(&kind),
-			code: None,
+			code: code.map(|code| code.to_string()),
 			message: reason,
 			labels: Vec::new(),
 			notes: Vec::default(),
 		},
-		checker::Diagnostic::Position { reason, position, kind } => {
+		checker::Diagnostic::Position { reason, position, kind, code } => {
 			let (message, labels) = if compact {
 				(reason, Vec::new())
 			} else {
@@ -45,16 +46,22 @@ fn checker_diagnostic_to_codespan_diagnostic(
 
 			Diagnostic {
 				severity: ezno_diagnostic_to_severity(&kind),
-				code: None,
+				code: code.map(|code| code.to_string()),
 				message,
 				labels,
 				notes: Vec::default(),
 			}
 		}
-		checker::Diagnostic::PositionWithAdditionalLabels { reason, position, labels, kind } => {
+		checker::Diagnostic::PositionWithAdditionalLabels {
+			reason,
+			position,
+			labels,
+			kind,
+			code,
+		} => {
 			let mut diagnostic = Diagnostic {
 				severity: ezno_diagnostic_to_severity(&kind),
-				code: None,
+				code: code.map(|code| code.to_string()),
 				message: String::new(),
 				labels: Vec::new(),
 				notes: Vec::new(),
@@ -119,3 +126,465 @@ pub(crate) fn emit_diagnostics<T: PathMap>(
 
 	Ok(())
 }
+
+/// How diagnostics are reported. Machine readable formats are printed to stdout as a single document
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub(crate) enum DiagnosticsFormat {
+	/// Rendered for the terminal
+	#[default]
+	Pretty,
+	/// Array of diagnostics
+	JSON,
+	/// [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+	SARIF,
+	/// [GitHub Actions workflow commands](https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions)
+	GitHub,
+}
+
+impl std::str::FromStr for DiagnosticsFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"pretty" => Ok(Self::Pretty),
+			"json" => Ok(Self::JSON),
+			"sarif" => Ok(Self::SARIF),
+			"github" => Ok(Self::GitHub),
+			s => Err(format!("unknown format '{s}', expected pretty, json, sarif or github")),
+		}
+	}
+}
+
+impl DiagnosticsFormat {
+	/// Whether messages other than diagnostics (such as summaries) can be printed
+	pub(crate) fn is_pretty(self) -> bool {
+		matches!(self, Self::Pretty)
+	}
+}
+
+/// `compact` only applies to [`DiagnosticsFormat::Pretty`]
+pub(crate) fn report_diagnostics<T: PathMap>(
+	diagnostics: impl IntoIterator<Item = checker::Diagnostic>,
+	fs: &MapFileStore<T>,
+	format: DiagnosticsFormat,
+	compact: bool,
+) -> Result<(), codespan_reporting::files::Error> {
+	if format.is_pretty() {
+		emit_diagnostics(diagnostics, fs, compact)
+	} else {
+		crate::utilities::print_to_cli_without_newline(format_args!(
+			"{}",
+			format_diagnostics(diagnostics, fs, format)
+		));
+		Ok(())
+	}
+}
+
+/// Output for the machine readable formats, ending with a new line (unless there are no GitHub commands)
+fn format_diagnostics<T: PathMap>(
+	diagnostics: impl IntoIterator<Item = checker::Diagnostic>,
+	fs: &MapFileStore<T>,
+	format: DiagnosticsFormat,
+) -> String {
+	match format {
+		DiagnosticsFormat::Pretty => unreachable!("pretty diagnostics are emitted by codespan"),
+		DiagnosticsFormat::JSON => {
+			let diagnostics: Vec<_> = diagnostics
+				.into_iter()
+				.map(|diagnostic| JSONDiagnostic::new(diagnostic, fs))
+				.collect();
+			format!("{}\n", serde_json::to_string_pretty(&diagnostics).unwrap())
+		}
+		DiagnosticsFormat::SARIF => {
+			let results: Vec<_> =
+				diagnostics.into_iter().map(|diagnostic| sarif_result(diagnostic, fs)).collect();
+			let log = serde_json::json!({
+				"$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+				"version": "2.1.0",
+				"runs": [{
+					"tool": {
+						"driver": {
+							"name": "ezno",
+							"version": env!("CARGO_PKG_VERSION"),
+							"informationUri": env!("CARGO_PKG_HOMEPAGE"),
+						}
+					},
+					"columnKind": "unicodeCodePoints",
+					"results": results,
+				}]
+			});
+			format!("{}\n", serde_json::to_string_pretty(&log).unwrap())
+		}
+		DiagnosticsFormat::GitHub => diagnostics
+			.into_iter()
+			.map(|diagnostic| github_workflow_command(diagnostic, fs) + "\n")
+			.collect(),
+	}
+}
+
+fn kind_name(kind: checker::DiagnosticKind) -> &'static str {
+	match kind {
+		checker::DiagnosticKind::Error => "error",
+		checker::DiagnosticKind::Warning => "warning",
+		checker::DiagnosticKind::Info => "info",
+	}
+}
+
+/// Lines and columns start at one. Columns are in characters
+#[derive(serde::Serialize)]
+struct LineColumn {
+	line: usize,
+	column: usize,
+}
+
+#[derive(serde::Serialize)]
+struct Location {
+	file: String,
+	start: LineColumn,
+	end: LineColumn,
+}
+
+impl Location {
+	fn new<T: PathMap>(position: SpanWithSource, fs: &MapFileStore<T>) -> Self {
+		let files = fs.into_code_span_store();
+		let line_column = |index: u32| {
+			files.location(position.source, index as usize).map_or(
+				LineColumn { line: 1, column: 1 },
+				|location| LineColumn {
+					line: location.line_number,
+					column: location.column_number,
+				},
+			)
+		};
+		Self {
+			file: fs.get_file_path(position.source).display().to_string(),
+			start: line_column(position.start),
+			end: line_column(position.end),
+		}
+	}
+}
+
+#[derive(serde::Serialize)]
+struct JSONLabel {
+	message: String,
+	location: Option<Location>,
+}
+
+#[derive(serde::Serialize)]
+struct JSONDiagnostic {
+	kind: &'static str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	code: Option<String>,
+	reason: String,
+	location: Option<Location>,
+	labels: Vec<JSONLabel>,
+}
+
+impl JSONDiagnostic {
+	fn new<T: PathMap>(diagnostic: checker::Diagnostic, fs: &MapFileStore<T>) -> Self {
+		let kind = kind_name(diagnostic.kind());
+		let code = diagnostic.code().map(|code| code.to_string());
+		match diagnostic {
+			checker::Diagnostic::Global { reason, .. } => {
+				Self { kind, code, reason, location: None, labels: Vec::new() }
+			}
+			checker::Diagnostic::Position { reason, position, .. } => Self {
+				kind,
+				code,
+				reason,
+				location: Some(Location::new(position, fs)),
+				labels: Vec::new(),
+			},
+			checker::Diagnostic::PositionWithAdditionalLabels {
+				reason, position, labels, ..
+			} => Self {
+				kind,
+				code,
+				reason,
+				location: Some(Location::new(position, fs)),
+				labels: labels
+					.into_iter()
+					.map(|(message, position)| JSONLabel {
+						message,
+						location: position.map(|position| Location::new(position, fs)),
+					})
+					.collect(),
+			},
+		}
+	}
+}
+
+fn sarif_result<T: PathMap>(
+	diagnostic: checker::Diagnostic,
+	fs: &MapFileStore<T>,
+) -> serde_json::Value {
+	fn physical_location(location: &Location) -> serde_json::Value {
+		serde_json::json!({
+			"artifactLocation": { "uri": location.file },
+			"region": {
+				"startLine": location.start.line,
+				"startColumn": location.start.column,
+				"endLine": location.end.line,
+				"endColumn": location.end.column,
+			}
+		})
+	}
+
+	let JSONDiagnostic { kind, code, reason, location, labels } =
+		JSONDiagnostic::new(diagnostic, fs);
+	let level = match kind {
+		"info" => "note",
+		kind => kind,
+	};
+
+	let locations: Vec<_> = location
+		.iter()
+		.map(|location| serde_json::json!({ "physicalLocation": physical_location(location) }))
+		.collect();
+
+	let related_locations: Vec<_> = labels
+		.iter()
+		.enumerate()
+		.map(|(id, label)| {
+			let mut related = serde_json::json!({ "id": id, "message": { "text": label.message } });
+			if let Some(ref location) = label.location {
+				related["physicalLocation"] = physical_location(location);
+			}
+			related
+		})
+		.collect();
+
+	let mut result = serde_json::json!({
+		"level": level,
+		"message": { "text": reason },
+		"locations": locations,
+		"relatedLocations": related_locations,
+	});
+	if let Some(code) = code {
+		result["ruleId"] = code.into();
+	}
+	result
+}
+
+/// Labels are included in the message, as annotations can only have one position
+fn github_workflow_command<T: PathMap>(
+	diagnostic: checker::Diagnostic,
+	fs: &MapFileStore<T>,
+) -> String {
+	fn escape_data(data: &str) -> String {
+		data.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+	}
+
+	fn escape_property(property: &str) -> String {
+		escape_data(property).replace(':', "%3A").replace(',', "%2C")
+	}
+
+	let JSONDiagnostic { kind, code, reason, location, labels } =
+		JSONDiagnostic::new(diagnostic, fs);
+	let command = match kind {
+		"info" => "notice",
+		kind => kind,
+	};
+
+	let mut message = reason;
+	for label in labels {
+		message.push('\n');
+		if let Some(location) = label.location {
+			use std::fmt::Write;
+			let _ = write!(
+				message,
+				"{}:{}:{}: ",
+				location.file, location.start.line, location.start.column
+			);
+		}
+		message.push_str(&label.message);
+	}
+
+	let title = code.map(|code| format!("title={code}"));
+
+	if let Some(location) = location {
+		format!(
+			"::{command} {}file={},line={},col={},endLine={},endColumn={}::{}",
+			title.map(|title| format!("{title},")).unwrap_or_default(),
+			escape_property(&location.file),
+			location.start.line,
+			location.start.column,
+			location.end.line,
+			location.end.column,
+			escape_data(&message)
+		)
+	} else {
+		format!(
+			"::{command}{}::{}",
+			title.map(|title| format!(" {title}")).unwrap_or_default(),
+			escape_data(&message)
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{format_diagnostics, DiagnosticsFormat};
+	use checker::{Diagnostic, DiagnosticKind};
+	use parser::source_map::{FileSystem, MapFileStore, NoPathMap, SpanWithSource};
+
+	/// An error with a code and a label, a warning and a global info. `é` is two bytes, so columns
+	/// are only right if they count characters
+	fn diagnostics(fs: &mut MapFileStore<NoPathMap>) -> Vec<Diagnostic> {
+		let source = fs
+			.new_source_id("src/a,b:c%.ts".into(), "const a = 1;\nlet é: string = a;\n".to_owned());
+		vec![
+			Diagnostic::PositionWithAdditionalLabels {
+				reason: "Type 1 is not assignable to type string".to_owned(),
+				position: SpanWithSource { start: 30, end: 31, source },
+				labels: vec![(
+					"Variable declared here\nwith a type".to_owned(),
+					Some(SpanWithSource { start: 17, end: 27, source }),
+				)],
+				kind: DiagnosticKind::Error,
+				code: Some("E0003".parse().unwrap()),
+			},
+			Diagnostic::Position {
+				reason: "'a' is declared but never used".to_owned(),
+				position: SpanWithSource { start: 6, end: 7, source },
+				kind: DiagnosticKind::Warning,
+				code: None,
+			},
+			Diagnostic::Global {
+				reason: "100% checked".to_owned(),
+				kind: DiagnosticKind::Info,
+				code: None,
+			},
+		]
+	}
+
+	fn format(format: DiagnosticsFormat) -> String {
+		let mut fs = MapFileStore::<NoPathMap>::default();
+		let diagnostics = diagnostics(&mut fs);
+		format_diagnostics(diagnostics, &fs, format)
+	}
+
+	#[test]
+	fn json() {
+		let expected = r#"[
+  {
+    "kind": "error",
+    "code": "E0003",
+    "reason": "Type 1 is not assignable to type string",
+    "location": {
+      "file": "src/a,b:c%.ts",
+      "start": {
+        "line": 2,
+        "column": 17
+      },
+      "end": {
+        "line": 2,
+        "column": 18
+      }
+    },
+    "labels": [
+      {
+        "message": "Variable declared here\nwith a type",
+        "location": {
+          "file": "src/a,b:c%.ts",
+          "start": {
+            "line": 2,
+            "column": 5
+          },
+          "end": {
+            "line": 2,
+            "column": 14
+          }
+        }
+      }
+    ]
+  },
+  {
+    "kind": "warning",
+    "reason": "'a' is declared but never used",
+    "location": {
+      "file": "src/a,b:c%.ts",
+      "start": {
+        "line": 1,
+        "column": 7
+      },
+      "end": {
+        "line": 1,
+        "column": 8
+      }
+    },
+    "labels": []
+  },
+  {
+    "kind": "info",
+    "reason": "100% checked",
+    "location": null,
+    "labels": []
+  }
+]
+"#;
+		assert_eq!(format(DiagnosticsFormat::JSON), expected);
+	}
+
+	#[test]
+	fn sarif() {
+		let region = |line: usize, start: usize, end: usize| {
+			serde_json::json!({
+				"artifactLocation": { "uri": "src/a,b:c%.ts" },
+				"region": { "startLine": line, "startColumn": start, "endLine": line, "endColumn": end }
+			})
+		};
+		let expected = serde_json::json!({
+			"$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+			"version": "2.1.0",
+			"runs": [{
+				"tool": {
+					"driver": {
+						"name": "ezno",
+						"version": env!("CARGO_PKG_VERSION"),
+						"informationUri": env!("CARGO_PKG_HOMEPAGE"),
+					}
+				},
+				"columnKind": "unicodeCodePoints",
+				"results": [
+					{
+						"ruleId": "E0003",
+						"level": "error",
+						"message": { "text": "Type 1 is not assignable to type string" },
+						"locations": [{ "physicalLocation": region(2, 17, 18) }],
+						"relatedLocations": [{
+							"id": 0,
+							"message": { "text": "Variable declared here\nwith a type" },
+							"physicalLocation": region(2, 5, 14),
+						}],
+					},
+					{
+						"level": "warning",
+						"message": { "text": "'a' is declared but never used" },
+						"locations": [{ "physicalLocation": region(1, 7, 8) }],
+						"relatedLocations": [],
+					},
+					{
+						"level": "note",
+						"message": { "text": "100% checked" },
+						"locations": [],
+						"relatedLocations": [],
+					}
+				],
+			}]
+		});
+		let output: serde_json::Value =
+			serde_json::from_str(&format(DiagnosticsFormat::SARIF)).unwrap();
+		assert_eq!(output, expected);
+	}
+
+	#[test]
+	fn github() {
+		let expected = "::error title=E0003,file=src/a%2Cb%3Ac%25.ts,line=2,col=17,endLine=2,endColumn=18::Type 1 is not assignable to type string%0Asrc/a,b:c%25.ts:2:5: Variable declared here%0Awith a type
+::warning file=src/a%2Cb%3Ac%25.ts,line=1,col=7,endLine=1,endColumn=8::'a' is declared but never used
+::notice::100%25 checked
+";
+		assert_eq!(format(DiagnosticsFormat::GitHub), expected);
+	}
+}
@@ -0,0 +1,125 @@
+use std::mem;
+
+use checker::types::FunctionEffect;
+use parser::{
+	expressions::{
+		operators::UnaryOperator, FunctionArgument, MultipleExpression, SpecialOperators,
+	},
+	visiting::{Chain, VisitorMut},
+	ASTNode, BlockLikeMut, Declaration, Expression, NumberRepresentation, SourceId, Statement,
+	StatementOrDeclaration,
+};
+
+use super::inlining::get_function;
+use crate::build::CheckingOutputWithoutDiagnostics;
+
+/// Constant functions from the internal definitions which only exist for checking
+const INTRINSICS: &[&str] = &[
+	"print_type",
+	"debug_type",
+	"print_and_debug_type",
+	"print_constraint",
+	"debug_type_rust",
+	"debug_type_rust_independent",
+	"debug_type_independent",
+	"debug_effects_rust",
+	"debug_effects",
+	"is_dependent",
+	"print_environment_state",
+	"debug_context",
+	"context_id",
+	"context_id_chain",
+	"satisfies",
+	"compile_type_to_object",
+];
+
+/// A transformer that removes things which only have meaning to the checker
+/// - Expression statements of calls the checker marked as [`checker::SpecialExpressions`] (such
+///   as `print_type(x)`) or of calls to intrinsics are removed. Elsewhere the call is replaced
+///   with `void 0` (or the argument for the `satisfies` function). Arguments are not evaluated
+/// - `satisfies`, `as` and `!` are unwrapped to their value
+/// - `declare` variable statements are removed
+pub struct CompileOutRemover;
+
+impl VisitorMut<BlockLikeMut<'_>, CheckingOutputWithoutDiagnostics> for CompileOutRemover {
+	fn visit_mut(
+		&mut self,
+		block: &mut BlockLikeMut,
+		data: &mut CheckingOutputWithoutDiagnostics,
+		chain: &Chain,
+	) {
+		let source = chain.get_module();
+		block.items.retain(|item| match item {
+			StatementOrDeclaration::Statement(Statement::Expression(
+				MultipleExpression::Single(expression),
+			)) => compiled_out(expression, data, source).is_none(),
+			StatementOrDeclaration::Declaration(Declaration::DeclareVariable(..)) => false,
+			_ => true,
+		});
+	}
+}
+
+impl VisitorMut<Expression, CheckingOutputWithoutDiagnostics> for CompileOutRemover {
+	fn visit_mut(
+		&mut self,
+		item: &mut Expression,
+		data: &mut CheckingOutputWithoutDiagnostics,
+		chain: &Chain,
+	) {
+		// `x as unknown as T`
+		while let Expression::SpecialOperators(
+			SpecialOperators::Satisfies { value, .. }
+			| SpecialOperators::AsCast { value, .. }
+			| SpecialOperators::NonNullAssertion(value),
+			_,
+		) = item
+		{
+			let position = value.get_position();
+			*item = mem::replace(&mut **value, Expression::Null(position));
+		}
+
+		let Some(is_satisfies) = compiled_out(item, data, chain.get_module()) else { return };
+		let Expression::FunctionCall { arguments, position, .. } = item else { return };
+		let position = *position;
+		let argument = arguments.iter_mut().find_map(|argument| match argument {
+			FunctionArgument::Standard(argument) if is_satisfies => Some(argument),
+			_ => None,
+		});
+		*item = if let Some(argument) = argument {
+			mem::replace(argument, Expression::Null(position))
+		} else {
+			Expression::UnaryOperation {
+				operator: UnaryOperator::Void,
+				operand: Box::new(Expression::NumberLiteral(
+					NumberRepresentation::from(0.),
+					position,
+				)),
+				position,
+			}
+		};
+	}
+}
+
+/// `Some` if the expression is a call that is compiled out. `true` if it is to the `satisfies`
+/// function, whose argument is kept
+fn compiled_out(
+	expression: &Expression,
+	data: &CheckingOutputWithoutDiagnostics,
+	source: SourceId,
+) -> Option<bool> {
+	let Expression::FunctionCall { function, position, .. } = expression else { return None };
+	let module = data.modules.get(&source)?;
+	let intrinsic = get_function(function, data, source).and_then(|id| {
+		match data.types.get_function_from_id(id).effect {
+			FunctionEffect::Constant(ref name) if INTRINSICS.contains(&name.as_str()) => {
+				Some(name.as_str())
+			}
+			_ => None,
+		}
+	});
+	if let Some(name) = intrinsic {
+		Some(name == "satisfies")
+	} else {
+		module.mappings.special_expressions.get_exact(*position).map(|_| false)
+	}
+}
@@ -0,0 +1,217 @@
+use std::fmt::Write;
+
+use checker::{Constant, Type};
+use parser::{
+	expressions::{
+		operators::UnaryOperator, MultipleExpression, SpecialOperators, TemplateLiteral,
+		TemplateLiteralPart,
+	},
+	visiting::{Annex, Chain, VisitOptions, Visitable, VisitorMut, VisitorReceiver},
+	ASTNode, Expression, NumberRepresentation, Quoted, SourceId, Span,
+};
+
+use crate::build::CheckingOutputWithoutDiagnostics;
+
+/// A transformer that replaces side effect free expressions, whose synthesised type is a single
+/// constant, with the literal. Skips expressions which read variables that are assigned in inner
+/// scopes (as the checker does not track their value after those)
+pub struct ConstantFolder;
+
+impl VisitorMut<Expression, CheckingOutputWithoutDiagnostics> for ConstantFolder {
+	fn visit_mut(
+		&mut self,
+		item: &mut Expression,
+		data: &mut CheckingOutputWithoutDiagnostics,
+		chain: &Chain,
+	) {
+		let source = chain.get_module();
+		if is_literal(item)
+			|| !is_side_effect_free(item)
+			|| reads_variable_assigned_in_inner_scope(item, data, source)
+		{
+			return;
+		}
+		let position = item.get_position();
+		if let Some(literal) = get_constant(item, data, source)
+			.and_then(|constant| constant_to_literal(constant, position))
+		{
+			*item = literal;
+		}
+	}
+}
+
+pub(crate) fn get_constant<'a>(
+	expression: &Expression,
+	data: &'a CheckingOutputWithoutDiagnostics,
+	source: SourceId,
+) -> Option<&'a Constant> {
+	let module = data.modules.get(&source)?;
+	let instance = module.mappings.expressions_to_instances.get_exact(expression.get_position())?;
+	if let Type::Constant(constant) = data.types.get_type_by_id(instance.clone().get_value()) {
+		Some(constant)
+	} else {
+		None
+	}
+}
+
+pub(crate) fn constant_to_literal(constant: &Constant, position: Span) -> Option<Expression> {
+	match constant {
+		Constant::Number(value) => {
+			let value = **value;
+			if !value.is_finite() {
+				None
+			} else if value.is_sign_negative() {
+				// As a unary operation so that it is spaced from a preceding `-`
+				let literal =
+					Expression::NumberLiteral(NumberRepresentation::from(-value), position);
+				Some(Expression::UnaryOperation {
+					operator: UnaryOperator::Negation,
+					operand: Box::new(literal),
+					position,
+				})
+			} else {
+				Some(Expression::NumberLiteral(NumberRepresentation::from(value), position))
+			}
+		}
+		Constant::String(value) => {
+			let escaped = escape_constant_string(value);
+			// Long strings (as opposed to references to them) are only inlined if it does not
+			// increase the size
+			let original_length = (position.end - position.start) as usize;
+			(value.len() <= 16 || escaped.len() + 2 <= original_length)
+				.then_some(Expression::StringLiteral(escaped, Quoted::Double, position))
+		}
+		Constant::Boolean(value) => Some(Expression::BooleanLiteral(*value, position)),
+		// `undefined` can be shadowed
+		Constant::Undefined => Some(Expression::UnaryOperation {
+			operator: UnaryOperator::Void,
+			operand: Box::new(Expression::NumberLiteral(NumberRepresentation::from(0.), position)),
+			position,
+		}),
+		Constant::Null => Some(Expression::Null(position)),
+		Constant::NaN | Constant::Symbol { .. } => None,
+	}
+}
+
+pub(crate) fn escape_string(value: &str) -> String {
+	let mut escaped = String::with_capacity(value.len());
+	for chr in value.chars() {
+		match chr {
+			'"' => escaped.push_str("\\\""),
+			'\\' => escaped.push_str("\\\\"),
+			'\n' => escaped.push_str("\\n"),
+			'\r' => escaped.push_str("\\r"),
+			'\t' => escaped.push_str("\\t"),
+			'\u{2028}' | '\u{2029}' => write!(escaped, "\\u{:04x}", chr as u32).unwrap(),
+			chr if chr.is_control() => write!(escaped, "\\u{:04x}", chr as u32).unwrap(),
+			chr => escaped.push(chr),
+		}
+	}
+	escaped
+}
+
+/// Strings from the checker keep the escapes of the literals they came from, so only quotes (which
+/// may have been in a single quoted literal) and characters which cannot appear in a literal are
+/// escaped
+pub(crate) fn escape_constant_string(value: &str) -> String {
+	let mut escaped = String::with_capacity(value.len());
+	let mut chars = value.chars();
+	while let Some(chr) = chars.next() {
+		match chr {
+			'\\' => {
+				escaped.push('\\');
+				escaped.extend(chars.next());
+			}
+			'"' => escaped.push_str("\\\""),
+			'\n' => escaped.push_str("\\n"),
+			'\r' => escaped.push_str("\\r"),
+			'\u{2028}' | '\u{2029}' => write!(escaped, "\\u{:04x}", chr as u32).unwrap(),
+			chr => escaped.push(chr),
+		}
+	}
+	escaped
+}
+
+fn is_literal(expression: &Expression) -> bool {
+	matches!(
+		expression,
+		Expression::NumberLiteral(..)
+			| Expression::StringLiteral(..)
+			| Expression::BooleanLiteral(..)
+			| Expression::Null(..)
+	)
+}
+
+/// Whether evaluating the expression cannot have an effect (ignoring `valueOf` etc)
+pub(crate) fn is_side_effect_free(expression: &Expression) -> bool {
+	match expression {
+		Expression::NumberLiteral(..)
+		| Expression::StringLiteral(..)
+		| Expression::BooleanLiteral(..)
+		| Expression::Null(..)
+		| Expression::VariableReference(..) => true,
+		Expression::ParenthesizedExpression(inner, _) => {
+			matches!(&**inner, MultipleExpression::Single(inner) if is_side_effect_free(inner))
+		}
+		Expression::BinaryOperation { lhs, rhs, .. } => {
+			is_side_effect_free(lhs) && is_side_effect_free(rhs)
+		}
+		Expression::UnaryOperation { operator, operand, .. } => {
+			!matches!(
+				operator,
+				UnaryOperator::Await
+					| UnaryOperator::Delete
+					| UnaryOperator::Yield
+					| UnaryOperator::DelegatedYield
+			) && is_side_effect_free(operand)
+		}
+		Expression::ConditionalTernary { condition, truthy_result, falsy_result, .. } => {
+			is_side_effect_free(condition)
+				&& is_side_effect_free(truthy_result)
+				&& is_side_effect_free(falsy_result)
+		}
+		Expression::TemplateLiteral(TemplateLiteral { tag: None, parts, .. }) => {
+			parts.iter().all(|part| match part {
+				TemplateLiteralPart::Static(_) => true,
+				TemplateLiteralPart::Dynamic(expression) => is_side_effect_free(expression),
+			})
+		}
+		Expression::SpecialOperators(
+			SpecialOperators::Satisfies { value, .. }
+			| SpecialOperators::AsCast { value, .. }
+			| SpecialOperators::NonNullAssertion(value),
+			_,
+		) => is_side_effect_free(value),
+		_ => false,
+	}
+}
+
+/// See [`checker::VariableUsage::assigned_in_inner_scopes`]
+pub(crate) fn reads_variable_assigned_in_inner_scope(
+	expression: &Expression,
+	data: &CheckingOutputWithoutDiagnostics,
+	source: SourceId,
+) -> bool {
+	struct FindReads<'a> {
+		data: &'a CheckingOutputWithoutDiagnostics,
+		source: SourceId,
+		found: bool,
+	}
+
+	impl VisitorReceiver<()> for FindReads<'_> {
+		fn visit_expression(&mut self, expression: &Expression, _data: &mut (), _chain: &Chain) {
+			if let Expression::VariableReference(_, position) = expression {
+				let usage = &self.data.variable_usage;
+				self.found |= usage
+					.references
+					.get(&position.with_source(self.source))
+					.is_some_and(|id| usage.assigned_in_inner_scopes.contains(id));
+			}
+		}
+	}
+
+	let mut finder = FindReads { data, source, found: false };
+	let mut chain = Chain::new();
+	expression.visit(&mut finder, &mut (), &VisitOptions::default(), &mut Annex::new(&mut chain));
+	finder.found
+}
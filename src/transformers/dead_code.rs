@@ -0,0 +1,254 @@
+use std::mem;
+
+use checker::TypeMappings;
+use parser::{
+	declarations::{variable::DeclarationExpression, VariableDeclaration, VariableDeclarationItem},
+	expressions::MultipleExpression,
+	source_map::Nullable,
+	statements::{
+		ConditionalElseStatement, ForLoopCondition, ForLoopStatementInitializer, IfStatement,
+		UnconditionalElseStatement,
+	},
+	visiting::{Chain, VisitorMut},
+	ASTNode, Block, BlockLikeMut, BlockOrSingleStatement, Declaration, Expression, Span, Statement,
+	StatementOrDeclaration, VariableField,
+};
+
+use super::{constant_folding::is_side_effect_free, declares_var};
+use crate::build::CheckingOutputWithoutDiagnostics;
+
+/// A transformer that removes code which the checker found is never evaluated
+/// - Branches of `if` statements and ternaries whose condition always has the same truthiness
+/// - Statements following a `return`, `throw` etc
+/// - Bodies of `while` and `for` loops which do not run any iterations
+pub struct DeadCodeEliminator;
+
+impl VisitorMut<Expression, CheckingOutputWithoutDiagnostics> for DeadCodeEliminator {
+	fn visit_mut(
+		&mut self,
+		item: &mut Expression,
+		data: &mut CheckingOutputWithoutDiagnostics,
+		chain: &Chain,
+	) {
+		let Expression::ConditionalTernary { condition, .. } = item else { return };
+		let Some(mappings) = data.modules.get(&chain.get_module()).map(|module| &module.mappings)
+		else {
+			return;
+		};
+		let Some(&value) = mappings.dead_branches.get_exact(condition.get_position()) else {
+			return;
+		};
+
+		let Expression::ConditionalTernary { condition, truthy_result, falsy_result, position } =
+			mem::replace(item, Expression::Null(Span::NULL))
+		else {
+			unreachable!()
+		};
+		let result = if value { *truthy_result } else { *falsy_result };
+		*item = if is_side_effect_free(&condition) {
+			result
+		} else {
+			let expression = MultipleExpression::Multiple {
+				lhs: Box::new(MultipleExpression::Single(*condition)),
+				rhs: result,
+				position,
+			};
+			Expression::ParenthesizedExpression(Box::new(expression), position)
+		};
+	}
+}
+
+impl VisitorMut<BlockLikeMut<'_>, CheckingOutputWithoutDiagnostics> for DeadCodeEliminator {
+	fn visit_mut(
+		&mut self,
+		block: &mut BlockLikeMut,
+		data: &mut CheckingOutputWithoutDiagnostics,
+		chain: &Chain,
+	) {
+		let Some(mappings) = data.modules.get(&chain.get_module()).map(|module| &module.mappings)
+		else {
+			return;
+		};
+
+		for item in mem::take(block.items) {
+			let position = item.get_position();
+			if mappings.unreachable_statements.get_exact(position).is_some() && !is_hoisted(&item) {
+				continue;
+			}
+			let never_iterates = mappings.loops_without_iterations.get_exact(position).is_some();
+
+			match item {
+				StatementOrDeclaration::Statement(Statement::If(statement)) => {
+					fold_if_statement(statement, mappings, block.items);
+				}
+				StatementOrDeclaration::Statement(Statement::WhileLoop(mut statement))
+					if never_iterates =>
+				{
+					if !is_multiple_side_effect_free(&statement.condition) {
+						statement.inner = empty_body(statement.inner.get_position());
+						block.items.push(Statement::WhileLoop(statement).into());
+					}
+				}
+				StatementOrDeclaration::Statement(Statement::ForLoop(mut statement))
+					if never_iterates =>
+				{
+					let ForLoopCondition::Statements {
+						initialiser, condition, afterthought, ..
+					} = &mut statement.condition
+					else {
+						block.items.push(Statement::ForLoop(statement).into());
+						continue;
+					};
+					let initialiser_is_side_effect_free = match initialiser {
+						Some(ForLoopStatementInitializer::Expression(expression)) => {
+							is_multiple_side_effect_free(expression)
+						}
+						Some(ForLoopStatementInitializer::VariableDeclaration(
+							VariableDeclaration::ConstDeclaration { declarations, .. },
+						)) => are_declarations_side_effect_free(declarations),
+						Some(ForLoopStatementInitializer::VariableDeclaration(
+							VariableDeclaration::LetDeclaration { declarations, .. },
+						)) => are_declarations_side_effect_free(declarations),
+						Some(ForLoopStatementInitializer::VarStatement(_)) => false,
+						None => true,
+					};
+					let condition_is_side_effect_free =
+						condition.as_ref().is_none_or(is_multiple_side_effect_free);
+
+					if !(initialiser_is_side_effect_free && condition_is_side_effect_free) {
+						*afterthought = None;
+						statement.inner = empty_body(statement.inner.get_position());
+						block.items.push(Statement::ForLoop(statement).into());
+					}
+				}
+				item => block.items.push(item),
+			}
+		}
+	}
+}
+
+/// Adds the statements that are left after removing the branches which are never taken
+fn fold_if_statement(
+	mut statement: IfStatement,
+	mappings: &TypeMappings,
+	items: &mut Vec<StatementOrDeclaration>,
+) {
+	// The first condition is always evaluated, so can be kept as a statement
+	let value = get_dead_branch(&statement.condition, mappings, true).filter(|value| {
+		// `var`s are hoisted out of branches, so those with them cannot be removed
+		if *value {
+			!statement
+				.else_conditions
+				.iter()
+				.any(|else_condition| declares_var(&else_condition.inner))
+				&& !statement
+					.trailing_else
+					.as_ref()
+					.is_some_and(|trailing_else| declares_var(&trailing_else.inner))
+		} else {
+			!declares_var(&statement.inner)
+		}
+	});
+	if let Some(value) = value {
+		let MultipleExpression::Single(condition) = &statement.condition else { unreachable!() };
+		if !is_side_effect_free(condition) {
+			items.push(Statement::Expression(statement.condition.clone()).into());
+		}
+
+		if value {
+			push_branch(statement.inner, items);
+		} else if statement.else_conditions.is_empty() {
+			if let Some(UnconditionalElseStatement { inner, .. }) = statement.trailing_else {
+				push_branch(inner, items);
+			}
+		} else {
+			let ConditionalElseStatement { condition, inner, .. } =
+				statement.else_conditions.remove(0);
+			fold_if_statement(IfStatement { condition, inner, ..statement }, mappings, items);
+		}
+		return;
+	}
+
+	let mut else_conditions = Vec::with_capacity(statement.else_conditions.len());
+	let mut rest = mem::take(&mut statement.else_conditions).into_iter();
+	while let Some(else_condition) = rest.next() {
+		match get_dead_branch(&else_condition.condition, mappings, false) {
+			Some(true)
+				if !rest
+					.as_slice()
+					.iter()
+					.any(|else_condition| declares_var(&else_condition.inner))
+					&& !statement
+						.trailing_else
+						.as_ref()
+						.is_some_and(|trailing_else| declares_var(&trailing_else.inner)) =>
+			{
+				let ConditionalElseStatement { inner, position, .. } = else_condition;
+				statement.trailing_else = Some(UnconditionalElseStatement { inner, position });
+				break;
+			}
+			Some(false) if !declares_var(&else_condition.inner) => {}
+			_ => else_conditions.push(else_condition),
+		}
+	}
+	statement.else_conditions = else_conditions;
+	items.push(Statement::If(statement).into());
+}
+
+/// `None` if the condition is not known
+fn get_dead_branch(
+	condition: &MultipleExpression,
+	mappings: &TypeMappings,
+	allow_side_effects: bool,
+) -> Option<bool> {
+	let MultipleExpression::Single(expression) = condition else { return None };
+	if !allow_side_effects && !is_side_effect_free(expression) {
+		return None;
+	}
+	mappings.dead_branches.get_exact(condition.get_position()).copied()
+}
+
+fn push_branch(branch: BlockOrSingleStatement, items: &mut Vec<StatementOrDeclaration>) {
+	match branch {
+		// Can only inline if the declarations would not leak
+		BlockOrSingleStatement::Braced(block)
+			if block
+				.0
+				.iter()
+				.any(|item| matches!(item, StatementOrDeclaration::Declaration(_))) =>
+		{
+			items.push(Statement::Block(block).into());
+		}
+		BlockOrSingleStatement::Braced(block) => items.extend(block.0),
+		BlockOrSingleStatement::SingleStatement(statement) => items.push((*statement).into()),
+	}
+}
+
+fn empty_body(position: Span) -> BlockOrSingleStatement {
+	BlockOrSingleStatement::Braced(Block(Vec::new(), position))
+}
+
+/// Function declarations and `var`s can be used before the unreachable code
+fn is_hoisted(item: &StatementOrDeclaration) -> bool {
+	matches!(item, StatementOrDeclaration::Declaration(Declaration::Function(_)))
+		|| declares_var(item)
+}
+
+fn is_multiple_side_effect_free(expression: &MultipleExpression) -> bool {
+	match expression {
+		MultipleExpression::Multiple { lhs, rhs, .. } => {
+			is_multiple_side_effect_free(lhs) && is_side_effect_free(rhs)
+		}
+		MultipleExpression::Single(expression) => is_side_effect_free(expression),
+	}
+}
+
+/// Destructuring could call getters, so only declarations of names
+fn are_declarations_side_effect_free<T: DeclarationExpression>(
+	declarations: &[VariableDeclarationItem<T>],
+) -> bool {
+	declarations.iter().all(|declaration| {
+		matches!(declaration.name.get_ast_ref(), VariableField::Name(_))
+			&& declaration.expression.as_option_expression_ref().is_none_or(is_side_effect_free)
+	})
+}
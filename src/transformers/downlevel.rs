@@ -0,0 +1,1248 @@
+use std::{
+	collections::{HashMap, HashSet},
+	mem,
+};
+
+use parser::{
+	ast::{
+		ClassDeclaration, ClassFunction, ClassMember, ClassProperty, ExportDeclaration,
+		ExpressionOrBlock, FunctionArgument, FunctionBody, FunctionParameters, LHSOfAssignment,
+		MethodHeader, MultipleExpression, ReturnStatement, SpreadParameter, SuperReference,
+		VariableDeclaration, VariableDeclarationItem, VariableOrPropertyAccess,
+	},
+	declarations::export::Exportable,
+	expressions::{
+		object_literal::{ObjectLiteral, ObjectLiteralMember},
+		operators::{
+			BinaryAssignmentOperator, BinaryOperator, IncrementOrDecrement, UnaryOperator,
+			UnaryPostfixAssignmentOperator, UnaryPrefixAssignmentOperator,
+		},
+	},
+	functions::GeneratorSpecifier,
+	property_key::PublicOrPrivate,
+	source_map::Nullable,
+	visiting::{
+		Annex, BlockItemMut, Chain, ImmutableVariableOrProperty, VisitOptions, Visitable,
+		VisitorMut, VisitorMutReceiver, VisitorReceiver,
+	},
+	ASTNode, Block, BlockLikeMut, Declaration, Decorated, Expression,
+	ExpressionOrStatementPosition, ExpressionPosition, FunctionHeader, NumberRepresentation,
+	PropertyKey, PropertyReference, Span, Statement, StatementOrDeclaration, VariableField,
+	VariableIdentifier, WithComment,
+};
+
+use super::synthesised;
+use crate::{
+	build::{CheckingOutputWithoutDiagnostics, Target},
+	bundler::unique,
+};
+
+/// A transformer that rewrites syntax newer than the [`Target`]
+/// - ES2016: `a ** b` to `Math.pow(a, b)`
+/// - ES2018: object spread to `Object.assign`
+/// - ES2020: optional chaining and `??` to conditionals on `== null`
+/// - ES2021: `a ||= b` to `a || (a = b)` (and the same for `&&=` and `??=`)
+/// - ES2022: class fields to assignments in the constructor (or after the class if static),
+///   private fields to `WeakMap`s and private methods to functions called with `.call`
+///
+/// Blocks are lowered when visited. Temporary variables are declared at the top of the block
+pub struct Downleveller {
+	pub target: Target,
+	/// Names in the current module, which temporary variables must not shadow
+	names: HashSet<String>,
+}
+
+impl Downleveller {
+	#[must_use]
+	pub fn new(target: Target) -> Self {
+		Self { target, names: HashSet::new() }
+	}
+}
+
+impl VisitorMut<BlockLikeMut<'_>, CheckingOutputWithoutDiagnostics> for Downleveller {
+	fn visit_mut(
+		&mut self,
+		block: &mut BlockLikeMut,
+		_data: &mut CheckingOutputWithoutDiagnostics,
+		chain: &Chain,
+	) {
+		// The top of the module is visited first
+		if chain.get_innermost_scope().is_none() {
+			let mut names = Names::default();
+			let mut chain = Chain::new();
+			for item in block.items.iter() {
+				item.visit(
+					&mut names,
+					&mut (),
+					&VisitOptions::default(),
+					&mut Annex::new(&mut chain),
+				);
+			}
+			self.names = names.0;
+		}
+
+		let mut lowering =
+			Lowering { target: self.target, names: &mut self.names, temporaries: Vec::new() };
+
+		// Class declarations need declarations before them and statements after them
+		if self.target < Target::ES2022 {
+			let mut index = 0;
+			while index < block.items.len() {
+				let lowered = class_declaration(&mut block.items[index]).and_then(|class| {
+					let name = class.name.as_option_str()?.to_owned();
+					let reference =
+						Expression::VariableReference(name, synthesised(class.position));
+					Some(lowering.lower_class(class, Some(&reference)))
+				});
+				if let Some(LoweredClass { before, after }) = lowered {
+					let (before_count, after_count) = (before.len(), after.len());
+					block
+						.items
+						.splice((index + 1)..=index, after.into_iter().map(expression_statement));
+					block.items.splice(
+						index..index,
+						before.into_iter().map(|(name, value)| const_declaration(name, value)),
+					);
+					index += before_count + after_count;
+				}
+				index += 1;
+			}
+		}
+
+		// Nested blocks are lowered when they are visited
+		let options = VisitOptions { visit_nested_blocks: false, ..VisitOptions::default() };
+		let mut chain = chain.clone();
+		for item in block.items.iter_mut() {
+			item.visit_mut(&mut lowering, &mut (), &options, &mut Annex::new(&mut chain));
+		}
+
+		if !lowering.temporaries.is_empty() {
+			let index =
+				block.items.iter().position(|item| !is_prologue(item)).unwrap_or(block.items.len());
+			block.items.insert(index, let_declaration(lowering.temporaries));
+		}
+	}
+}
+
+/// Lowers the expressions of one block
+struct Lowering<'a> {
+	target: Target,
+	names: &'a mut HashSet<String>,
+	/// Declared with `let` at the top of the block
+	temporaries: Vec<String>,
+}
+
+impl VisitorMutReceiver<()> for Lowering<'_> {
+	fn visit_expression_mut(
+		&mut self,
+		expression: &mut Expression,
+		_data: &mut (),
+		_chain: &Chain,
+	) {
+		if needs_lowering(expression, self.target) {
+			self.lower(expression);
+		}
+
+		// Only blocks are lowered, so arrow functions that need it are given one
+		if let Expression::ArrowFunction(function) = expression {
+			if let ExpressionOrBlock::Expression(ref body) = function.body {
+				let mut finder = FindLowerable { target: self.target, found: false };
+				body.visit(
+					&mut finder,
+					&mut (),
+					&VisitOptions::default(),
+					&mut Annex::new(&mut Chain::new()),
+				);
+				if finder.found {
+					let position = body.get_position();
+					let ExpressionOrBlock::Expression(body) = mem::replace(
+						&mut function.body,
+						ExpressionOrBlock::Block(Block(Vec::new(), position)),
+					) else {
+						unreachable!()
+					};
+					let statement = Statement::Return(ReturnStatement(
+						Some(MultipleExpression::Single(*body)),
+						position,
+					));
+					function.body = ExpressionOrBlock::Block(Block(
+						vec![StatementOrDeclaration::Statement(statement)],
+						position,
+					));
+				}
+			}
+		}
+	}
+}
+
+impl Lowering<'_> {
+	fn temporary(&mut self) -> String {
+		let name = unique("_ref", self.names, |_| false);
+		self.temporaries.push(name.clone());
+		name
+	}
+
+	/// For using `expression` twice. The first is evaluated first and assigns to a temporary
+	/// variable if `expression` is not simple
+	fn reuse(&mut self, expression: Expression) -> (Expression, Expression) {
+		if is_simple(&expression) {
+			(expression.clone(), expression)
+		} else {
+			let position = synthesised(expression.get_position());
+			let name = self.temporary();
+			(parenthesise(assign(name.clone(), expression, position)), reference(name, position))
+		}
+	}
+
+	/// Should only be called if [`needs_lowering`]
+	fn lower(&mut self, expression: &mut Expression) {
+		*expression = match take(expression) {
+			Expression::BinaryOperation {
+				lhs,
+				operator: BinaryOperator::Exponent,
+				rhs,
+				position,
+			} => math_pow(*lhs, *rhs, position),
+			Expression::BinaryOperation {
+				lhs,
+				operator: BinaryOperator::NullCoalescing,
+				rhs,
+				position,
+			} => self.nullish_coalescing(*lhs, *rhs, position),
+			Expression::BinaryAssignmentOperation {
+				lhs,
+				operator: BinaryAssignmentOperator::ExponentAssign,
+				rhs,
+				position,
+			} => {
+				let (read, write) = self.split_reference(lhs, false);
+				Expression::Assignment {
+					lhs: LHSOfAssignment::VariableOrPropertyAccess(write),
+					rhs: Box::new(math_pow(read, *rhs, position)),
+					position,
+				}
+			}
+			Expression::BinaryAssignmentOperation { lhs, operator, rhs, position } => {
+				let (read, write) = self.split_reference(lhs, true);
+				let assignment = parenthesise(Expression::Assignment {
+					lhs: LHSOfAssignment::VariableOrPropertyAccess(write),
+					rhs,
+					position,
+				});
+				match operator.into() {
+					BinaryOperator::NullCoalescing if self.target < Target::ES2020 => {
+						self.nullish_coalescing(read, assignment, position)
+					}
+					operator => Expression::BinaryOperation {
+						lhs: Box::new(read),
+						operator,
+						rhs: Box::new(assignment),
+						position,
+					},
+				}
+			}
+			Expression::ObjectLiteral(literal) => object_assign(literal),
+			Expression::ClassExpression(class) => self.lower_class_expression(class),
+			mut expression => {
+				self.lower_optional_chain(&mut expression);
+				expression
+			}
+		}
+	}
+
+	/// `a ?? b` to `a != null ? a : b`
+	fn nullish_coalescing(
+		&mut self,
+		lhs: Expression,
+		rhs: Expression,
+		position: Span,
+	) -> Expression {
+		let (test, value) = self.reuse(lhs);
+		let position = synthesised(position);
+		parenthesise(Expression::ConditionalTernary {
+			condition: Box::new(binary(test, BinaryOperator::NotEqual, Expression::Null(position))),
+			truthy_result: Box::new(value),
+			falsy_result: Box::new(rhs),
+			position,
+		})
+	}
+
+	/// Splits an assignment target into a read and a write of the same property. Objects and
+	/// indexes are only evaluated once, in the read if `read_first` else in the write
+	fn split_reference(
+		&mut self,
+		target: VariableOrPropertyAccess,
+		read_first: bool,
+	) -> (Expression, VariableOrPropertyAccess) {
+		let order = |(first, second)| if read_first { (first, second) } else { (second, first) };
+		match target {
+			VariableOrPropertyAccess::Variable(name, position) => (
+				Expression::VariableReference(name.clone(), position),
+				VariableOrPropertyAccess::Variable(name, position),
+			),
+			VariableOrPropertyAccess::PropertyAccess { parent, property, position } => {
+				let (read_parent, write_parent) = order(self.reuse(*parent));
+				let read = Expression::PropertyAccess {
+					parent: Box::new(read_parent),
+					property: property.clone(),
+					is_optional: false,
+					position,
+				};
+				let write = VariableOrPropertyAccess::PropertyAccess {
+					parent: Box::new(write_parent),
+					property,
+					position,
+				};
+				(read, write)
+			}
+			VariableOrPropertyAccess::Index { indexee, indexer, position } => {
+				let (read_indexee, write_indexee) = order(self.reuse(*indexee));
+				let key = match *indexer {
+					MultipleExpression::Single(key) => key,
+					multiple @ MultipleExpression::Multiple { .. } => {
+						let position = multiple.get_position();
+						Expression::ParenthesizedExpression(Box::new(multiple), position)
+					}
+				};
+				let (read_key, write_key) = order(self.reuse(key));
+				let read = Expression::Index {
+					indexee: Box::new(read_indexee),
+					indexer: Box::new(MultipleExpression::Single(read_key)),
+					is_optional: false,
+					position,
+				};
+				let write = VariableOrPropertyAccess::Index {
+					indexee: Box::new(write_indexee),
+					indexer: Box::new(MultipleExpression::Single(write_key)),
+					position,
+				};
+				(read, write)
+			}
+		}
+	}
+
+	/// `a?.b.c` to `a == null ? void 0 : a.b.c`. Any `?.` before the outermost one are in the
+	/// condition and are lowered when it is visited
+	fn lower_optional_chain(&mut self, expression: &mut Expression) {
+		let Some(depth) = optional_link_depth(expression) else { return };
+		let position = synthesised(expression.get_position());
+		let condition = match chain_link(expression, depth) {
+			Expression::FunctionCall { function, arguments, is_optional, .. } => {
+				*is_optional = false;
+				self.optional_call_condition(function, arguments)
+			}
+			Expression::PropertyAccess { parent: object, is_optional, .. }
+			| Expression::Index { indexee: object, is_optional, .. } => {
+				*is_optional = false;
+				let (test, reference) = self.reuse(take(object));
+				**object = reference;
+				test
+			}
+			_ => unreachable!(),
+		};
+		let rest = take(expression);
+		*expression = parenthesise(Expression::ConditionalTernary {
+			condition: Box::new(binary(
+				condition,
+				BinaryOperator::Equal,
+				Expression::Null(position),
+			)),
+			truthy_result: Box::new(void_zero(position)),
+			falsy_result: Box::new(rest),
+			position,
+		});
+	}
+
+	/// For `f?.()`. Methods are called with `.call` so `this` is kept
+	fn optional_call_condition(
+		&mut self,
+		function: &mut Expression,
+		arguments: &mut Vec<FunctionArgument>,
+	) -> Expression {
+		match take(function) {
+			Expression::PropertyAccess { parent, property, is_optional, position } => {
+				let (object, this) = self.reuse(*parent);
+				let method = Expression::PropertyAccess {
+					parent: Box::new(object),
+					property,
+					is_optional,
+					position,
+				};
+				self.call_with_this(function, arguments, method, this)
+			}
+			Expression::Index { indexee, indexer, is_optional, position } => {
+				let (object, this) = self.reuse(*indexee);
+				let method =
+					Expression::Index { indexee: Box::new(object), indexer, is_optional, position };
+				self.call_with_this(function, arguments, method, this)
+			}
+			callee => {
+				let (test, reference) = self.reuse(callee);
+				*function = reference;
+				test
+			}
+		}
+	}
+
+	fn call_with_this(
+		&mut self,
+		function: &mut Expression,
+		arguments: &mut Vec<FunctionArgument>,
+		method: Expression,
+		this: Expression,
+	) -> Expression {
+		let position = synthesised(method.get_position());
+		let name = self.temporary();
+		*function = property(reference(name.clone(), position), "call", position);
+		arguments.insert(0, FunctionArgument::Standard(this));
+		parenthesise(assign(name, method, position))
+	}
+
+	/// Static fields are assigned to a temporary variable, which the expression evaluates to
+	fn lower_class_expression(
+		&mut self,
+		mut class: ClassDeclaration<ExpressionPosition>,
+	) -> Expression {
+		let position = synthesised(class.position);
+		let has_static_fields =
+			class.members.iter().any(|member| matches!(member.on, ClassMember::Property(true, _)));
+		let name = has_static_fields.then(|| self.temporary());
+		let class_reference = name.clone().map(|name| reference(name, position));
+		let LoweredClass { before, after } = self.lower_class(&mut class, class_reference.as_ref());
+
+		let mut expressions = Vec::new();
+		for (name, value) in before {
+			self.temporaries.push(name.clone());
+			expressions.push(assign(name, value, position));
+		}
+		let class = Expression::ClassExpression(class);
+		if let (Some(name), Some(class_reference)) = (name, class_reference) {
+			expressions.push(assign(name, class, position));
+			expressions.extend(after);
+			expressions.push(class_reference);
+		} else {
+			expressions.push(class);
+		}
+		sequence(expressions, position)
+	}
+
+	/// Moves fields into the constructor and private methods out of the class. `class_reference`
+	/// is required if there are static fields
+	fn lower_class<T: ExpressionOrStatementPosition>(
+		&mut self,
+		class: &mut ClassDeclaration<T>,
+		class_reference: Option<&Expression>,
+	) -> LoweredClass {
+		let mut lowered = LoweredClass::default();
+		if !is_lowerable_class(class) {
+			return lowered;
+		}
+		let position = synthesised(class.position);
+
+		// Getters and setters are not lowered, so neither are the other private members
+		let lower_private = !class.members.iter().any(|member| {
+			matches!(
+				&member.on,
+				ClassMember::Method(_, method)
+					if method.name.get_ast_ref().is_private()
+						&& matches!(method.header, MethodHeader::Get | MethodHeader::Set)
+			)
+		});
+		let mut fields = HashMap::new();
+		let mut methods = HashMap::new();
+		if lower_private {
+			for member in &class.members {
+				let (key, is_field) = match &member.on {
+					ClassMember::Property(_, property) => (property.key.get_ast_ref(), true),
+					ClassMember::Method(_, method) => (method.name.get_ast_ref(), false),
+					_ => continue,
+				};
+				if let PropertyKey::Ident(name, _, PublicOrPrivate::Private) = key {
+					let local = unique(&format!("_{name}"), self.names, |_| false);
+					if is_field {
+						lowered.before.push((local.clone(), new_weak_map(position)));
+						fields.insert(name.clone(), local);
+					} else {
+						methods.insert(name.clone(), local);
+					}
+				}
+			}
+		}
+		if !fields.is_empty() || !methods.is_empty() {
+			let mut private_names =
+				PrivateNames { fields: &fields, methods: &methods, lowering: self };
+			class.members.visit_mut(
+				&mut private_names,
+				&mut (),
+				&VisitOptions::default(),
+				&mut Annex::new(&mut Chain::new()),
+			);
+		}
+
+		let mut constructor_statements = Vec::new();
+		for member in mem::take(&mut class.members) {
+			match member.on {
+				ClassMember::Property(is_static, property)
+					if lower_private || !property.key.get_ast_ref().is_private() =>
+				{
+					let ClassProperty { key, value, position: property_position, .. } = property;
+					let mut value = value
+						.map_or_else(|| void_zero(synthesised(property_position)), |value| *value);
+					let object = if is_static {
+						let class_reference =
+							class_reference.expect("reference to class with static fields");
+						replace_this(&mut value, class_reference);
+						class_reference.clone()
+					} else {
+						Expression::ThisReference(position)
+					};
+					let initialiser = match key.get_ast() {
+						PropertyKey::Ident(name, _, PublicOrPrivate::Private) => {
+							let map = reference(fields[&name].clone(), position);
+							method_call(map, "set", vec![object, value], property_position)
+						}
+						PropertyKey::Ident(name, _, PublicOrPrivate::Public) => {
+							let target = VariableOrPropertyAccess::PropertyAccess {
+								parent: Box::new(object),
+								property: PropertyReference::Standard {
+									property: name,
+									is_private: false,
+								},
+								position: property_position,
+							};
+							assignment(target, value, property_position)
+						}
+						key => {
+							let indexer = match key {
+								PropertyKey::StringLiteral(value, quoted, position) => {
+									Expression::StringLiteral(value, quoted, position)
+								}
+								PropertyKey::NumberLiteral(value, position) => {
+									Expression::NumberLiteral(value, position)
+								}
+								PropertyKey::Computed(expression, _) => *expression,
+								PropertyKey::Ident(..) => unreachable!(),
+							};
+							let target = VariableOrPropertyAccess::Index {
+								indexee: Box::new(object),
+								indexer: Box::new(MultipleExpression::Single(indexer)),
+								position: property_position,
+							};
+							assignment(target, value, property_position)
+						}
+					};
+					if is_static {
+						lowered.after.push(initialiser);
+					} else {
+						constructor_statements.push(expression_statement(initialiser));
+					}
+				}
+				ClassMember::Method(_, method)
+					if lower_private && method.name.get_ast_ref().is_private() =>
+				{
+					let PropertyKey::Ident(ref name, ..) = method.name.get_ast_ref() else {
+						unreachable!()
+					};
+					let name = methods[name].clone();
+					lowered.before.push((name, method_to_function(method)));
+				}
+				on => class.members.push(Decorated { on, ..member }),
+			}
+		}
+		if !constructor_statements.is_empty() {
+			add_to_constructor(class, constructor_statements);
+		}
+		lowered
+	}
+}
+
+/// Declarations and statements for around a lowered class
+#[derive(Default)]
+struct LoweredClass {
+	/// `WeakMap`s for private fields and functions for private methods
+	before: Vec<(String, Expression)>,
+	/// Initialisers of static fields
+	after: Vec<Expression>,
+}
+
+/// Rewrites uses of private fields and methods, which are lowered to `WeakMap`s and functions
+struct PrivateNames<'a, 'b> {
+	fields: &'a HashMap<String, String>,
+	methods: &'a HashMap<String, String>,
+	lowering: &'a mut Lowering<'b>,
+}
+
+/// A write to a private field
+enum Write {
+	Assign(Expression),
+	Compound(BinaryOperator, Expression),
+	Update(IncrementOrDecrement, bool),
+}
+
+impl VisitorMutReceiver<()> for PrivateNames<'_, '_> {
+	fn visit_statement_mut(&mut self, statement: BlockItemMut, _data: &mut (), _chain: &Chain) {
+		// The value of statements is not used, so writes do not need to evaluate to it
+		if let BlockItemMut::StatementOrDeclaration(StatementOrDeclaration::Statement(
+			Statement::Expression(MultipleExpression::Single(expression)),
+		))
+		| BlockItemMut::SingleStatement(Statement::Expression(MultipleExpression::Single(
+			expression,
+		))) = statement
+		{
+			self.rewrite_write(expression, true);
+		}
+	}
+
+	fn visit_expression_mut(
+		&mut self,
+		expression: &mut Expression,
+		_data: &mut (),
+		_chain: &Chain,
+	) {
+		if self.rewrite_write(expression, false) {
+			return;
+		}
+		match expression {
+			Expression::FunctionCall { function, arguments, .. } => {
+				let Some(name) = self.private_name(function) else { return };
+				let Expression::PropertyAccess { parent, position, .. } = take(function) else {
+					unreachable!()
+				};
+				let position = synthesised(position);
+				if let Some(local) = self.methods.get(&name) {
+					**function = property(reference(local.clone(), position), "call", position);
+					arguments.insert(0, FunctionArgument::Standard(*parent));
+				} else {
+					let (object, this) = self.lowering.reuse(*parent);
+					let map = reference(self.fields[&name].clone(), position);
+					let value = method_call(map, "get", vec![object], position);
+					**function = property(value, "call", position);
+					arguments.insert(0, FunctionArgument::Standard(this));
+				}
+			}
+			Expression::PropertyAccess { .. } => {
+				let Some(name) = self.private_name(expression) else { return };
+				let Expression::PropertyAccess { parent, position, .. } = take(expression) else {
+					unreachable!()
+				};
+				*expression = if let Some(local) = self.methods.get(&name) {
+					reference(local.clone(), position)
+				} else {
+					let map = reference(self.fields[&name].clone(), synthesised(position));
+					method_call(map, "get", vec![*parent], position)
+				};
+			}
+			_ => {}
+		}
+	}
+}
+
+impl PrivateNames<'_, '_> {
+	fn private_name(&self, expression: &Expression) -> Option<String> {
+		if let Expression::PropertyAccess {
+			property: PropertyReference::Standard { property, is_private: true },
+			..
+		} = expression
+		{
+			(self.fields.contains_key(property) || self.methods.contains_key(property))
+				.then(|| property.clone())
+		} else {
+			None
+		}
+	}
+
+	/// The name of the `WeakMap` if `target` is a private field
+	fn field(&self, target: &VariableOrPropertyAccess) -> Option<String> {
+		if let VariableOrPropertyAccess::PropertyAccess {
+			property: PropertyReference::Standard { property, is_private: true },
+			..
+		} = target
+		{
+			self.fields.get(property).cloned()
+		} else {
+			None
+		}
+	}
+
+	fn rewrite_write(&mut self, expression: &mut Expression, is_statement: bool) -> bool {
+		let map = match &*expression {
+			Expression::Assignment {
+				lhs: LHSOfAssignment::VariableOrPropertyAccess(target),
+				..
+			}
+			| Expression::BinaryAssignmentOperation { lhs: target, .. }
+			| Expression::UnaryPrefixAssignmentOperation {
+				operator: UnaryPrefixAssignmentOperator::IncrementOrDecrement(_),
+				operand: target,
+				..
+			}
+			| Expression::UnaryPostfixAssignmentOperation { operand: target, .. } => self.field(target),
+			_ => None,
+		};
+		let Some(map) = map else { return false };
+
+		let position = synthesised(expression.get_position());
+		let (object, write) = match take(expression) {
+			Expression::Assignment {
+				lhs: LHSOfAssignment::VariableOrPropertyAccess(target),
+				rhs,
+				..
+			} => (target_object(target), Write::Assign(*rhs)),
+			Expression::BinaryAssignmentOperation { lhs, operator, rhs, .. } => {
+				(target_object(lhs), Write::Compound(operator.into(), *rhs))
+			}
+			Expression::UnaryPrefixAssignmentOperation {
+				operator: UnaryPrefixAssignmentOperator::IncrementOrDecrement(direction),
+				operand,
+				..
+			} => (target_object(operand), Write::Update(direction, false)),
+			Expression::UnaryPostfixAssignmentOperation {
+				operand,
+				operator: UnaryPostfixAssignmentOperator(direction),
+				..
+			} => (target_object(operand), Write::Update(direction, true)),
+			_ => unreachable!(),
+		};
+		let get =
+			|object| method_call(reference(map.clone(), position), "get", vec![object], position);
+
+		*expression = match write {
+			Write::Assign(value) => self.set(&map, object, value, is_statement, position),
+			Write::Compound(
+				operator @ (BinaryOperator::LogicalAnd
+				| BinaryOperator::LogicalOr
+				| BinaryOperator::NullCoalescing),
+				value,
+			) => {
+				let (first, second) = self.lowering.reuse(object);
+				let set = self.set(&map, second, value, is_statement, position);
+				binary(get(first), operator, set)
+			}
+			Write::Compound(operator, value) => {
+				let (first, second) = self.lowering.reuse(object);
+				let value = binary(get(second), operator, operand(value));
+				self.set(&map, first, value, is_statement, position)
+			}
+			Write::Update(direction, is_postfix) => {
+				let (first, second) = self.lowering.reuse(object);
+				let operator = match direction {
+					IncrementOrDecrement::Increment => BinaryOperator::Add,
+					IncrementOrDecrement::Decrement => BinaryOperator::Subtract,
+				};
+				let one = Expression::NumberLiteral(NumberRepresentation::from(1.), position);
+				if is_postfix && !is_statement {
+					let name = self.lowering.temporary();
+					let old = parenthesise(assign(name.clone(), get(second), position));
+					let set = method_call(
+						reference(map.clone(), position),
+						"set",
+						vec![first, binary(old, operator, one)],
+						position,
+					);
+					sequence(vec![set, reference(name, position)], position)
+				} else {
+					let value = binary(get(second), operator, one);
+					self.set(&map, first, value, is_statement, position)
+				}
+			}
+		};
+		true
+	}
+
+	/// `WeakMap.prototype.set` returns the map, so in expressions the value is also assigned to a
+	/// temporary variable to evaluate to
+	fn set(
+		&mut self,
+		map: &str,
+		object: Expression,
+		value: Expression,
+		is_statement: bool,
+		position: Span,
+	) -> Expression {
+		let map = reference(map.to_owned(), position);
+		if is_statement {
+			method_call(map, "set", vec![object, value], position)
+		} else {
+			let name = self.lowering.temporary();
+			let value = assign(name.clone(), value, position);
+			let set = method_call(map, "set", vec![object, value], position);
+			sequence(vec![set, reference(name, position)], position)
+		}
+	}
+}
+
+fn target_object(target: VariableOrPropertyAccess) -> Expression {
+	if let VariableOrPropertyAccess::PropertyAccess { parent, .. } = target {
+		*parent
+	} else {
+		unreachable!()
+	}
+}
+
+/// Finds whether an expression (including in nested functions) has anything to lower
+struct FindLowerable {
+	target: Target,
+	found: bool,
+}
+
+impl VisitorReceiver<()> for FindLowerable {
+	fn visit_expression(&mut self, expression: &Expression, _data: &mut (), _chain: &Chain) {
+		self.found |= needs_lowering(expression, self.target);
+	}
+}
+
+/// Names of variables, including references to globals
+#[derive(Default)]
+struct Names(HashSet<String>);
+
+impl VisitorReceiver<()> for Names {
+	fn visit_expression(&mut self, expression: &Expression, _data: &mut (), _chain: &Chain) {
+		if let Expression::VariableReference(name, _) = expression {
+			self.0.insert(name.clone());
+		}
+	}
+
+	fn visit_variable(
+		&mut self,
+		variable: &ImmutableVariableOrProperty,
+		_data: &mut (),
+		_chain: &Chain,
+	) {
+		if let Some(name) = variable.get_variable_name() {
+			self.0.insert(name.to_owned());
+		}
+	}
+}
+
+/// Replaces `this` in static field initialisers (but not in nested functions)
+struct ReplaceThis<'a>(&'a Expression);
+
+impl VisitorMutReceiver<()> for ReplaceThis<'_> {
+	fn visit_expression_mut(
+		&mut self,
+		expression: &mut Expression,
+		_data: &mut (),
+		_chain: &Chain,
+	) {
+		if let Expression::ThisReference(_) = expression {
+			*expression = self.0.clone();
+		}
+	}
+}
+
+fn replace_this(expression: &mut Expression, class_reference: &Expression) {
+	if let Expression::ThisReference(_) = expression {
+		*expression = class_reference.clone();
+		return;
+	}
+	let options = VisitOptions { visit_nested_blocks: false, ..VisitOptions::default() };
+	let mut chain = Chain::new_with_initial(parser::visiting::ChainVariable::Block(Span::NULL));
+	expression.visit_mut(
+		&mut ReplaceThis(class_reference),
+		&mut (),
+		&options,
+		&mut Annex::new(&mut chain),
+	);
+}
+
+fn needs_lowering(expression: &Expression, target: Target) -> bool {
+	match expression {
+		Expression::BinaryOperation { operator: BinaryOperator::Exponent, .. }
+		| Expression::BinaryAssignmentOperation {
+			operator: BinaryAssignmentOperator::ExponentAssign,
+			..
+		} => target < Target::ES2016,
+		Expression::ObjectLiteral(literal) => {
+			target < Target::ES2018
+				&& literal
+					.members
+					.iter()
+					.any(|member| matches!(member, ObjectLiteralMember::Spread(..)))
+		}
+		Expression::BinaryOperation { operator: BinaryOperator::NullCoalescing, .. } => {
+			target < Target::ES2020
+		}
+		Expression::PropertyAccess { .. }
+		| Expression::Index { .. }
+		| Expression::FunctionCall { .. } => {
+			target < Target::ES2020 && optional_link_depth(expression).is_some()
+		}
+		Expression::BinaryAssignmentOperation {
+			operator:
+				BinaryAssignmentOperator::LogicalNullishAssignment
+				| BinaryAssignmentOperator::LogicalAndAssign
+				| BinaryAssignmentOperator::LogicalOrAssign,
+			..
+		} => target < Target::ES2021,
+		Expression::ClassExpression(class) => target < Target::ES2022 && is_lowerable_class(class),
+		_ => false,
+	}
+}
+
+/// How many accesses and calls in from `expression` the outermost `?.` is
+fn optional_link_depth(expression: &Expression) -> Option<usize> {
+	let mut current = expression;
+	let mut depth = 0;
+	loop {
+		let (Expression::PropertyAccess { parent, is_optional, .. }
+		| Expression::Index { indexee: parent, is_optional, .. }
+		| Expression::FunctionCall { function: parent, is_optional, .. }) = current
+		else {
+			return None;
+		};
+		if *is_optional {
+			return Some(depth);
+		}
+		current = parent;
+		depth += 1;
+	}
+}
+
+fn chain_link(expression: &mut Expression, depth: usize) -> &mut Expression {
+	let mut current = expression;
+	for _ in 0..depth {
+		let (Expression::PropertyAccess { parent, .. }
+		| Expression::Index { indexee: parent, .. }
+		| Expression::FunctionCall { function: parent, .. }) = current
+		else {
+			unreachable!()
+		};
+		current = parent;
+	}
+	current
+}
+
+/// Has fields or private methods and they can be added to the constructor
+fn is_lowerable_class<T: ExpressionOrStatementPosition>(class: &ClassDeclaration<T>) -> bool {
+	let has_lowerable_members = class.members.iter().any(|member| match &member.on {
+		ClassMember::Property(..) => true,
+		ClassMember::Method(_, method) => method.name.get_ast_ref().is_private(),
+		_ => false,
+	});
+	has_lowerable_members && can_add_to_constructor(class) && !class.name.is_declare()
+}
+
+/// Whether statements can be added to the start of the constructor. For derived classes,
+/// `super(...)` must be called at the top level
+pub(crate) fn can_add_to_constructor<T: ExpressionOrStatementPosition>(
+	class: &ClassDeclaration<T>,
+) -> bool {
+	let constructor = class.members.iter().find_map(|member| match &member.on {
+		ClassMember::Constructor(constructor) => constructor.body.0.as_ref(),
+		_ => None,
+	});
+	match constructor {
+		Some(body) if class.extends.is_some() => super_call_index(&body.0).is_some(),
+		_ => true,
+	}
+}
+
+/// Adds statements to the start of the constructor (after `super(...)` in derived classes),
+/// adding a constructor if there is not one. Requires [`can_add_to_constructor`]
+pub(crate) fn add_to_constructor<T: ExpressionOrStatementPosition>(
+	class: &mut ClassDeclaration<T>,
+	statements: Vec<StatementOrDeclaration>,
+) {
+	let is_derived = class.extends.is_some();
+	let constructor = class.members.iter_mut().find_map(|member| match &mut member.on {
+		ClassMember::Constructor(constructor) => constructor.body.0.as_mut(),
+		_ => None,
+	});
+	if let Some(body) = constructor {
+		let index = if is_derived {
+			super_call_index(&body.0).expect("super call in constructor") + 1
+		} else {
+			0
+		};
+		body.0.splice(index..index, statements);
+		return;
+	}
+
+	let position = synthesised(class.position);
+	let mut items = Vec::new();
+	let mut rest_parameter = None;
+	if is_derived {
+		// `constructor(...args) { super(...args) }`
+		let name = VariableIdentifier::Standard("args".to_owned(), position);
+		rest_parameter = Some(Box::new(SpreadParameter {
+			name: VariableField::Name(name),
+			type_annotation: None,
+			position,
+		}));
+		let arguments =
+			vec![FunctionArgument::Spread(reference("args".to_owned(), position), position)];
+		let call = Expression::SuperExpression(SuperReference::Call { arguments }, position);
+		items.push(expression_statement(call));
+	}
+	items.extend(statements);
+	let constructor = parser::ast::ClassConstructor {
+		header: (),
+		name: (),
+		type_parameters: None,
+		parameters: FunctionParameters {
+			leading: (None, None),
+			parameters: Vec::new(),
+			rest_parameter,
+			position,
+		},
+		return_type: None,
+		body: FunctionBody(Some(Block(items, position))),
+		position,
+	};
+	class.members.insert(0, Decorated::new_empty(ClassMember::Constructor(constructor)));
+}
+
+fn super_call_index(items: &[StatementOrDeclaration]) -> Option<usize> {
+	items.iter().position(|item| {
+		matches!(
+			item,
+			StatementOrDeclaration::Statement(Statement::Expression(MultipleExpression::Single(
+				Expression::SuperExpression(SuperReference::Call { .. }, _)
+			)))
+		)
+	})
+}
+
+pub(crate) fn class_declaration(
+	item: &mut StatementOrDeclaration,
+) -> Option<&mut ClassDeclaration<parser::ast::StatementPosition>> {
+	match item {
+		StatementOrDeclaration::Declaration(Declaration::Class(class)) => Some(&mut class.on),
+		StatementOrDeclaration::Declaration(Declaration::Export(export)) => match &mut export.on {
+			ExportDeclaration::Variable { exported: Exportable::Class(class), .. } => Some(class),
+			_ => None,
+		},
+		_ => None,
+	}
+}
+
+fn method_to_function(method: ClassFunction) -> Expression {
+	let position = method.position;
+	let MethodHeader::Regular { is_async, generator } = method.header else {
+		unreachable!("private accessors are not lowered")
+	};
+	let generator_star_token_position = generator.map(|generator| match generator {
+		GeneratorSpecifier::Star(position) => position,
+		GeneratorSpecifier::Keyword => position,
+	});
+	let FunctionParameters {
+		leading: (this, _),
+		parameters,
+		rest_parameter,
+		position: parameters_position,
+	} = method.parameters;
+	Expression::ExpressionFunction(parser::ast::ExpressionFunction {
+		header: FunctionHeader::VirginFunctionHeader {
+			is_async,
+			location: None,
+			generator_star_token_position,
+			position,
+		},
+		name: ExpressionPosition(None),
+		type_parameters: None,
+		parameters: FunctionParameters {
+			leading: this,
+			parameters,
+			rest_parameter,
+			position: parameters_position,
+		},
+		return_type: None,
+		body: method.body.0.unwrap_or(Block(Vec::new(), position)),
+		position,
+	})
+}
+
+/// `{ a, ...b, c }` to `Object.assign({ a }, b, { c })`
+fn object_assign(literal: ObjectLiteral) -> Expression {
+	let position = synthesised(literal.position);
+	let object = |members| Expression::ObjectLiteral(ObjectLiteral { members, position });
+	let mut arguments = Vec::new();
+	let mut members = Vec::new();
+	for member in literal.members {
+		if let ObjectLiteralMember::Spread(expression, _) = member {
+			// The first argument is the object that is assigned to
+			if !members.is_empty() || arguments.is_empty() {
+				arguments.push(object(mem::take(&mut members)));
+			}
+			arguments.push(expression);
+		} else {
+			members.push(member);
+		}
+	}
+	if !members.is_empty() {
+		arguments.push(object(members));
+	}
+	method_call(reference("Object".to_owned(), position), "assign", arguments, literal.position)
+}
+
+fn math_pow(lhs: Expression, rhs: Expression, position: Span) -> Expression {
+	let math = reference("Math".to_owned(), synthesised(position));
+	method_call(math, "pow", vec![lhs, rhs], position)
+}
+
+fn new_weak_map(position: Span) -> Expression {
+	Expression::ConstructorCall {
+		constructor: Box::new(reference("WeakMap".to_owned(), position)),
+		type_arguments: None,
+		arguments: Some(Vec::new()),
+		position,
+	}
+}
+
+/// References and literals, which can be evaluated again without side effects
+fn is_simple(expression: &Expression) -> bool {
+	matches!(
+		expression,
+		Expression::VariableReference(..)
+			| Expression::ThisReference(..)
+			| Expression::NumberLiteral(..)
+			| Expression::StringLiteral(..)
+			| Expression::BooleanLiteral(..)
+			| Expression::Null(..)
+	)
+}
+
+/// Parenthesises `expression` unless it binds tighter than any operator
+fn operand(expression: Expression) -> Expression {
+	if is_simple(&expression)
+		|| matches!(
+			expression,
+			Expression::PropertyAccess { .. }
+				| Expression::Index { .. }
+				| Expression::FunctionCall { .. }
+				| Expression::ParenthesizedExpression(..)
+		) {
+		expression
+	} else {
+		parenthesise(expression)
+	}
+}
+
+fn take(expression: &mut Expression) -> Expression {
+	let position = expression.get_position();
+	mem::replace(expression, Expression::Null(position))
+}
+
+pub(crate) fn reference(name: String, position: Span) -> Expression {
+	Expression::VariableReference(name, position)
+}
+
+pub(crate) fn property(object: Expression, property: &str, position: Span) -> Expression {
+	Expression::PropertyAccess {
+		parent: Box::new(object),
+		property: PropertyReference::Standard { property: property.to_owned(), is_private: false },
+		is_optional: false,
+		position,
+	}
+}
+
+fn method_call(
+	object: Expression,
+	method: &str,
+	arguments: Vec<Expression>,
+	position: Span,
+) -> Expression {
+	let function = property(object, method, synthesised(position));
+	Expression::FunctionCall {
+		function: Box::new(function),
+		type_arguments: None,
+		arguments: arguments.into_iter().map(FunctionArgument::Standard).collect(),
+		is_optional: false,
+		position,
+	}
+}
+
+fn binary(lhs: Expression, operator: BinaryOperator, rhs: Expression) -> Expression {
+	let position = synthesised(lhs.get_position());
+	Expression::BinaryOperation { lhs: Box::new(lhs), operator, rhs: Box::new(rhs), position }
+}
+
+pub(crate) fn assignment(
+	target: VariableOrPropertyAccess,
+	value: Expression,
+	position: Span,
+) -> Expression {
+	Expression::Assignment {
+		lhs: LHSOfAssignment::VariableOrPropertyAccess(target),
+		rhs: Box::new(value),
+		position,
+	}
+}
+
+fn assign(name: String, value: Expression, position: Span) -> Expression {
+	assignment(VariableOrPropertyAccess::Variable(name, position), value, position)
+}
+
+fn void_zero(position: Span) -> Expression {
+	Expression::UnaryOperation {
+		operator: UnaryOperator::Void,
+		operand: Box::new(Expression::NumberLiteral(NumberRepresentation::from(0.), position)),
+		position,
+	}
+}
+
+pub(crate) fn parenthesise(expression: Expression) -> Expression {
+	let position = synthesised(expression.get_position());
+	Expression::ParenthesizedExpression(Box::new(MultipleExpression::Single(expression)), position)
+}
+
+/// `(a, b, c)`
+fn sequence(expressions: Vec<Expression>, position: Span) -> Expression {
+	let mut expressions = expressions.into_iter();
+	let first = MultipleExpression::Single(expressions.next().expect("empty sequence"));
+	let multiple = expressions.fold(first, |lhs, rhs| MultipleExpression::Multiple {
+		lhs: Box::new(lhs),
+		rhs,
+		position,
+	});
+	match multiple {
+		MultipleExpression::Single(expression) => expression,
+		multiple @ MultipleExpression::Multiple { .. } => {
+			Expression::ParenthesizedExpression(Box::new(multiple), position)
+		}
+	}
+}
+
+pub(crate) fn expression_statement(expression: Expression) -> StatementOrDeclaration {
+	StatementOrDeclaration::Statement(Statement::Expression(MultipleExpression::Single(expression)))
+}
+
+pub(crate) fn const_declaration(name: String, value: Expression) -> StatementOrDeclaration {
+	let position = synthesised(value.get_position());
+	let item = VariableDeclarationItem {
+		name: WithComment::None(VariableField::Name(VariableIdentifier::Standard(name, position))),
+		type_annotation: None,
+		expression: value,
+		position,
+	};
+	let declaration = VariableDeclaration::ConstDeclaration { declarations: vec![item], position };
+	StatementOrDeclaration::Declaration(Declaration::Variable(declaration))
+}
+
+fn let_declaration(names: Vec<String>) -> StatementOrDeclaration {
+	let declarations = names
+		.into_iter()
+		.map(|name| VariableDeclarationItem {
+			name: WithComment::None(VariableField::Name(VariableIdentifier::Standard(
+				name,
+				Span::NULL,
+			))),
+			type_annotation: None,
+			expression: None,
+			position: Span::NULL,
+		})
+		.collect();
+	let declaration = VariableDeclaration::LetDeclaration { declarations, position: Span::NULL };
+	StatementOrDeclaration::Declaration(Declaration::Variable(declaration))
+}
+
+/// Imports and directives (such as `"use strict"`), which temporary variables go after
+fn is_prologue(item: &StatementOrDeclaration) -> bool {
+	matches!(
+		item,
+		StatementOrDeclaration::Declaration(Declaration::Import(..))
+			| StatementOrDeclaration::Statement(Statement::Expression(MultipleExpression::Single(
+				Expression::StringLiteral(..)
+			)))
+	)
+}
@@ -0,0 +1,448 @@
+use std::collections::{HashMap, HashSet};
+
+use checker::{
+	events::{Event, FinalEvent},
+	features::objects::SpecialObjects,
+	types::{FunctionEffect, PolyNature},
+	FunctionId, Type, TypeId, VariableId,
+};
+use parser::{
+	declarations::VariableDeclaration,
+	expressions::{
+		arrow_function::ExpressionOrBlock, operators::UnaryOperator, FunctionArgument,
+		MultipleExpression,
+	},
+	functions::{FunctionParameters, ParameterData},
+	statements::ReturnStatement,
+	visiting::{
+		Annex, Chain, ChainVariable, VisitOptions, Visitable, VisitorMut, VisitorMutReceiver,
+		VisitorReceiver,
+	},
+	ASTNode, Block, BlockLikeMut, Declaration, Decorated, Expression, NumberRepresentation,
+	SourceId, Span, Statement, StatementOrDeclaration, VariableField, VariableIdentifier,
+};
+
+use super::constant_folding::{constant_to_literal, get_constant, is_side_effect_free};
+use crate::build::CheckingOutputWithoutDiagnostics;
+
+/// A transformer that removes calls to functions
+/// - Function declarations (and `const` arrow functions) are inlined if they are only referenced
+///   by a single call, return a side effect free expression of their parameters and the checker
+///   found their events to be pure. Arguments must be side effect free as they may be evaluated a
+///   different number of times
+/// - Calls to functions from the definitions with known (constant) results are replaced with the
+///   result if the function is pure and the arguments (including arrow function callbacks) are
+///   side effect free
+pub struct FunctionInliner;
+
+impl VisitorMut<BlockLikeMut<'_>, CheckingOutputWithoutDiagnostics> for FunctionInliner {
+	fn visit_mut(
+		&mut self,
+		block: &mut BlockLikeMut,
+		data: &mut CheckingOutputWithoutDiagnostics,
+		chain: &Chain,
+	) {
+		let source = chain.get_module();
+		let mut calls = HashMap::new();
+		for item in block.items.iter() {
+			let StatementOrDeclaration::Declaration(declaration) = item else { continue };
+			match declaration {
+				Declaration::Function(Decorated { decorators, on: function, .. })
+					if decorators.is_empty()
+						&& !function.name.declare
+						&& !function.header.is_async()
+						&& !function.header.is_generator() =>
+				{
+					// Function declarations are referenced by the position of the function
+					let start = function.position.start;
+					let (variable, function_id) =
+						(VariableId(source, start), FunctionId(source, start));
+					if let Some((reference, inlinable)) =
+						function.body.0.as_ref().and_then(returned_expression).and_then(|body| {
+							inlinable(&function.parameters, body, variable, function_id, data)
+						}) {
+						calls.insert(reference, (function.position, inlinable));
+					}
+				}
+				Declaration::Variable(VariableDeclaration::ConstDeclaration {
+					declarations,
+					..
+				}) => {
+					for declaration in declarations {
+						let (
+							VariableField::Name(VariableIdentifier::Standard(_, name_position)),
+							Expression::ArrowFunction(function),
+						) = (declaration.name.get_ast_ref(), &declaration.expression)
+						else {
+							continue;
+						};
+						let body = match &function.body {
+							_ if function.header => None,
+							ExpressionOrBlock::Expression(expression) => Some(&**expression),
+							ExpressionOrBlock::Block(block) => returned_expression(block),
+						};
+						let variable = VariableId(source, name_position.start);
+						let function_id = FunctionId(source, function.position.start);
+						if let Some((reference, inlinable)) = body.and_then(|body| {
+							inlinable(&function.parameters, body, variable, function_id, data)
+						}) {
+							calls.insert(reference, (function.position, inlinable));
+						}
+					}
+				}
+				_ => {}
+			}
+		}
+		if calls.is_empty() {
+			return;
+		}
+
+		let mut inliner = Inliner { calls, inlined: HashSet::new() };
+		let mut chain = Chain::new_with_initial(ChainVariable::Module(source));
+		block.items.visit_mut(
+			&mut inliner,
+			&mut (),
+			&VisitOptions::default(),
+			&mut Annex::new(&mut chain),
+		);
+
+		// Remove the inlined functions
+		let inlined_functions = inliner.inlined;
+		block.items.retain_mut(|item| match item {
+			StatementOrDeclaration::Declaration(Declaration::Function(function)) => {
+				!inlined_functions.contains(&function.on.position)
+			}
+			StatementOrDeclaration::Declaration(Declaration::Variable(
+				VariableDeclaration::ConstDeclaration { declarations, .. },
+			)) => {
+				declarations.retain(|declaration| {
+					!matches!(
+						&declaration.expression,
+						Expression::ArrowFunction(function) if inlined_functions.contains(&function.position)
+					)
+				});
+				!declarations.is_empty()
+			}
+			_ => true,
+		});
+	}
+}
+
+impl VisitorMut<Expression, CheckingOutputWithoutDiagnostics> for FunctionInliner {
+	fn visit_mut(
+		&mut self,
+		item: &mut Expression,
+		data: &mut CheckingOutputWithoutDiagnostics,
+		chain: &Chain,
+	) {
+		let Expression::FunctionCall { function, arguments, is_optional: false, position, .. } =
+			&*item
+		else {
+			return;
+		};
+		let arguments_side_effect_free = arguments.iter().all(|argument| match argument {
+			FunctionArgument::Standard(argument) => {
+				is_side_effect_free(argument) || is_side_effect_free_callback(argument)
+			}
+			FunctionArgument::Comment { .. } => true,
+			FunctionArgument::Spread(..) => false,
+		});
+		let on_side_effect_free = match &**function {
+			Expression::PropertyAccess { parent, .. } => is_side_effect_free(parent),
+			function => is_side_effect_free(function),
+		};
+		if !arguments_side_effect_free || !on_side_effect_free {
+			return;
+		}
+
+		let source = chain.get_module();
+		let Some(function_id) = get_function(function, data, source) else { return };
+		// Only functions from the definitions
+		if data.modules.contains_key(&function_id.0) {
+			return;
+		}
+		let pure = match data.types.get_function_from_id(function_id).effect {
+			FunctionEffect::Constant(_) => true,
+			FunctionEffect::SideEffects { ref events, .. } => {
+				are_events_pure(events, &data.types, &mut HashSet::new())
+			}
+			FunctionEffect::InputOutput(_) | FunctionEffect::Unknown => false,
+		};
+		if let Some(literal) = pure
+			.then(|| get_constant(item, data, source))
+			.flatten()
+			.and_then(|constant| constant_to_literal(constant, *position))
+		{
+			*item = literal;
+		}
+	}
+}
+
+/// `{ return expression }`
+fn returned_expression(block: &Block) -> Option<&Expression> {
+	if let [StatementOrDeclaration::Statement(Statement::Return(ReturnStatement(
+		Some(MultipleExpression::Single(expression)),
+		_,
+	)))] = block.0.as_slice()
+	{
+		Some(expression)
+	} else {
+		None
+	}
+}
+
+/// A function which can be inlined (into the call at its single reference)
+struct Inlinable {
+	parameters: Vec<String>,
+	body: Expression,
+}
+
+/// Returns the position of the single reference to `variable` if the function can be inlined
+fn inlinable<L, V>(
+	parameters: &FunctionParameters<L, V>,
+	body: &Expression,
+	variable: VariableId,
+	function_id: FunctionId,
+	data: &CheckingOutputWithoutDiagnostics,
+) -> Option<(Span, Inlinable)> {
+	if parameters.rest_parameter.is_some() {
+		return None;
+	}
+	let parameters = parameters
+		.parameters
+		.iter()
+		.map(|parameter| match (parameter.name.get_ast_ref(), &parameter.additionally) {
+			(
+				VariableField::Name(VariableIdentifier::Standard(name, _)),
+				None | Some(ParameterData::Optional),
+			) => Some(name.clone()),
+			_ => None,
+		})
+		.collect::<Option<Vec<_>>>()?;
+
+	if !is_side_effect_free(body) {
+		return None;
+	}
+	let mut references = ReferencesOnly { names: &parameters, only: true };
+	let mut chain = Chain::new_with_initial(ChainVariable::Module(variable.0));
+	body.visit(&mut references, &mut (), &VisitOptions::default(), &mut Annex::new(&mut chain));
+	if !references.only {
+		return None;
+	}
+
+	if !data.types.has_function(function_id) || !data.types.called_functions.contains(&function_id)
+	{
+		return None;
+	}
+	let FunctionEffect::SideEffects { ref events, ref free_variables, .. } =
+		data.types.get_function_from_id(function_id).effect
+	else {
+		return None;
+	};
+	if !free_variables.is_empty() || !are_events_pure(events, &data.types, &mut HashSet::new()) {
+		return None;
+	}
+
+	let exported = data
+		.modules
+		.get(&variable.0)
+		.is_some_and(|module| module.exported.named.iter().any(|(_, (id, _))| *id == variable));
+	if exported || data.variable_usage.assigned.contains(&variable) {
+		return None;
+	}
+	let mut references = data
+		.variable_usage
+		.references
+		.iter()
+		.filter_map(|(position, id)| (*id == variable).then_some(position.without_source()));
+	let reference = references.next()?;
+	if references.next().is_some() {
+		return None;
+	}
+
+	Some((reference, Inlinable { parameters, body: body.clone() }))
+}
+
+/// Whether running the events cannot have an effect outside of the call. Calls to parameters
+/// are allowed as the arguments are checked
+fn are_events_pure(
+	events: &[Event],
+	types: &checker::types::TypeStore,
+	created: &mut HashSet<TypeId>,
+) -> bool {
+	events.iter().all(|event| match event {
+		Event::ReadsReference { .. } | Event::Getter { .. } => true,
+		Event::CreateObject { referenced_in_scope_as, .. } => {
+			created.insert(*referenced_in_scope_as);
+			true
+		}
+		Event::Setter { on, .. } => created.contains(on),
+		Event::CallsType { on, .. } => {
+			matches!(types.get_type_by_id(*on), Type::RootPolyType(PolyNature::Parameter { .. }))
+		}
+		Event::Conditionally { true_events, else_events, .. } => {
+			are_events_pure(true_events, types, created)
+				&& are_events_pure(else_events, types, created)
+		}
+		Event::Iterate { iterate_over, .. } => are_events_pure(iterate_over, types, created),
+		Event::FinalEvent(event) => !matches!(event, FinalEvent::Throw { .. }),
+		Event::SetsVariable(..) => false,
+	})
+}
+
+/// The function the checker found `function` to be
+pub(crate) fn get_function(
+	function: &Expression,
+	data: &CheckingOutputWithoutDiagnostics,
+	source: SourceId,
+) -> Option<FunctionId> {
+	let module = data.modules.get(&source)?;
+	let instance = module.mappings.expressions_to_instances.get_exact(function.get_position())?;
+	match data.types.get_type_by_id(instance.clone().get_value()) {
+		Type::SpecialObject(SpecialObjects::Function(id, _)) if data.types.has_function(*id) => {
+			Some(*id)
+		}
+		_ => None,
+	}
+}
+
+/// `(x) => x * 2`
+fn is_side_effect_free_callback(expression: &Expression) -> bool {
+	let Expression::ArrowFunction(function) = expression else { return false };
+	!function.header
+		&& match &function.body {
+			ExpressionOrBlock::Expression(expression) => is_side_effect_free(expression),
+			ExpressionOrBlock::Block(block) => {
+				returned_expression(block).is_some_and(is_side_effect_free)
+			}
+		}
+}
+
+fn is_atomic(expression: &Expression) -> bool {
+	matches!(
+		expression,
+		Expression::NumberLiteral(..)
+			| Expression::StringLiteral(..)
+			| Expression::BooleanLiteral(..)
+			| Expression::Null(..)
+			| Expression::VariableReference(..)
+			| Expression::ParenthesizedExpression(..)
+	)
+}
+
+fn parenthesize(expression: Expression, position: Span) -> Expression {
+	if is_atomic(&expression) {
+		expression
+	} else {
+		Expression::ParenthesizedExpression(
+			Box::new(MultipleExpression::Single(expression)),
+			position,
+		)
+	}
+}
+
+/// Whether an expression only references `names`
+struct ReferencesOnly<'a> {
+	names: &'a [String],
+	only: bool,
+}
+
+impl VisitorReceiver<()> for ReferencesOnly<'_> {
+	fn visit_expression(&mut self, expression: &Expression, _data: &mut (), _chain: &Chain) {
+		if let Expression::VariableReference(name, _) = expression {
+			self.only &= self.names.contains(name);
+		}
+	}
+}
+
+struct Inliner {
+	/// Position of the reference to the function to the position of the function and its body
+	calls: HashMap<Span, (Span, Inlinable)>,
+	/// Positions of functions which were inlined
+	inlined: HashSet<Span>,
+}
+
+impl VisitorMutReceiver<()> for Inliner {
+	fn visit_expression_mut(&mut self, expression: &mut Expression, _data: &mut (), chain: &Chain) {
+		let Expression::FunctionCall { function, arguments, is_optional: false, position, .. } =
+			expression
+		else {
+			return;
+		};
+		let Expression::VariableReference(_, reference) = &**function else { return };
+		let Some((function_position, inlinable)) = self.calls.get(reference) else { return };
+
+		let arguments = arguments
+			.iter()
+			.filter_map(|argument| match argument {
+				FunctionArgument::Standard(argument) => Some(Some(argument)),
+				FunctionArgument::Spread(..) => Some(None),
+				FunctionArgument::Comment { .. } => None,
+			})
+			.collect::<Option<Vec<_>>>();
+		let Some(arguments) = arguments.filter(|arguments| {
+			arguments.len() <= inlinable.parameters.len()
+				&& arguments.iter().all(|argument| is_side_effect_free(argument))
+		}) else {
+			return;
+		};
+
+		// Missing arguments are `undefined`
+		let position = *position;
+		let substitutions = inlinable
+			.parameters
+			.iter()
+			.enumerate()
+			.map(|(idx, name)| {
+				let value = arguments.get(idx).map_or_else(
+					|| Expression::UnaryOperation {
+						operator: UnaryOperator::Void,
+						operand: Box::new(Expression::NumberLiteral(
+							NumberRepresentation::from(0.),
+							position,
+						)),
+						position,
+					},
+					|argument| parenthesize((*argument).clone(), argument.get_position()),
+				);
+				(name.clone(), value)
+			})
+			.collect();
+
+		let mut body = inlinable.body.clone();
+		let mut substitute = Substitute { substitutions, within: inlinable.body.get_position() };
+		let mut chain = Chain::new_with_initial(ChainVariable::Module(chain.get_module()));
+		body.visit_mut(
+			&mut substitute,
+			&mut (),
+			&VisitOptions::default(),
+			&mut Annex::new(&mut chain),
+		);
+
+		self.inlined.insert(*function_position);
+		*expression = parenthesize(body, position);
+	}
+}
+
+/// Replaces references to parameters in the body. Arguments are from outside of the body so
+/// are not substituted again
+struct Substitute {
+	substitutions: HashMap<String, Expression>,
+	within: Span,
+}
+
+impl VisitorMutReceiver<()> for Substitute {
+	fn visit_expression_mut(
+		&mut self,
+		expression: &mut Expression,
+		_data: &mut (),
+		_chain: &Chain,
+	) {
+		if let Expression::VariableReference(name, position) = expression {
+			let inside = self.within.start <= position.start && position.end <= self.within.end;
+			if let Some(value) = self.substitutions.get(name).filter(|_| inside) {
+				*expression = value.clone();
+			}
+		}
+	}
+}
@@ -0,0 +1,483 @@
+use std::{borrow::Cow, collections::BTreeSet};
+
+use parser::{
+	declarations::{
+		import::ImportedItems, ImportDeclaration, ImportExportName, ImportLocation, ImportPart,
+	},
+	expressions::{
+		object_literal::{ObjectLiteral, ObjectLiteralMember},
+		ArrayElement, FunctionArgument,
+	},
+	property_key::AlwaysPublic,
+	source_map::Nullable,
+	visiting::{Annex, Chain, VisitOptions, Visitable, VisitorMut, VisitorReceiver},
+	ASTNode, BlockLikeMut, Declaration, Expression, JSXAttribute, JSXElement, JSXElementChildren,
+	JSXNode, JSXRoot, PropertyKey, PropertyReference, Quoted, Span, StatementOrDeclaration,
+	WithComment,
+};
+
+use super::{constant_folding::escape_string, synthesised};
+use crate::build::{CheckingOutputWithoutDiagnostics, JSXRuntime};
+
+const JSX: &str = "_jsx";
+const JSXS: &str = "_jsxs";
+const FRAGMENT: &str = "_Fragment";
+const CREATE_ELEMENT: &str = "_createElement";
+
+/// A transformer that turns JSX into function calls, which are imported from the import source
+/// - [`JSXRuntime::Automatic`]: `jsx("div", { id, children }, key)` (or `jsxs` for multiple
+///   children) from `<import source>/jsx-runtime`. `createElement` is used if `key` follows a
+///   spread attribute
+/// - [`JSXRuntime::Classic`]: `createElement("div", { id }, ...children)` from `<import source>`
+pub struct JSXTransformer {
+	pub runtime: JSXRuntime,
+	pub import_source: String,
+}
+
+impl VisitorMut<Expression, CheckingOutputWithoutDiagnostics> for JSXTransformer {
+	fn visit_mut(
+		&mut self,
+		item: &mut Expression,
+		_data: &mut CheckingOutputWithoutDiagnostics,
+		_chain: &Chain,
+	) {
+		// Nested JSX in attributes and children is transformed when visiting the result
+		if let Expression::JSXRoot(root) = item {
+			*item = match root {
+				JSXRoot::Element(element) => self.element_to_call(element),
+				JSXRoot::Fragment(fragment) => {
+					let position = synthesised(fragment.position);
+					let tag = Expression::VariableReference(FRAGMENT.to_owned(), position);
+					self.to_call(tag, Vec::new(), collect_children(&fragment.children), position)
+				}
+			};
+		}
+	}
+}
+
+impl VisitorMut<BlockLikeMut<'_>, CheckingOutputWithoutDiagnostics> for JSXTransformer {
+	fn visit_mut(
+		&mut self,
+		block: &mut BlockLikeMut,
+		_data: &mut CheckingOutputWithoutDiagnostics,
+		chain: &Chain,
+	) {
+		// Imports are added to the top of the module (which is visited first)
+		if chain.get_innermost_scope().is_some() {
+			return;
+		}
+
+		let mut used = UsedFunctions { runtime: self.runtime, ..Default::default() };
+		let mut chain = Chain::new();
+		for item in block.items.iter() {
+			item.visit(&mut used, &mut (), &VisitOptions::default(), &mut Annex::new(&mut chain));
+		}
+
+		let runtime_source = match self.runtime {
+			JSXRuntime::Automatic => format!("{}/jsx-runtime", self.import_source),
+			JSXRuntime::Classic => self.import_source.clone(),
+		};
+		let mut runtime_imports = Vec::new();
+		let mut source_imports = Vec::new();
+		for function in used.functions {
+			let names = function.names();
+			// Under the automatic runtime, `createElement` is not in the `jsx-runtime` module
+			if let (RuntimeFunction::CreateElement, JSXRuntime::Automatic) =
+				(function, self.runtime)
+			{
+				source_imports.push(names);
+			} else {
+				runtime_imports.push(names);
+			}
+		}
+
+		let imports =
+			[(runtime_source, runtime_imports), (self.import_source.clone(), source_imports)]
+				.into_iter()
+				.filter(|(_, parts)| !parts.is_empty())
+				.map(|(from, parts)| import_declaration(from, &parts));
+		block.items.splice(0..0, imports);
+	}
+}
+
+impl JSXTransformer {
+	fn element_to_call(&self, element: &JSXElement) -> Expression {
+		let position = synthesised(element.position);
+		let tag = tag_to_expression(&element.tag_name, position);
+		let children = match element.children {
+			JSXElementChildren::Children(ref children) => collect_children(children),
+			JSXElementChildren::SelfClosing => Vec::new(),
+		};
+		self.to_call(tag, element.attributes.clone(), children, position)
+	}
+
+	fn to_call(
+		&self,
+		tag: Expression,
+		attributes: Vec<JSXAttribute>,
+		children: Vec<Child>,
+		position: Span,
+	) -> Expression {
+		if matches!(self.runtime, JSXRuntime::Classic) || has_key_after_spread(&attributes) {
+			let props = if attributes.is_empty() {
+				Expression::Null(position)
+			} else {
+				Expression::ObjectLiteral(ObjectLiteral {
+					members: attributes.into_iter().map(attribute_to_member).collect(),
+					position,
+				})
+			};
+			let arguments = [FunctionArgument::Standard(tag), FunctionArgument::Standard(props)]
+				.into_iter()
+				.chain(children.into_iter().map(Child::into_argument))
+				.collect();
+			return call(CREATE_ELEMENT, arguments, position);
+		}
+
+		let mut key = None;
+		let mut members = Vec::with_capacity(attributes.len() + 1);
+		for attribute in attributes {
+			match attribute {
+				JSXAttribute::Static(name, value, position) if name == "key" => {
+					key = Some(string_literal(&value, position));
+				}
+				JSXAttribute::Dynamic(name, value, _) if name == "key" => key = Some(*value),
+				JSXAttribute::BooleanAttribute(name, position) if name == "key" => {
+					key = Some(Expression::BooleanLiteral(true, position));
+				}
+				attribute => members.push(attribute_to_member(attribute)),
+			}
+		}
+
+		let function = if is_static_children(&children) { JSXS } else { JSX };
+		if let Some(value) = children_to_value(children, position) {
+			members.push(property("children", value, position));
+		}
+
+		let props = Expression::ObjectLiteral(ObjectLiteral { members, position });
+		let mut arguments =
+			vec![FunctionArgument::Standard(tag), FunctionArgument::Standard(props)];
+		arguments.extend(key.map(FunctionArgument::Standard));
+		call(function, arguments, position)
+	}
+}
+
+/// Ordered as they are imported
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum RuntimeFunction {
+	Jsx,
+	Jsxs,
+	Fragment,
+	CreateElement,
+}
+
+impl RuntimeFunction {
+	/// The exported name and the name it is imported as
+	fn names(self) -> (&'static str, &'static str) {
+		match self {
+			RuntimeFunction::Jsx => ("jsx", JSX),
+			RuntimeFunction::Jsxs => ("jsxs", JSXS),
+			RuntimeFunction::Fragment => ("Fragment", FRAGMENT),
+			RuntimeFunction::CreateElement => ("createElement", CREATE_ELEMENT),
+		}
+	}
+}
+
+/// Finds which of the runtime functions a module uses
+#[derive(Default)]
+struct UsedFunctions {
+	runtime: JSXRuntime,
+	functions: BTreeSet<RuntimeFunction>,
+}
+
+impl VisitorReceiver<()> for UsedFunctions {
+	fn visit_expression(&mut self, expression: &Expression, _data: &mut (), _chain: &Chain) {
+		match expression {
+			Expression::JSXRoot(JSXRoot::Element(element)) => self.add_element(element),
+			Expression::JSXRoot(JSXRoot::Fragment(fragment)) => {
+				self.functions.insert(RuntimeFunction::Fragment);
+				self.add_children(&fragment.children, &[]);
+			}
+			_ => {}
+		}
+	}
+}
+
+impl UsedFunctions {
+	fn add_element(&mut self, element: &JSXElement) {
+		let children = match element.children {
+			JSXElementChildren::Children(ref children) => children.as_slice(),
+			JSXElementChildren::SelfClosing => &[],
+		};
+		self.add_children(children, &element.attributes);
+	}
+
+	fn add_children(&mut self, children: &[JSXNode], attributes: &[JSXAttribute]) {
+		let function =
+			if matches!(self.runtime, JSXRuntime::Classic) || has_key_after_spread(attributes) {
+				RuntimeFunction::CreateElement
+			} else if is_static_children(&collect_children(children)) {
+				RuntimeFunction::Jsxs
+			} else {
+				RuntimeFunction::Jsx
+			};
+		self.functions.insert(function);
+		// Nested elements are not expressions so are not visited
+		for child in children {
+			if let JSXNode::Element(element) = child {
+				self.add_element(element);
+			}
+		}
+	}
+}
+
+enum Child {
+	Text(String, Span),
+	Expression(Expression),
+	Spread(Expression, Span),
+}
+
+impl Child {
+	fn into_argument(self) -> FunctionArgument {
+		match self {
+			Child::Text(text, position) => {
+				FunctionArgument::Standard(string_literal(&text, position))
+			}
+			Child::Expression(expression) => FunctionArgument::Standard(expression),
+			Child::Spread(expression, position) => FunctionArgument::Spread(expression, position),
+		}
+	}
+}
+
+/// Nested elements are left as JSX (to be transformed when visiting the result)
+fn collect_children(nodes: &[JSXNode]) -> Vec<Child> {
+	let mut children = Vec::new();
+	let mut text = String::new();
+	let mut text_position = Span::NULL;
+	for node in nodes {
+		match node {
+			JSXNode::TextNode(content, position) => {
+				if text.is_empty() {
+					text_position = *position;
+				}
+				text.push_str(content);
+			}
+			JSXNode::LineBreak => text.push('\n'),
+			JSXNode::Element(element) => {
+				push_text(&mut text, text_position, &mut children);
+				let root = JSXRoot::Element(element.clone());
+				children.push(Child::Expression(Expression::JSXRoot(root)));
+			}
+			JSXNode::InterpolatedExpression(argument, _) => {
+				push_text(&mut text, text_position, &mut children);
+				match &**argument {
+					FunctionArgument::Standard(expression) => {
+						children.push(Child::Expression(expression.clone()));
+					}
+					FunctionArgument::Spread(expression, position) => {
+						children.push(Child::Spread(expression.clone(), *position));
+					}
+					FunctionArgument::Comment { .. } => {}
+				}
+			}
+			JSXNode::Comment(..) => {}
+		}
+	}
+	push_text(&mut text, text_position, &mut children);
+	children
+}
+
+/// Lines are trimmed and joined with spaces. Lines with only whitespace are removed
+fn push_text(text: &mut String, position: Span, children: &mut Vec<Child>) {
+	let lines: Vec<&str> =
+		text.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).collect();
+	let last_non_empty = lines.iter().rposition(|line| !line.trim().is_empty());
+	let mut cleaned = String::new();
+	for (index, line) in lines.iter().enumerate() {
+		let mut line = *line;
+		if index != 0 {
+			line = line.trim_start();
+		}
+		if index != lines.len() - 1 {
+			line = line.trim_end();
+		}
+		if !line.is_empty() {
+			cleaned.push_str(line);
+			if Some(index) != last_non_empty {
+				cleaned.push(' ');
+			}
+		}
+	}
+	text.clear();
+	if !cleaned.is_empty() {
+		children.push(Child::Text(cleaned, position));
+	}
+}
+
+/// `jsxs` marks children which are created once (rather than from a spread)
+fn is_static_children(children: &[Child]) -> bool {
+	children.len() > 1 || children.iter().any(|child| matches!(child, Child::Spread(..)))
+}
+
+fn children_to_value(mut children: Vec<Child>, position: Span) -> Option<Expression> {
+	if children.len() == 1 && !matches!(children[0], Child::Spread(..)) {
+		return children.pop().map(|child| match child.into_argument() {
+			FunctionArgument::Standard(expression) => expression,
+			_ => unreachable!(),
+		});
+	}
+	(!children.is_empty()).then(|| {
+		let elements =
+			children.into_iter().map(|child| ArrayElement(Some(child.into_argument()))).collect();
+		Expression::ArrayLiteral(elements, position)
+	})
+}
+
+/// With a key after a spread, the key cannot be separated from the props as the spread may
+/// contain `key`
+fn has_key_after_spread(attributes: &[JSXAttribute]) -> bool {
+	attributes.iter().skip_while(|attribute| !matches!(attribute, JSXAttribute::Spread(..))).any(
+		|attribute| {
+			matches!(
+				attribute,
+				JSXAttribute::Static(name, ..)
+					| JSXAttribute::Dynamic(name, ..)
+					| JSXAttribute::BooleanAttribute(name, ..)
+					if name == "key"
+			)
+		},
+	)
+}
+
+/// Lowercase names (and those with `-` or `:`) are intrinsic elements. Others are references
+fn tag_to_expression(tag_name: &str, position: Span) -> Expression {
+	if tag_name.starts_with(|chr: char| chr.is_ascii_lowercase()) || tag_name.contains(['-', ':']) {
+		return string_literal(tag_name, position);
+	}
+	let mut parts = tag_name.split('.');
+	let first = parts.next().unwrap_or_default();
+	let reference = Expression::VariableReference(first.to_owned(), position);
+	parts.fold(reference, |parent, property| Expression::PropertyAccess {
+		parent: Box::new(parent),
+		property: PropertyReference::Standard { property: property.to_owned(), is_private: false },
+		is_optional: false,
+		position,
+	})
+}
+
+fn attribute_to_member(attribute: JSXAttribute) -> ObjectLiteralMember {
+	match attribute {
+		JSXAttribute::Static(name, value, position) => {
+			property(&name, string_literal(&value, position), position)
+		}
+		JSXAttribute::Dynamic(name, value, position) => property(&name, *value, position),
+		JSXAttribute::BooleanAttribute(name, position) => {
+			property(&name, Expression::BooleanLiteral(true, position), position)
+		}
+		JSXAttribute::Spread(expression, position) => {
+			ObjectLiteralMember::Spread(expression, position)
+		}
+		JSXAttribute::Shorthand(Expression::VariableReference(name, position)) => {
+			ObjectLiteralMember::Shorthand(name, position)
+		}
+		JSXAttribute::Shorthand(expression) => {
+			let position = expression.get_position();
+			ObjectLiteralMember::Spread(expression, position)
+		}
+	}
+}
+
+/// Names such as `aria-label` are quoted
+fn property(name: &str, value: Expression, position: Span) -> ObjectLiteralMember {
+	let is_identifier = name.starts_with(|chr: char| !chr.is_ascii_digit())
+		&& name.chars().all(|chr| chr.is_alphanumeric() || matches!(chr, '_' | '$'));
+	let key = if is_identifier {
+		PropertyKey::Ident(name.to_owned(), position, AlwaysPublic)
+	} else {
+		PropertyKey::StringLiteral(escape_string(name), Quoted::Double, position)
+	};
+	ObjectLiteralMember::Property {
+		key: WithComment::None(key),
+		assignment: false,
+		value,
+		position,
+	}
+}
+
+fn string_literal(value: &str, position: Span) -> Expression {
+	Expression::StringLiteral(escape_string(&decode_entities(value)), Quoted::Double, position)
+}
+
+/// Character references (`&amp;`, `&#123;` and `&#x7B;`) in text and attribute values. Named
+/// references other than the common ones are left as is
+fn decode_entities(value: &str) -> Cow<'_, str> {
+	if !value.contains('&') {
+		return Cow::Borrowed(value);
+	}
+	let mut decoded = String::with_capacity(value.len());
+	let mut rest = value;
+	while let Some(start) = rest.find('&') {
+		decoded.push_str(&rest[..start]);
+		rest = &rest[start..];
+		let reference = rest[1..].find(';').map(|end| &rest[1..=end]);
+		let chr = reference.and_then(|reference| match reference {
+			"amp" => Some('&'),
+			"lt" => Some('<'),
+			"gt" => Some('>'),
+			"quot" => Some('"'),
+			"apos" => Some('\''),
+			"nbsp" => Some('\u{a0}'),
+			reference => {
+				let number = reference.strip_prefix('#')?;
+				let code = if let Some(hex) = number.strip_prefix(['x', 'X']) {
+					u32::from_str_radix(hex, 16).ok()?
+				} else {
+					number.parse().ok()?
+				};
+				char::from_u32(code)
+			}
+		});
+		if let (Some(chr), Some(reference)) = (chr, reference) {
+			decoded.push(chr);
+			rest = &rest[reference.len() + 2..];
+		} else {
+			decoded.push('&');
+			rest = &rest[1..];
+		}
+	}
+	decoded.push_str(rest);
+	Cow::Owned(decoded)
+}
+
+fn call(function: &str, arguments: Vec<FunctionArgument>, position: Span) -> Expression {
+	Expression::FunctionCall {
+		function: Box::new(Expression::VariableReference(function.to_owned(), position)),
+		type_arguments: None,
+		arguments,
+		is_optional: false,
+		position,
+	}
+}
+
+/// `import { imported as local, ... } from "from"`
+fn import_declaration(from: String, parts: &[(&str, &str)]) -> StatementOrDeclaration {
+	let parts = parts
+		.iter()
+		.map(|(imported, local)| ImportPart::NameWithAlias {
+			name: (*local).to_owned(),
+			alias: ImportExportName::Reference((*imported).to_owned()),
+			position: Span::NULL,
+		})
+		.collect();
+	let import = ImportDeclaration {
+		is_deferred: false,
+		is_type_annotation_import_only: false,
+		default: None,
+		items: ImportedItems::Parts(Some(parts)),
+		from: ImportLocation::Quoted(from, Quoted::Double),
+		with: None,
+		position: Span::NULL,
+		reversed: false,
+	};
+	StatementOrDeclaration::Declaration(Declaration::Import(import))
+}
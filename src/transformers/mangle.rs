@@ -0,0 +1,837 @@
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
+use checker::VariableId;
+use parser::{
+	declarations::{
+		classes::ClassMember, export::Exportable, import::ImportedItems, ExportDeclaration,
+		VariableDeclaration,
+	},
+	expressions::{
+		assignments::{LHSOfAssignment, VariableOrPropertyAccess},
+		object_literal::ObjectLiteralMember,
+		InExpressionLHS, SpecialOperators,
+	},
+	property_key::{AlwaysPublic, PublicOrPrivate},
+	statements::{ForLoopCondition, ForLoopStatementInitializer},
+	visiting::{
+		Annex, BlockItem, Chain, ChainVariable, ImmutableVariableOrProperty,
+		MutableVariableOrProperty, VisitOptions, Visitable, VisitorMut, VisitorMutReceiver,
+		VisitorReceiver,
+	},
+	ASTNode, ArrayDestructuringField, BlockLikeMut, Declaration, Decorated, Decorator, Expression,
+	ExpressionOrStatementPosition, ObjectDestructuringField, PropertyKey, PropertyReference,
+	SourceId, Span, Statement, StatementOrDeclaration, VariableField, VariableIdentifier,
+	VariableKeyword, WithComment,
+};
+
+use crate::{
+	build::CheckingOutputWithoutDiagnostics,
+	bundler::{export_part_names, import_part_names, variable_field_names},
+};
+
+/// A transformer that shortens the names of local variables, parameters, functions and classes
+/// (and optionally private class members). Should be run after the other transformers
+///
+/// Bindings are matched with their references using the [`VariableId`]s the checker recorded
+/// ([`checker::VariableUsage::references`]). A binding keeps its name if
+/// - a reference to it was not visited (or was removed)
+/// - a reference with the same name does not resolve to a binding in the module (globals, imports
+///   and code the checker did not synthesise)
+/// - it is exported
+/// - a direct `eval` is in its scope (`with` is not allowed in modules)
+///
+/// New names are not any name in the module. Bindings in scopes which do not overlap can have the
+/// same name. The `name` of functions and classes is changed
+pub struct Mangler {
+	/// Also rename `#private` class members
+	pub private_members: bool,
+	/// [`checker::VariableUsage::references`] under the module they are in
+	references: Option<HashMap<SourceId, HashMap<Span, VariableId>>>,
+}
+
+impl Mangler {
+	#[must_use]
+	pub fn new(private_members: bool) -> Self {
+		Self { private_members, references: None }
+	}
+}
+
+impl VisitorMut<BlockLikeMut<'_>, CheckingOutputWithoutDiagnostics> for Mangler {
+	fn visit_mut(
+		&mut self,
+		block: &mut BlockLikeMut,
+		data: &mut CheckingOutputWithoutDiagnostics,
+		chain: &Chain,
+	) {
+		// The whole module is renamed at once
+		if chain.get_innermost_scope().is_some() {
+			return;
+		}
+
+		let source = chain.get_module();
+		let references = self
+			.references
+			.get_or_insert_with(|| {
+				let mut by_source: HashMap<SourceId, HashMap<Span, VariableId>> = HashMap::new();
+				for (position, id) in &data.variable_usage.references {
+					by_source
+						.entry(position.source)
+						.or_default()
+						.insert(position.without_source(), *id);
+				}
+				by_source
+			})
+			.remove(&source)
+			.unwrap_or_default();
+		let exported: HashSet<VariableId> = data
+			.modules
+			.get(&source)
+			.map(|module| module.exported.named.iter().map(|(_, (id, _))| *id).collect())
+			.unwrap_or_default();
+
+		// Items are visited together as the chain is reverted when the annex is dropped
+		let mut collector = Collector::default();
+		let mut chain = Chain::new_with_initial(ChainVariable::Module(source));
+		block.items.visit(
+			&mut collector,
+			&mut (),
+			&VisitOptions::default(),
+			&mut Annex::new(&mut chain),
+		);
+
+		let mut renamer = Renamer {
+			names: collector.allocate(source, &references, &exported),
+			private: if self.private_members {
+				collector.allocate_private()
+			} else {
+				HashMap::new()
+			},
+		};
+		if renamer.names.is_empty() && renamer.private.is_empty() {
+			return;
+		}
+		let mut chain = Chain::new_with_initial(ChainVariable::Module(source));
+		block.items.visit_mut(
+			&mut renamer,
+			&mut (),
+			&VisitOptions::default(),
+			&mut Annex::new(&mut chain),
+		);
+	}
+}
+
+/// A name in the module. Renamed (along with the others with the same [`VariableId`]) by position
+struct Occurrence {
+	name: String,
+	position: Span,
+	/// The scope (`None` for the module) and the position the checker's [`VariableId`] is based on
+	binding: Option<(Option<Span>, u32)>,
+}
+
+struct Binding {
+	name: String,
+	scope: Option<Span>,
+	/// Positions of the binding and its references
+	occurrences: Vec<Span>,
+	renameable: bool,
+}
+
+#[derive(Default)]
+struct Collector {
+	occurrences: Vec<Occurrence>,
+	/// Names which are not renamed
+	blocked: HashSet<String>,
+	/// Every name in the module, which new names cannot be
+	names: HashSet<String>,
+	/// Patterns of `var` declarations, which are scoped to the function rather than the block
+	var_patterns: Vec<Span>,
+	/// Positions of the names of function declarations to the position of the function (which
+	/// their [`VariableId`] is based on)
+	function_declarations: HashMap<Span, u32>,
+	/// Positions of direct `eval` calls, which can reference any variable in scope
+	evals: Vec<Span>,
+	/// Private member names and how many times they occur
+	private: HashMap<String, usize>,
+	blocked_private: HashSet<String>,
+	/// Under something which [`Renamer`] does not visit
+	blocking: bool,
+}
+
+impl Collector {
+	fn add(&mut self, name: &str, position: Span, binding: Option<(Option<Span>, u32)>) {
+		self.names.insert(name.to_owned());
+		if self.blocking {
+			self.blocked.insert(name.to_owned());
+		} else {
+			self.occurrences.push(Occurrence { name: name.to_owned(), position, binding });
+		}
+	}
+
+	fn add_binding(&mut self, name: &str, position: Span, chain: &Chain) {
+		let start = self.function_declarations.get(&position).copied().unwrap_or(position.start);
+		let scope = if self.var_patterns.iter().any(|pattern| contains(Some(*pattern), position)) {
+			None
+		} else {
+			chain.get_innermost_scope()
+		};
+		self.add(name, position, Some((scope, start)));
+	}
+
+	fn add_private(&mut self, name: &str) {
+		if self.blocking {
+			self.blocked_private.insert(name.to_owned());
+		}
+		*self.private.entry(name.to_owned()).or_default() += 1;
+	}
+
+	fn block(&mut self, name: &str) {
+		self.names.insert(name.to_owned());
+		self.blocked.insert(name.to_owned());
+	}
+
+	/// For parts of the AST which are not visited
+	fn block_all(&mut self, item: &impl Visitable, chain: &Chain) {
+		let blocking = std::mem::replace(&mut self.blocking, true);
+		let mut chain = Chain::new_with_initial(ChainVariable::Module(chain.get_module()));
+		item.visit(self, &mut (), &VisitOptions::default(), &mut Annex::new(&mut chain));
+		self.blocking = blocking;
+	}
+
+	fn block_decorators(&mut self, decorators: &[Decorator], chain: &Chain) {
+		for decorator in decorators {
+			if let Some(name) = decorator.name.first() {
+				self.block(name);
+			}
+			if let Some(ref arguments) = decorator.arguments {
+				self.block_all(arguments, chain);
+			}
+		}
+	}
+
+	fn block_member_decorators(&mut self, members: &[Decorated<ClassMember>], chain: &Chain) {
+		for member in members {
+			self.block_decorators(&member.decorators, chain);
+		}
+	}
+
+	fn block_exported(&mut self, export: &ExportDeclaration, chain: &Chain) {
+		match export {
+			ExportDeclaration::Variable { exported, .. } => match exported {
+				Exportable::Variable(declaration) => {
+					let mut names = Vec::new();
+					match declaration {
+						VariableDeclaration::ConstDeclaration { declarations, .. } => {
+							for item in declarations {
+								variable_field_names(item.name.get_ast_ref(), &mut names);
+							}
+						}
+						VariableDeclaration::LetDeclaration { declarations, .. } => {
+							for item in declarations {
+								variable_field_names(item.name.get_ast_ref(), &mut names);
+							}
+						}
+					}
+					for name in names {
+						self.block(&name);
+					}
+				}
+				Exportable::Function(function) => {
+					if let Some(name) = function.name.identifier.as_option_str() {
+						self.block(name);
+					}
+				}
+				Exportable::Class(class) => {
+					if let Some(name) = class.name.identifier.as_option_str() {
+						self.block(name);
+					}
+					self.block_member_decorators(&class.members, chain);
+				}
+				Exportable::Parts(parts) => {
+					for (local, _) in parts.iter().filter_map(export_part_names) {
+						self.block(&local);
+					}
+				}
+				_ => {}
+			},
+			ExportDeclaration::Default { expression, .. } => match &**expression {
+				Expression::VariableReference(name, _) => self.block(name),
+				Expression::ExpressionFunction(function) => {
+					if let Some(name) = function.name.as_option_str() {
+						self.block(name);
+					}
+				}
+				Expression::ClassExpression(class) => {
+					if let Some(name) = class.name.as_option_str() {
+						self.block(name);
+					}
+				}
+				_ => {}
+			},
+			ExportDeclaration::DefaultFunction { .. } => {}
+		}
+	}
+
+	/// Returns the new names of bindings and references by their position (along with their
+	/// current name)
+	fn allocate(
+		&self,
+		source: SourceId,
+		references: &HashMap<Span, VariableId>,
+		exported: &HashSet<VariableId>,
+	) -> HashMap<Span, (String, String)> {
+		let mut blocked = self.blocked.clone();
+		let mut bindings: HashMap<VariableId, Binding> = HashMap::new();
+		let mut uses = Vec::new();
+
+		for Occurrence { name, position, binding } in &self.occurrences {
+			// Added by a transformer
+			if position.start == position.end {
+				blocked.insert(name.clone());
+				continue;
+			}
+			let resolved = references.get(position).copied();
+			match binding {
+				// A binding can be an assignment (`for (x of ...)`)
+				Some((scope, start))
+					if resolved.is_none_or(|id| id == VariableId(source, *start)) =>
+				{
+					match bindings.entry(VariableId(source, *start)) {
+						Entry::Occupied(mut entry) => entry.get_mut().renameable = false,
+						Entry::Vacant(entry) => {
+							entry.insert(Binding {
+								name: name.clone(),
+								scope: *scope,
+								occurrences: vec![*position],
+								renameable: true,
+							});
+						}
+					}
+				}
+				_ => {
+					if let Some(id) = resolved {
+						uses.push((id, name, *position));
+					} else {
+						blocked.insert(name.clone());
+					}
+				}
+			}
+		}
+
+		for (id, name, position) in uses {
+			match bindings.get_mut(&id) {
+				Some(binding) if binding.name == *name => binding.occurrences.push(position),
+				_ => {
+					blocked.insert(name.clone());
+				}
+			}
+		}
+
+		// All the references must be renamed
+		for (position, id) in references {
+			if let Some(binding) = bindings.get_mut(id) {
+				if !binding.occurrences.contains(position) {
+					binding.renameable = false;
+				}
+			}
+		}
+
+		let mut renameable: Vec<(VariableId, Binding)> = bindings
+			.into_iter()
+			.filter(|(id, binding)| {
+				binding.renameable
+					&& !blocked.contains(&binding.name)
+					&& !exported.contains(id)
+					&& !self.evals.iter().any(|eval| contains(binding.scope, *eval))
+			})
+			.collect();
+		// Most used first, as they get the shortest names
+		renameable.sort_by(|(a_id, a), (b_id, b)| {
+			b.occurrences.len().cmp(&a.occurrences.len()).then(a_id.1.cmp(&b_id.1))
+		});
+
+		// The scopes of the bindings given each new name
+		let mut given: Vec<Vec<Option<Span>>> = Vec::new();
+		let mut renames = HashMap::new();
+		for (_, binding) in renameable {
+			let mut index = 0;
+			let new_name = loop {
+				let name = short_name(index);
+				if name.len() >= binding.name.len() {
+					break None;
+				}
+				let free = !self.names.contains(&name)
+					&& !RESERVED.contains(&name.as_str())
+					&& given.get(index).is_none_or(|scopes| {
+						scopes.iter().all(|scope| !overlaps(*scope, binding.scope))
+					});
+				if free {
+					break Some((index, name));
+				}
+				index += 1;
+			};
+			let Some((index, new_name)) = new_name else { continue };
+			if given.len() <= index {
+				given.resize_with(index + 1, Vec::new);
+			}
+			given[index].push(binding.scope);
+			for position in binding.occurrences {
+				renames.insert(position, (binding.name.clone(), new_name.clone()));
+			}
+		}
+		renames
+	}
+
+	/// Private names are scoped to the class, so the same name is always renamed to the same name
+	fn allocate_private(&self) -> HashMap<String, String> {
+		let mut private: Vec<(&String, &usize)> =
+			self.private.iter().filter(|(name, _)| !self.blocked_private.contains(*name)).collect();
+		private.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+
+		let mut renames = HashMap::new();
+		let mut index = 0;
+		for (name, _) in private {
+			let new_name = loop {
+				let candidate = short_name(index);
+				index += 1;
+				if !self.private.contains_key(&candidate) {
+					break candidate;
+				}
+			};
+			if new_name.len() < name.len() {
+				renames.insert(name.clone(), new_name);
+			}
+		}
+		renames
+	}
+}
+
+impl VisitorReceiver<()> for Collector {
+	fn visit_expression(&mut self, expression: &Expression, _data: &mut (), chain: &Chain) {
+		match expression {
+			Expression::VariableReference(name, position) => self.add(name, *position, None),
+			Expression::Assignment { lhs, .. } => match lhs {
+				LHSOfAssignment::VariableOrPropertyAccess(access) => self.add_access(access),
+				LHSOfAssignment::ArrayDestructuring(fields, _) => self.block_all(fields, chain),
+				LHSOfAssignment::ObjectDestructuring(fields, _) => self.block_all(fields, chain),
+			},
+			Expression::BinaryAssignmentOperation { lhs: access, .. }
+			| Expression::UnaryPrefixAssignmentOperation { operand: access, .. }
+			| Expression::UnaryPostfixAssignmentOperation { operand: access, .. } => {
+				self.add_access(access);
+			}
+			Expression::PropertyAccess {
+				property: PropertyReference::Standard { property, is_private: true },
+				..
+			}
+			| Expression::SpecialOperators(
+				SpecialOperators::In { lhs: InExpressionLHS::PrivateProperty(property), .. },
+				_,
+			) => self.add_private(property),
+			Expression::ObjectLiteral(literal) => {
+				for member in &literal.members {
+					match member {
+						ObjectLiteralMember::Shorthand(name, position) => {
+							self.add(name, *position, None);
+						}
+						ObjectLiteralMember::Property { key, .. } => {
+							if let PropertyKey::Computed(expression, _) = key.get_ast_ref() {
+								self.block_all(expression, chain);
+							}
+						}
+						ObjectLiteralMember::Spread(..) | ObjectLiteralMember::Method(_) => {}
+					}
+				}
+			}
+			Expression::FunctionCall { function, position, .. } if matches!(&**function, Expression::VariableReference(name, _) if name == "eval") =>
+			{
+				self.evals.push(*position);
+			}
+			Expression::ClassExpression(class) => {
+				self.block_member_decorators(&class.members, chain);
+			}
+			_ => {}
+		}
+	}
+
+	fn visit_statement(&mut self, item: BlockItem, _data: &mut (), chain: &Chain) {
+		let statement = match item {
+			BlockItem::StatementOrDeclaration(StatementOrDeclaration::Declaration(declaration)) => {
+				match declaration {
+					Declaration::Function(Decorated { decorators, on: function, .. }) => {
+						if let VariableIdentifier::Standard(_, position) = function.name.identifier
+						{
+							self.function_declarations.insert(position, function.position.start);
+						}
+						self.block_decorators(decorators, chain);
+					}
+					Declaration::Class(Decorated { decorators, on: class, .. }) => {
+						self.block_decorators(decorators, chain);
+						self.block_member_decorators(&class.members, chain);
+					}
+					Declaration::Export(Decorated { decorators, on: export, .. }) => {
+						self.block_decorators(decorators, chain);
+						self.block_exported(export, chain);
+					}
+					Declaration::Import(import) => {
+						if let Some(VariableIdentifier::Standard(ref name, _)) = import.default {
+							self.names.insert(name.clone());
+						}
+						match import.items {
+							ImportedItems::All {
+								under: VariableIdentifier::Standard(ref name, _),
+							} => {
+								self.names.insert(name.clone());
+							}
+							ImportedItems::Parts(Some(ref parts)) => {
+								self.names.extend(
+									parts
+										.iter()
+										.filter_map(import_part_names)
+										.map(|(_, local)| local),
+								);
+							}
+							_ => {}
+						}
+					}
+					_ => {}
+				}
+				return;
+			}
+			BlockItem::StatementOrDeclaration(StatementOrDeclaration::Statement(statement))
+			| BlockItem::SingleStatement(statement) => statement,
+			BlockItem::StatementOrDeclaration(StatementOrDeclaration::Marker(..)) => return,
+		};
+		match statement {
+			Statement::VarVariable(statement) => {
+				self.var_patterns.extend(
+					statement
+						.declarations
+						.iter()
+						.map(|item| item.name.get_ast_ref().get_position()),
+				);
+			}
+			Statement::ForLoop(statement) => match &statement.condition {
+				ForLoopCondition::ForOf {
+					keyword: Some(VariableKeyword::Var), variable, ..
+				}
+				| ForLoopCondition::ForIn {
+					keyword: Some(VariableKeyword::Var), variable, ..
+				} => {
+					self.var_patterns.push(variable.get_ast_ref().get_position());
+				}
+				ForLoopCondition::Statements {
+					initialiser: Some(ForLoopStatementInitializer::VarStatement(statement)),
+					..
+				} => {
+					self.var_patterns.extend(
+						statement
+							.declarations
+							.iter()
+							.map(|item| item.name.get_ast_ref().get_position()),
+					);
+				}
+				_ => {}
+			},
+			_ => {}
+		}
+	}
+
+	fn visit_variable(
+		&mut self,
+		variable: &ImmutableVariableOrProperty,
+		_data: &mut (),
+		chain: &Chain,
+	) {
+		match variable {
+			ImmutableVariableOrProperty::VariableFieldName(name, position) => {
+				self.add_binding(name, **position, chain);
+			}
+			ImmutableVariableOrProperty::FunctionName(Some(VariableIdentifier::Standard(
+				name,
+				position,
+			)))
+			| ImmutableVariableOrProperty::ClassName(Some(VariableIdentifier::Standard(
+				name,
+				position,
+			)))
+			| ImmutableVariableOrProperty::ArrayDestructuringMember(
+				ArrayDestructuringField::Spread(
+					VariableField::Name(VariableIdentifier::Standard(name, position)),
+					_,
+				),
+			) => self.add_binding(name, *position, chain),
+			ImmutableVariableOrProperty::ObjectDestructuringMember(member) => {
+				match member.get_ast_ref() {
+					ObjectDestructuringField::Name(
+						VariableIdentifier::Standard(name, position),
+						..,
+					)
+					| ObjectDestructuringField::Spread(
+						VariableIdentifier::Standard(name, position),
+						_,
+					) => self.add_binding(name, *position, chain),
+					_ => {}
+				}
+			}
+			ImmutableVariableOrProperty::ClassPropertyKey(key) => match key {
+				PropertyKey::Ident(name, _, PublicOrPrivate::Private) => self.add_private(name),
+				PropertyKey::Computed(expression, _) => self.block_all(expression, chain),
+				_ => {}
+			},
+			ImmutableVariableOrProperty::ObjectPropertyKey(PropertyKey::Computed(
+				expression,
+				_,
+			)) => self.block_all(expression, chain),
+			_ => {}
+		}
+	}
+}
+
+impl Collector {
+	fn add_access(&mut self, access: &VariableOrPropertyAccess) {
+		match access {
+			VariableOrPropertyAccess::Variable(name, position) => self.add(name, *position, None),
+			VariableOrPropertyAccess::PropertyAccess {
+				property: PropertyReference::Standard { property, is_private: true },
+				..
+			} => self.add_private(property),
+			_ => {}
+		}
+	}
+}
+
+struct Renamer {
+	/// Position to the current name and the new name
+	names: HashMap<Span, (String, String)>,
+	private: HashMap<String, String>,
+}
+
+impl Renamer {
+	fn rename(&self, name: &mut String, position: Span) {
+		if let Some((current, new_name)) = self.names.get(&position) {
+			if current == name {
+				name.clone_from(new_name);
+			}
+		}
+	}
+
+	fn rename_access(&self, access: &mut VariableOrPropertyAccess) {
+		match access {
+			VariableOrPropertyAccess::Variable(name, position) => {
+				let position = *position;
+				self.rename(name, position);
+			}
+			VariableOrPropertyAccess::PropertyAccess {
+				property: PropertyReference::Standard { property, is_private: true },
+				..
+			} => self.rename_private(property),
+			_ => {}
+		}
+	}
+
+	fn rename_private(&self, name: &mut String) {
+		if let Some(new_name) = self.private.get(name.as_str()) {
+			name.clone_from(new_name);
+		}
+	}
+}
+
+impl VisitorMutReceiver<()> for Renamer {
+	fn visit_expression_mut(
+		&mut self,
+		expression: &mut Expression,
+		_data: &mut (),
+		_chain: &Chain,
+	) {
+		match expression {
+			Expression::VariableReference(name, position) => {
+				let position = *position;
+				self.rename(name, position);
+			}
+			Expression::Assignment {
+				lhs: LHSOfAssignment::VariableOrPropertyAccess(access),
+				..
+			}
+			| Expression::BinaryAssignmentOperation { lhs: access, .. }
+			| Expression::UnaryPrefixAssignmentOperation { operand: access, .. }
+			| Expression::UnaryPostfixAssignmentOperation { operand: access, .. } => {
+				self.rename_access(access);
+			}
+			Expression::PropertyAccess {
+				property: PropertyReference::Standard { property, is_private: true },
+				..
+			}
+			| Expression::SpecialOperators(
+				SpecialOperators::In { lhs: InExpressionLHS::PrivateProperty(property), .. },
+				_,
+			) => self.rename_private(property),
+			Expression::ObjectLiteral(literal) => {
+				// Expanded to keep the key
+				for member in &mut literal.members {
+					if let ObjectLiteralMember::Shorthand(name, position) = member {
+						let mut new_name = name.clone();
+						self.rename(&mut new_name, *position);
+						if new_name != *name {
+							let (name, position) = (name.clone(), *position);
+							*member = ObjectLiteralMember::Property {
+								key: WithComment::None(PropertyKey::Ident(
+									name,
+									position,
+									AlwaysPublic,
+								)),
+								assignment: false,
+								value: Expression::VariableReference(new_name, position),
+								position,
+							};
+						}
+					}
+				}
+			}
+			_ => {}
+		}
+	}
+
+	fn visit_variable_mut(
+		&mut self,
+		variable: &mut MutableVariableOrProperty,
+		_data: &mut (),
+		_chain: &Chain,
+	) {
+		match variable {
+			MutableVariableOrProperty::VariableFieldName(name, position) => {
+				self.rename(name, **position);
+			}
+			MutableVariableOrProperty::FunctionName(Some(VariableIdentifier::Standard(
+				name,
+				position,
+			)))
+			| MutableVariableOrProperty::ClassName(Some(VariableIdentifier::Standard(
+				name,
+				position,
+			)))
+			| MutableVariableOrProperty::ArrayDestructuringMember(
+				ArrayDestructuringField::Spread(
+					VariableField::Name(VariableIdentifier::Standard(name, position)),
+					_,
+				),
+			)
+			| MutableVariableOrProperty::ObjectDestructuringMember(WithComment::None(
+				ObjectDestructuringField::Spread(VariableIdentifier::Standard(name, position), _),
+			)) => {
+				let position = *position;
+				self.rename(name, position);
+			}
+			MutableVariableOrProperty::ObjectDestructuringMember(member) => {
+				if let ObjectDestructuringField::Name(
+					VariableIdentifier::Standard(name, name_position),
+					default_value,
+					position,
+				) = member.get_ast_mut()
+				{
+					// Expanded to `{ name: new_name }`
+					let mut new_name = name.clone();
+					self.rename(&mut new_name, *name_position);
+					if new_name != *name {
+						let field = ObjectDestructuringField::Map {
+							from: PropertyKey::Ident(name.clone(), *name_position, AlwaysPublic),
+							name: WithComment::None(VariableField::Name(
+								VariableIdentifier::Standard(new_name, *name_position),
+							)),
+							default_value: default_value.take(),
+							position: *position,
+						};
+						*member.get_ast_mut() = field;
+					}
+				}
+			}
+			MutableVariableOrProperty::ClassPropertyKey(PropertyKey::Ident(
+				name,
+				_,
+				PublicOrPrivate::Private,
+			)) => self.rename_private(name),
+			_ => {}
+		}
+	}
+}
+
+/// Strict mode reserved words and names which cannot be declared
+const RESERVED: &[&str] = &[
+	"do",
+	"if",
+	"in",
+	"for",
+	"let",
+	"new",
+	"try",
+	"var",
+	"NaN",
+	"case",
+	"else",
+	"enum",
+	"eval",
+	"null",
+	"this",
+	"true",
+	"void",
+	"with",
+	"await",
+	"break",
+	"catch",
+	"class",
+	"const",
+	"false",
+	"super",
+	"throw",
+	"while",
+	"yield",
+	"delete",
+	"export",
+	"import",
+	"public",
+	"return",
+	"static",
+	"switch",
+	"typeof",
+	"default",
+	"extends",
+	"finally",
+	"package",
+	"private",
+	"continue",
+	"debugger",
+	"function",
+	"Infinity",
+	"arguments",
+	"interface",
+	"protected",
+	"undefined",
+	"implements",
+	"instanceof",
+];
+
+/// `a`, `b`, ..., `$`, `aa`, `ba`, ...
+fn short_name(mut index: usize) -> String {
+	const FIRST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_$";
+	const REST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_$0123456789";
+
+	let mut name = String::from(FIRST[index % FIRST.len()] as char);
+	index /= FIRST.len();
+	while index > 0 {
+		index -= 1;
+		name.push(REST[index % REST.len()] as char);
+		index /= REST.len();
+	}
+	name
+}
+
+/// `None` is the whole module
+fn contains(scope: Option<Span>, position: Span) -> bool {
+	scope.is_none_or(|scope| scope.start <= position.start && position.end <= scope.end)
+}
+
+fn overlaps(a: Option<Span>, b: Option<Span>) -> bool {
+	match (a, b) {
+		(Some(a), Some(b)) => a.start < b.end && b.start < a.end,
+		_ => true,
+	}
+}
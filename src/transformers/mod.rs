@@ -1,6 +1,46 @@
+pub mod compile_out;
+pub mod constant_folding;
+pub mod dead_code;
+pub mod downlevel;
+pub mod inlining;
+pub mod jsx;
+pub mod mangle;
 pub mod optimisations;
+pub mod typescript;
 
-use parser::{visiting::BlockItemMut, Declaration, Module, StatementOrDeclaration};
+use parser::{
+	visiting::{Annex, BlockItem, BlockItemMut, Chain, VisitOptions, Visitable, VisitorReceiver},
+	Declaration, Module, Span, Statement, StatementOrDeclaration,
+};
+
+/// Empty span at the start of `position`, for nodes that a transformer creates. Unlike the
+/// positions of the nodes they replace, it does not match a type from checking, so the constant
+/// folder leaves them alone
+pub(crate) fn synthesised(position: Span) -> Span {
+	Span { start: position.start, end: position.start, source: position.source }
+}
+
+/// Whether `item` contains a `var` declaration. These are hoisted out of blocks, so the
+/// statements that contain them cannot be removed
+pub(crate) fn declares_var(item: &impl Visitable) -> bool {
+	struct FindVar(bool);
+
+	impl VisitorReceiver<()> for FindVar {
+		fn visit_statement(&mut self, statement: BlockItem, _data: &mut (), _chain: &Chain) {
+			let statement = match statement {
+				BlockItem::StatementOrDeclaration(StatementOrDeclaration::Statement(statement))
+				| BlockItem::SingleStatement(statement) => statement,
+				BlockItem::StatementOrDeclaration(_) => return,
+			};
+			self.0 |= matches!(statement, Statement::VarVariable(_));
+		}
+	}
+
+	let mut finder = FindVar(false);
+	let mut chain = Chain::new();
+	item.visit(&mut finder, &mut (), &VisitOptions::default(), &mut Annex::new(&mut chain));
+	finder.0
+}
 
 pub struct ConstToLet;
 
@@ -1,14 +1,22 @@
-use checker::FunctionId;
+use std::mem;
+
+use checker::{FunctionId, VariableId};
 use parser::{
 	declarations::{
-		classes::{ClassMember, ClassProperty},
-		ClassDeclaration,
+		classes::ClassMember,
+		export::{ExportPart, Exportable},
+		import::ImportedItems,
+		variable::DeclarationExpression,
+		ClassDeclaration, ExportDeclaration, ImportDeclaration, ImportExportName, ImportPart,
+		VariableDeclaration, VariableDeclarationItem,
 	},
 	expressions::object_literal::ObjectLiteralMember,
-	visiting::{BlockItemMut, VisitorMut},
-	ASTNode, Expression, ExpressionOrStatementPosition, SourceId, StatementOrDeclaration,
+	visiting::{BlockItemMut, Chain, VisitorMut},
+	ASTNode, BlockLikeMut, Declaration, Decorated, Expression, ExpressionOrStatementPosition,
+	PropertyKey, SourceId, StatementOrDeclaration, VariableField, VariableIdentifier,
 };
 
+use super::constant_folding::is_side_effect_free;
 use crate::build::CheckingOutputWithoutDiagnostics;
 
 /// A transformer that optimises expression code
@@ -22,7 +30,7 @@ impl VisitorMut<Expression, CheckingOutputWithoutDiagnostics> for ExpressionOpti
 		&mut self,
 		item: &mut Expression,
 		data: &mut CheckingOutputWithoutDiagnostics,
-		chain: &parser::visiting::Chain,
+		chain: &Chain,
 	) {
 		match item {
 			Expression::ObjectLiteral(literal) => {
@@ -67,7 +75,7 @@ impl VisitorMut<Expression, CheckingOutputWithoutDiagnostics> for ExpressionOpti
 }
 
 /// A transformer that optimises statement code
-/// - Removes dead functions
+/// - Removes declarations, imports and exports which are never used
 pub struct StatementOptimiser;
 
 impl VisitorMut<BlockItemMut<'_>, CheckingOutputWithoutDiagnostics> for StatementOptimiser {
@@ -75,73 +83,321 @@ impl VisitorMut<BlockItemMut<'_>, CheckingOutputWithoutDiagnostics> for Statemen
 		&mut self,
 		item: &mut BlockItemMut,
 		data: &mut CheckingOutputWithoutDiagnostics,
-		chain: &parser::visiting::Chain,
+		chain: &Chain,
 	) {
 		if let BlockItemMut::StatementOrDeclaration(StatementOrDeclaration::Declaration(
-			declaration,
+			Declaration::Class(cls),
 		)) = item
 		{
-			match declaration {
-				parser::Declaration::Variable(_) => {
-					// TODO remove if never read
-				}
-				parser::Declaration::Function(func) => {
-					if !data.is_function_called(FunctionId(
-						chain.get_module(),
-						func.get_position().start,
-					)) {
-						// Replace with property to not break Object.keys for now
-						// TODO replacing this with variable isn't great but
-						// is the unfortunate design of `StatementOrDeclarationMut`
-						*declaration = parser::Declaration::Variable(
-							parser::declarations::VariableDeclaration::LetDeclaration {
-								declarations: Vec::new(),
-								position: func.get_position(),
-							},
-						)
+			shake_class(&mut cls.on, data, chain.get_module());
+		}
+	}
+}
+
+impl VisitorMut<BlockLikeMut<'_>, CheckingOutputWithoutDiagnostics> for StatementOptimiser {
+	fn visit_mut(
+		&mut self,
+		block: &mut BlockLikeMut,
+		data: &mut CheckingOutputWithoutDiagnostics,
+		chain: &Chain,
+	) {
+		let source = chain.get_module();
+		// Exports of entry points are the public interface
+		let keep_exports = data.entry_points.contains(&source);
+
+		for item in mem::take(block.items) {
+			let item = match item {
+				StatementOrDeclaration::Declaration(Declaration::Export(export))
+					if !keep_exports =>
+				{
+					match shake_export(export, data, source) {
+						Some(item) => item,
+						None => continue,
 					}
 				}
-				parser::Declaration::Class(cls) => {
-					shake_class(&mut cls.on, data, chain.get_module());
-				}
-				parser::Declaration::Import(_) => {
-					// TODO imported items
+				item => item,
+			};
+			if let Some(item) = shake_declaration(item, data, source) {
+				block.items.push(item);
+			}
+		}
+	}
+}
+
+/// Returns `None` if the declaration can be removed. Reads are recorded against the declaration
+/// (rather than the import or export), so exported items are left as is
+fn shake_declaration(
+	item: StatementOrDeclaration,
+	data: &CheckingOutputWithoutDiagnostics,
+	source: SourceId,
+) -> Option<StatementOrDeclaration> {
+	let StatementOrDeclaration::Declaration(declaration) = item else { return Some(item) };
+	let declaration = match declaration {
+		Declaration::Variable(VariableDeclaration::ConstDeclaration {
+			mut declarations,
+			position,
+		}) => {
+			declarations.retain(|declaration| !is_unused_declaration(declaration, data, source));
+			if declarations.is_empty() {
+				return None;
+			}
+			Declaration::Variable(VariableDeclaration::ConstDeclaration { declarations, position })
+		}
+		Declaration::Variable(VariableDeclaration::LetDeclaration {
+			mut declarations,
+			position,
+		}) => {
+			declarations.retain(|declaration| !is_unused_declaration(declaration, data, source));
+			if declarations.is_empty() {
+				return None;
+			}
+			Declaration::Variable(VariableDeclaration::LetDeclaration { declarations, position })
+		}
+		Declaration::Function(func) => {
+			if !is_used(VariableId(source, func.get_position().start), data) {
+				return None;
+			}
+			Declaration::Function(func)
+		}
+		Declaration::Class(cls) => {
+			let used = match cls.on.name.identifier {
+				VariableIdentifier::Standard(_, position) => {
+					is_used(VariableId(source, position.start), data)
 				}
-				parser::Declaration::Export(_) => {
-					// TODO exported items
+				VariableIdentifier::Marker(..) => true,
+			};
+			if !used && cls.decorators.is_empty() && is_class_side_effect_free(&cls.on) {
+				return None;
+			}
+			Declaration::Class(cls)
+		}
+		Declaration::Import(import) => shake_import(import, data, source),
+		declaration => declaration,
+	};
+	Some(StatementOrDeclaration::Declaration(declaration))
+}
+
+fn is_used(id: VariableId, data: &CheckingOutputWithoutDiagnostics) -> bool {
+	data.variable_usage.read.contains(&id) || data.variable_usage.assigned.contains(&id)
+}
+
+/// Destructuring could call getters, so only declarations of names
+fn is_unused_declaration<T: DeclarationExpression>(
+	declaration: &VariableDeclarationItem<T>,
+	data: &CheckingOutputWithoutDiagnostics,
+	source: SourceId,
+) -> bool {
+	let VariableField::Name(VariableIdentifier::Standard(_, position)) =
+		declaration.name.get_ast_ref()
+	else {
+		return false;
+	};
+	!is_used(VariableId(source, position.start), data)
+		&& declaration.expression.as_option_expression_ref().is_none_or(is_side_effect_free)
+}
+
+/// Removes unread bindings. The import is kept (without bindings) as the module may have side
+/// effects
+fn shake_import(
+	mut import: ImportDeclaration,
+	data: &CheckingOutputWithoutDiagnostics,
+	source: SourceId,
+) -> Declaration {
+	let is_read = |position: parser::Span| {
+		data.variable_usage.read.contains(&VariableId(source, position.start))
+	};
+
+	if import.default.as_ref().is_some_and(|default| !is_read(default.get_position())) {
+		import.default = None;
+	}
+	match import.items {
+		ImportedItems::All { ref under } if !is_read(under.get_position()) => {
+			import.items = ImportedItems::Parts(None);
+		}
+		ImportedItems::Parts(Some(ref mut parts)) => {
+			parts.retain(|part| match part {
+				ImportPart::Name(_) | ImportPart::NameWithAlias { .. } => {
+					is_read(part.get_position())
 				}
-				parser::Declaration::Enum(_)
-				| parser::Declaration::Interface(_)
-				| parser::Declaration::TypeAlias(_)
-				| parser::Declaration::DeclareVariable(_)
-				| parser::Declaration::Namespace(_) => {}
+				ImportPart::PrefixComment(..) | ImportPart::PostfixComment(..) => true,
+			});
+			if parts.is_empty() {
+				import.items = ImportedItems::Parts(None);
 			}
 		}
+		ImportedItems::All { .. } | ImportedItems::Parts(None) => {}
 	}
+	Declaration::Import(import)
 }
 
-/// TODO properties and even entire class
+/// Turns exports which no module imports into declarations. Returns `None` if there is nothing left
+fn shake_export(
+	export: Decorated<ExportDeclaration>,
+	data: &CheckingOutputWithoutDiagnostics,
+	source: SourceId,
+) -> Option<StatementOrDeclaration> {
+	let Decorated { decorators, on, position } = export;
+	let on = match on {
+		ExportDeclaration::Variable { exported: Exportable::Function(func), .. }
+			if func
+				.name
+				.as_option_variable_identifier()
+				.is_some_and(|name| !is_export_used(name.as_option_str(), data, source)) =>
+		{
+			let position = func.get_position();
+			let declaration = Declaration::Function(Decorated { decorators, on: func, position });
+			return Some(StatementOrDeclaration::Declaration(declaration));
+		}
+		ExportDeclaration::Variable { exported: Exportable::Variable(declaration), .. }
+			if declared_names(&declaration).is_some_and(|mut names| {
+				names.all(|name| !is_export_used(Some(name), data, source))
+			}) =>
+		{
+			return Some(StatementOrDeclaration::Declaration(Declaration::Variable(declaration)));
+		}
+		ExportDeclaration::Variable { exported: Exportable::Parts(mut parts), position } => {
+			parts.retain(|part| is_export_used(exported_name(part), data, source));
+			if parts.is_empty() {
+				return None;
+			}
+			ExportDeclaration::Variable { exported: Exportable::Parts(parts), position }
+		}
+		on => on,
+	};
+	let export = Decorated { decorators, on, position };
+	Some(StatementOrDeclaration::Declaration(Declaration::Export(export)))
+}
+
+/// Exports which are not variables (classes and `default`) or do not have a name are treated as
+/// used
+fn is_export_used(
+	name: Option<&str>,
+	data: &CheckingOutputWithoutDiagnostics,
+	source: SourceId,
+) -> bool {
+	let Some(name) = name else { return true };
+	let Some(module) = data.modules.get(&source) else { return true };
+	module
+		.exported
+		.named
+		.iter()
+		.find(|(export, _)| export == name)
+		.is_none_or(|(_, (id, _))| data.variable_usage.imported.contains(id))
+}
+
+fn exported_name(part: &ExportPart) -> Option<&str> {
+	match part {
+		ExportPart::Name(VariableIdentifier::Standard(name, _))
+		| ExportPart::NameWithAlias {
+			alias: ImportExportName::Reference(name) | ImportExportName::Quoted(name, _),
+			..
+		} => Some(name),
+		_ => None,
+	}
+}
+
+/// `None` if any are destructured
+fn declared_names(declaration: &VariableDeclaration) -> Option<impl Iterator<Item = &str>> {
+	fn name<T: DeclarationExpression>(declaration: &VariableDeclarationItem<T>) -> Option<&str> {
+		match declaration.name.get_ast_ref() {
+			VariableField::Name(VariableIdentifier::Standard(name, _)) => Some(name),
+			_ => None,
+		}
+	}
+
+	let names: Option<Vec<&str>> = match declaration {
+		VariableDeclaration::ConstDeclaration { declarations, .. } => {
+			declarations.iter().map(name).collect()
+		}
+		VariableDeclaration::LetDeclaration { declarations, .. } => {
+			declarations.iter().map(name).collect()
+		}
+	};
+	names.map(Vec::into_iter)
+}
+
+/// Whether defining the class cannot have an effect. Static properties and blocks are evaluated
+/// when the class is defined, as are computed keys
+fn is_class_side_effect_free<T: ExpressionOrStatementPosition>(
+	class: &ClassDeclaration<T>,
+) -> bool {
+	fn is_key_side_effect_free<T: parser::property_key::PropertyKeyKind>(
+		key: &PropertyKey<T>,
+	) -> bool {
+		match key {
+			PropertyKey::Computed(expression, _) => is_side_effect_free(expression),
+			_ => true,
+		}
+	}
+
+	class.extends.as_deref().is_none_or(is_side_effect_free)
+		&& class.members.iter().all(|member| {
+			member.decorators.is_empty()
+				&& match &member.on {
+					ClassMember::Method(_, func) => {
+						is_key_side_effect_free(func.name.get_ast_ref())
+					}
+					ClassMember::Property(is_static, property) => {
+						is_key_side_effect_free(property.key.get_ast_ref())
+							&& (!is_static
+								|| property.value.as_deref().is_none_or(is_side_effect_free))
+					}
+					ClassMember::StaticBlock(_) => false,
+					ClassMember::Constructor(_)
+					| ClassMember::Indexer { .. }
+					| ClassMember::Comment(..) => true,
+				}
+		})
+}
+
+/// Methods are on the prototype (and so are not enumerable) so can be removed. Classes which
+/// could be used outside of the output are left as is
+///
+/// TODO properties
 fn shake_class<T: ExpressionOrStatementPosition>(
 	class: &mut ClassDeclaration<T>,
 	data: &CheckingOutputWithoutDiagnostics,
 	source: SourceId,
 ) {
-	for item in class.members.iter_mut() {
-		if let ClassMember::Method(is_static, func) = &item.on {
-			let id = FunctionId(source, func.position.start);
-			if !data.is_function_called(id) {
-				// Replace with property to not break Object.keys for now
-				item.on = ClassMember::Property(
-					*is_static,
-					ClassProperty {
-						is_readonly: false,
-						key: func.name.clone(),
-						type_annotation: None,
-						value: Some(Box::new(Expression::Null(func.position))),
-						position: func.position,
-					},
-				);
-			}
+	if may_be_public(class, data, source) {
+		return;
+	}
+	class.members.retain(|member| {
+		if let ClassMember::Method(_, func) = &member.on {
+			data.is_function_called(FunctionId(source, func.position.start))
+		} else {
+			true
+		}
+	});
+}
+
+/// Whether the class or its instances could be used by importers of the output, which could call
+/// methods the checker has not seen called. Instances can leave through any export of an entry point
+/// (such as being returned from an exported function), so then every class is treated as public
+fn may_be_public<T: ExpressionOrStatementPosition>(
+	class: &ClassDeclaration<T>,
+	data: &CheckingOutputWithoutDiagnostics,
+	source: SourceId,
+) -> bool {
+	let exports_values = |source: &SourceId| {
+		data.modules.get(source).is_none_or(|module| {
+			module.exported.default.is_some() || !module.exported.named.is_empty()
+		})
+	};
+	if data.entry_points.iter().any(exports_values) {
+		return true;
+	}
+
+	match class.name.as_option_variable_identifier() {
+		// The default export is a value rather than a variable, so cannot be compared
+		Some(VariableIdentifier::Standard(_, position)) => {
+			let id = VariableId(source, position.start);
+			data.modules.get(&source).is_none_or(|module| module.exported.default.is_some())
+				|| data.modules.values().any(|module| {
+					module.exported.named.iter().any(|(_, (exported, _))| *exported == id)
+				})
 		}
+		// Class expressions can be exported through what they are assigned to
+		_ => exports_values(&source),
 	}
 }
@@ -0,0 +1,645 @@
+use std::{
+	collections::{HashMap, HashSet},
+	mem,
+};
+
+use checker::Constant;
+use parser::{
+	ast::{
+		ClassDeclaration, ClassMember, ExportDeclaration, FunctionArgument, FunctionParameters,
+		MultipleExpression, Parameter, VariableDeclaration, VariableOrPropertyAccess,
+	},
+	declarations::{export::Exportable, EnumDeclaration, EnumMember},
+	expressions::{
+		object_literal::{ObjectLiteral, ObjectLiteralMember},
+		operators::{BinaryOperator, UnaryOperator},
+	},
+	property_key::AlwaysPublic,
+	types::namespace::Namespace,
+	visiting::{
+		Annex, BlockItemMut, Chain, VisitOptions, Visitable, VisitorMut, VisitorMutReceiver,
+	},
+	ASTNode, Block, BlockLikeMut, Declaration, Decorated, Expression,
+	ExpressionOrStatementPosition, ExpressionPosition, FunctionHeader, NumberRepresentation,
+	PropertyKey, PropertyReference, Quoted, SourceId, Span, Statement, StatementOrDeclaration,
+	VariableField, VariableIdentifier, WithComment,
+};
+
+use super::{
+	constant_folding::{escape_constant_string, get_constant, is_side_effect_free},
+	downlevel::{
+		add_to_constructor, assignment, can_add_to_constructor, const_declaration,
+		expression_statement, parenthesise, property, reference,
+	},
+	synthesised,
+};
+use crate::{
+	build::CheckingOutputWithoutDiagnostics,
+	bundler::{export_part_names, variable_field_names},
+};
+
+/// A transformer that lowers TypeScript constructs which have runtime semantics
+/// - `enum`s to objects, with reverse mappings for numeric members. Members of (non exported)
+///   `const enum`s are inlined and the declaration removed
+/// - `namespace`s to a variable that an IIFE adds the exported items to
+/// - Parameter properties (`constructor(private x)`) to assignments at the start of the
+///   constructor
+/// - Interfaces, type aliases and `declare`d functions and classes are removed
+pub struct TypeScriptTransformer;
+
+#[derive(Clone)]
+enum EnumValue {
+	Number(f64),
+	/// Escaped content
+	String(String, Quoted),
+}
+
+impl VisitorMut<BlockLikeMut<'_>, CheckingOutputWithoutDiagnostics> for TypeScriptTransformer {
+	fn visit_mut(
+		&mut self,
+		block: &mut BlockLikeMut,
+		data: &mut CheckingOutputWithoutDiagnostics,
+		chain: &Chain,
+	) {
+		// Member values of removed `const enum`s, which are inlined in the rest of the block
+		let mut const_enums = HashMap::new();
+		// Namespaces merge with each other and with functions and classes of the same name
+		let mut declared: HashSet<String> =
+			block.items.iter().filter_map(declared_name).map(ToOwned::to_owned).collect();
+
+		block.items.retain(|item| !is_type_only(item));
+
+		let mut index = 0;
+		while index < block.items.len() {
+			if !is_lowerable(&block.items[index]) {
+				index += 1;
+				continue;
+			}
+			let position = block.items[index].get_position();
+			let item = mem::replace(
+				&mut block.items[index],
+				StatementOrDeclaration::Statement(Statement::Empty(position)),
+			);
+			let lowered = match item {
+				StatementOrDeclaration::Declaration(Declaration::Enum(r#enum)) => {
+					lower_enum(r#enum.on, false, data, chain.get_module(), &mut const_enums)
+				}
+				StatementOrDeclaration::Declaration(Declaration::Namespace(namespace)) => {
+					lower_namespace(namespace, false, &mut declared)
+				}
+				StatementOrDeclaration::Declaration(Declaration::Export(Decorated {
+					on: ExportDeclaration::Variable { exported, .. },
+					..
+				})) => match exported {
+					Exportable::Enum(r#enum) => {
+						lower_enum(r#enum, true, data, chain.get_module(), &mut const_enums)
+					}
+					Exportable::Namespace(namespace) => {
+						lower_namespace(namespace, true, &mut declared)
+					}
+					_ => unreachable!(),
+				},
+				_ => unreachable!(),
+			};
+			let count = lowered.len();
+			block.items.splice(index..=index, lowered);
+			index += count;
+		}
+
+		if !const_enums.is_empty() {
+			let mut inline = InlineConstEnums(&const_enums);
+			let mut chain = chain.clone();
+			for item in block.items.iter_mut() {
+				item.visit_mut(
+					&mut inline,
+					&mut (),
+					&VisitOptions::default(),
+					&mut Annex::new(&mut chain),
+				);
+			}
+		}
+	}
+}
+
+impl VisitorMut<BlockItemMut<'_>, CheckingOutputWithoutDiagnostics> for TypeScriptTransformer {
+	fn visit_mut(
+		&mut self,
+		item: &mut BlockItemMut,
+		_data: &mut CheckingOutputWithoutDiagnostics,
+		_chain: &Chain,
+	) {
+		if let BlockItemMut::StatementOrDeclaration(item) = item {
+			if let Some(class) = super::downlevel::class_declaration(item) {
+				lower_parameter_properties(class);
+			}
+		}
+	}
+}
+
+impl VisitorMut<Expression, CheckingOutputWithoutDiagnostics> for TypeScriptTransformer {
+	fn visit_mut(
+		&mut self,
+		item: &mut Expression,
+		_data: &mut CheckingOutputWithoutDiagnostics,
+		_chain: &Chain,
+	) {
+		if let Expression::ClassExpression(class) = item {
+			lower_parameter_properties(class);
+		}
+	}
+}
+
+/// Replaces `E.A` and `E["A"]` with the value of the member
+struct InlineConstEnums<'a>(&'a HashMap<String, HashMap<String, EnumValue>>);
+
+impl VisitorMutReceiver<()> for InlineConstEnums<'_> {
+	fn visit_expression_mut(
+		&mut self,
+		expression: &mut Expression,
+		_data: &mut (),
+		_chain: &Chain,
+	) {
+		let (object, member, position) = match &*expression {
+			Expression::PropertyAccess {
+				parent,
+				property: PropertyReference::Standard { property, is_private: false },
+				is_optional: false,
+				position,
+			} => (&**parent, property.as_str(), *position),
+			Expression::Index { indexee, indexer, is_optional: false, position } => {
+				let MultipleExpression::Single(Expression::StringLiteral(member, ..)) = &**indexer
+				else {
+					return;
+				};
+				(&**indexee, member.as_str(), *position)
+			}
+			_ => return,
+		};
+		let Expression::VariableReference(name, _) = object else { return };
+		if let Some(value) = self.0.get(name).and_then(|members| members.get(member)) {
+			*expression = enum_value_literal(value, position);
+		}
+	}
+}
+
+/// `enum E { A, B = f() }` to `const E = { A: 0, 0: "A" }; E[E.B = f()] = "B";`. Values are
+/// taken from checking, so members can refer to other members and enums
+fn lower_enum(
+	r#enum: EnumDeclaration,
+	exported: bool,
+	data: &CheckingOutputWithoutDiagnostics,
+	source: SourceId,
+	const_enums: &mut HashMap<String, HashMap<String, EnumValue>>,
+) -> Vec<StatementOrDeclaration> {
+	let EnumDeclaration { is_constant, name, members, position } = r#enum;
+	let member_names: HashSet<String> =
+		members.iter().map(|EnumMember::Variant { name, .. }| name.clone()).collect();
+
+	let mut values = Vec::with_capacity(members.len());
+	let mut next = Some(0.);
+	for EnumMember::Variant { name, value, position } in members {
+		let known = match &value {
+			// Literals do not have a type from checking
+			Some(Expression::NumberLiteral(number, _)) => {
+				f64::try_from(number.clone()).ok().map(EnumValue::Number)
+			}
+			Some(Expression::StringLiteral(content, quoted, _)) => {
+				Some(EnumValue::String(content.clone(), *quoted))
+			}
+			Some(value) if is_side_effect_free(value) => match get_constant(value, data, source) {
+				Some(Constant::Number(number)) => Some(EnumValue::Number(**number)),
+				Some(Constant::String(string)) => {
+					Some(EnumValue::String(escape_constant_string(string), Quoted::Double))
+				}
+				_ => None,
+			},
+			Some(_) => None,
+			None => next.map(EnumValue::Number),
+		};
+		next = if let Some(EnumValue::Number(number)) = known { Some(number + 1.) } else { None };
+		values.push((name, known, value, position));
+	}
+
+	if is_constant && !exported && values.iter().all(|(_, known, ..)| known.is_some()) {
+		let values =
+			values.into_iter().filter_map(|(member, known, ..)| Some((member, known?))).collect();
+		const_enums.insert(name, values);
+		return Vec::new();
+	}
+
+	let object_position = synthesised(position);
+	let mut properties = Vec::new();
+	let mut statements = Vec::new();
+	let mut previous: Option<String> = None;
+	for (member, known, value, member_position) in values {
+		let member_position = synthesised(member_position);
+		if let Some(known) = known {
+			let key = PropertyKey::Ident(member.clone(), member_position, AlwaysPublic);
+			properties.push(enum_property(key, enum_value_literal(&known, member_position)));
+			if let EnumValue::Number(number) = known {
+				properties.push(enum_property(
+					number_key(number, member_position),
+					Expression::StringLiteral(member.clone(), Quoted::Double, member_position),
+				));
+			}
+		} else {
+			// `E[E.B = value] = "B"`
+			let value = if let Some(mut value) = value {
+				let mut qualify = QualifyMembers { enum_name: &name, members: &member_names };
+				value.visit_mut(
+					&mut qualify,
+					&mut (),
+					&VisitOptions::default(),
+					&mut Annex::new(&mut Chain::new()),
+				);
+				value
+			} else {
+				let previous = previous.as_deref().expect("first members have a value");
+				let previous =
+					property(reference(name.clone(), member_position), previous, member_position);
+				let one =
+					Expression::NumberLiteral(NumberRepresentation::from(1.), member_position);
+				Expression::BinaryOperation {
+					lhs: Box::new(previous),
+					operator: BinaryOperator::Add,
+					rhs: Box::new(one),
+					position: member_position,
+				}
+			};
+			let forward =
+				assignment(member_access(&name, &member, member_position), value, member_position);
+			let reverse = VariableOrPropertyAccess::Index {
+				indexee: Box::new(reference(name.clone(), member_position)),
+				indexer: Box::new(MultipleExpression::Single(forward)),
+				position: member_position,
+			};
+			let member_name =
+				Expression::StringLiteral(member.clone(), Quoted::Double, member_position);
+			statements.push(expression_statement(assignment(
+				reverse,
+				member_name,
+				member_position,
+			)));
+		}
+		previous = Some(member);
+	}
+
+	let object =
+		Expression::ObjectLiteral(ObjectLiteral { members: properties, position: object_position });
+	let mut declaration = const_declaration(name, object);
+	if exported {
+		declaration = export(declaration);
+	}
+	statements.insert(0, declaration);
+	statements
+}
+
+/// Replaces references to members in initialisers with `E.member`
+struct QualifyMembers<'a> {
+	enum_name: &'a str,
+	members: &'a HashSet<String>,
+}
+
+impl VisitorMutReceiver<()> for QualifyMembers<'_> {
+	fn visit_expression_mut(
+		&mut self,
+		expression: &mut Expression,
+		_data: &mut (),
+		_chain: &Chain,
+	) {
+		if let Expression::VariableReference(name, position) = expression {
+			if self.members.contains(name) {
+				let position = *position;
+				*expression =
+					property(reference(self.enum_name.to_owned(), position), name, position);
+			}
+		}
+	}
+}
+
+/// `namespace N { export const a = 1 }` to
+/// `let N; (function (N) { const a = 1; N.a = a; })(N || (N = {}));`. Nested enums and namespaces
+/// are lowered when the body is visited
+fn lower_namespace(
+	namespace: Namespace,
+	exported: bool,
+	declared: &mut HashSet<String>,
+) -> Vec<StatementOrDeclaration> {
+	let Namespace { name, inner: Block(items, body_position), position } = namespace;
+	let position = synthesised(position);
+
+	let mut body = Vec::with_capacity(items.len());
+	for item in items {
+		if let StatementOrDeclaration::Statement(Statement::Empty(..)) = item {
+			continue;
+		}
+		let StatementOrDeclaration::Declaration(Declaration::Export(Decorated {
+			on: ExportDeclaration::Variable { exported, position: export_position },
+			decorators,
+			position: decorated_position,
+		})) = item
+		else {
+			body.push(item);
+			continue;
+		};
+
+		// (local, exported)
+		let mut names = Vec::new();
+		let declaration = match exported {
+			Exportable::Variable(declaration) => {
+				let mut locals = Vec::new();
+				match &declaration {
+					VariableDeclaration::ConstDeclaration { declarations, .. } => {
+						for item in declarations {
+							variable_field_names(item.name.get_ast_ref(), &mut locals);
+						}
+					}
+					VariableDeclaration::LetDeclaration { declarations, .. } => {
+						for item in declarations {
+							variable_field_names(item.name.get_ast_ref(), &mut locals);
+						}
+					}
+				}
+				names.extend(locals.into_iter().map(|local| (local.clone(), local)));
+				Some(Declaration::Variable(declaration))
+			}
+			Exportable::Function(function) => {
+				if let Some(name) = function.name.identifier.as_option_str() {
+					names.push((name.to_owned(), name.to_owned()));
+				}
+				Some(Declaration::Function(Decorated {
+					decorators,
+					on: function,
+					position: export_position,
+				}))
+			}
+			Exportable::Class(class) => {
+				if let Some(name) = class.name.identifier.as_option_str() {
+					names.push((name.to_owned(), name.to_owned()));
+				}
+				Some(Declaration::Class(Decorated {
+					decorators,
+					on: class,
+					position: export_position,
+				}))
+			}
+			Exportable::Enum(r#enum) => {
+				names.push((r#enum.name.clone(), r#enum.name.clone()));
+				Some(Declaration::Enum(Decorated {
+					decorators,
+					on: r#enum,
+					position: export_position,
+				}))
+			}
+			Exportable::Namespace(namespace) => {
+				names.push((namespace.name.clone(), namespace.name.clone()));
+				Some(Declaration::Namespace(namespace))
+			}
+			Exportable::Interface(interface) => Some(Declaration::Interface(Decorated {
+				decorators,
+				on: interface,
+				position: export_position,
+			})),
+			Exportable::TypeAlias(alias) => Some(Declaration::TypeAlias(alias)),
+			Exportable::Parts(parts) => {
+				names.extend(parts.iter().filter_map(export_part_names));
+				None
+			}
+			exported @ (Exportable::ImportAll { .. } | Exportable::ImportParts { .. }) => {
+				Some(Declaration::Export(Decorated {
+					decorators,
+					on: ExportDeclaration::Variable { exported, position: export_position },
+					position: decorated_position,
+				}))
+			}
+		};
+		body.extend(declaration.map(StatementOrDeclaration::Declaration));
+		for (local, exported) in names {
+			let position = synthesised(export_position);
+			let target = member_access(&name, &exported, position);
+			body.push(expression_statement(assignment(
+				target,
+				reference(local, position),
+				position,
+			)));
+		}
+	}
+
+	let parameter = Parameter {
+		visibility: (),
+		name: WithComment::None(VariableField::Name(VariableIdentifier::Standard(
+			name.clone(),
+			position,
+		))),
+		type_annotation: None,
+		additionally: None,
+		position,
+	};
+	let function = Expression::ExpressionFunction(parser::ast::ExpressionFunction {
+		header: FunctionHeader::VirginFunctionHeader {
+			is_async: false,
+			location: None,
+			generator_star_token_position: None,
+			position,
+		},
+		name: ExpressionPosition(None),
+		type_parameters: None,
+		parameters: FunctionParameters {
+			leading: None,
+			parameters: vec![parameter],
+			rest_parameter: None,
+			position,
+		},
+		return_type: None,
+		body: Block(body, body_position),
+		position,
+	});
+
+	// `N || (N = {})`
+	let empty = Expression::ObjectLiteral(ObjectLiteral { members: Vec::new(), position });
+	let initialise =
+		assignment(VariableOrPropertyAccess::Variable(name.clone(), position), empty, position);
+	let argument = Expression::BinaryOperation {
+		lhs: Box::new(reference(name.clone(), position)),
+		operator: BinaryOperator::LogicalOr,
+		rhs: Box::new(parenthesise(initialise)),
+		position,
+	};
+	let call = Expression::FunctionCall {
+		function: Box::new(parenthesise(function)),
+		type_arguments: None,
+		arguments: vec![FunctionArgument::Standard(argument)],
+		is_optional: false,
+		position,
+	};
+
+	let mut items = Vec::with_capacity(2);
+	if declared.insert(name.clone()) {
+		let item = parser::ast::VariableDeclarationItem {
+			name: WithComment::None(VariableField::Name(VariableIdentifier::Standard(
+				name, position,
+			))),
+			type_annotation: None,
+			expression: None,
+			position,
+		};
+		let declaration = StatementOrDeclaration::Declaration(Declaration::Variable(
+			VariableDeclaration::LetDeclaration { declarations: vec![item], position },
+		));
+		items.push(if exported { export(declaration) } else { declaration });
+	}
+	items.push(expression_statement(call));
+	items
+}
+
+/// Adds `this.x = x` to the constructor for each parameter with a visibility modifier
+fn lower_parameter_properties<T: ExpressionOrStatementPosition>(class: &mut ClassDeclaration<T>) {
+	if !can_add_to_constructor(class) {
+		return;
+	}
+	let Some(constructor) = class.members.iter_mut().find_map(|member| match &mut member.on {
+		ClassMember::Constructor(constructor) => Some(constructor),
+		_ => None,
+	}) else {
+		return;
+	};
+
+	let mut statements = Vec::new();
+	for parameter in &mut constructor.parameters.parameters {
+		if parameter.visibility.take().is_none() {
+			continue;
+		}
+		if let VariableField::Name(VariableIdentifier::Standard(name, position)) =
+			parameter.name.get_ast_ref()
+		{
+			let position = synthesised(*position);
+			let target = VariableOrPropertyAccess::PropertyAccess {
+				parent: Box::new(Expression::ThisReference(position)),
+				property: PropertyReference::Standard { property: name.clone(), is_private: false },
+				position,
+			};
+			let value = reference(name.clone(), position);
+			statements.push(expression_statement(assignment(target, value, position)));
+		}
+	}
+	if !statements.is_empty() {
+		add_to_constructor(class, statements);
+	}
+}
+
+/// Names which a namespace merges with rather than declaring
+fn declared_name(item: &StatementOrDeclaration) -> Option<&str> {
+	match item {
+		StatementOrDeclaration::Declaration(Declaration::Function(function)) => {
+			function.on.name.identifier.as_option_str()
+		}
+		StatementOrDeclaration::Declaration(Declaration::Class(class)) => {
+			class.on.name.identifier.as_option_str()
+		}
+		StatementOrDeclaration::Declaration(Declaration::Export(Decorated {
+			on: ExportDeclaration::Variable { exported, .. },
+			..
+		})) => match exported {
+			Exportable::Function(function) => function.name.identifier.as_option_str(),
+			Exportable::Class(class) => class.name.identifier.as_option_str(),
+			_ => None,
+		},
+		_ => None,
+	}
+}
+
+fn is_lowerable(item: &StatementOrDeclaration) -> bool {
+	matches!(
+		item,
+		StatementOrDeclaration::Declaration(
+			Declaration::Enum(..)
+				| Declaration::Namespace(..)
+				| Declaration::Export(Decorated {
+					on: ExportDeclaration::Variable {
+						exported: Exportable::Enum(..) | Exportable::Namespace(..),
+						..
+					},
+					..
+				})
+		)
+	)
+}
+
+/// Removed rather than printed as an empty statement
+fn is_type_only(item: &StatementOrDeclaration) -> bool {
+	match item {
+		StatementOrDeclaration::Declaration(
+			Declaration::Interface(..)
+			| Declaration::TypeAlias(..)
+			| Declaration::Export(Decorated {
+				on:
+					ExportDeclaration::Variable {
+						exported: Exportable::Interface(..) | Exportable::TypeAlias(..),
+						..
+					},
+				..
+			}),
+		) => true,
+		StatementOrDeclaration::Declaration(Declaration::Function(function)) => {
+			function.on.name.declare
+		}
+		StatementOrDeclaration::Declaration(Declaration::Class(class)) => class.on.name.declare,
+		_ => false,
+	}
+}
+
+fn export(item: StatementOrDeclaration) -> StatementOrDeclaration {
+	let StatementOrDeclaration::Declaration(Declaration::Variable(declaration)) = item else {
+		unreachable!("only variable declarations are exported")
+	};
+	let position = synthesised(declaration.get_position());
+	StatementOrDeclaration::Declaration(Declaration::Export(Decorated::new_empty(
+		ExportDeclaration::Variable { exported: Exportable::Variable(declaration), position },
+	)))
+}
+
+fn member_access(object: &str, member: &str, position: Span) -> VariableOrPropertyAccess {
+	VariableOrPropertyAccess::PropertyAccess {
+		parent: Box::new(reference(object.to_owned(), position)),
+		property: PropertyReference::Standard { property: member.to_owned(), is_private: false },
+		position,
+	}
+}
+
+fn enum_property(key: PropertyKey<AlwaysPublic>, value: Expression) -> ObjectLiteralMember {
+	let position = value.get_position();
+	ObjectLiteralMember::Property {
+		key: WithComment::None(key),
+		assignment: false,
+		value,
+		position,
+	}
+}
+
+/// Negative and fractional numbers are quoted as they are not valid number keys
+fn number_key(number: f64, position: Span) -> PropertyKey<AlwaysPublic> {
+	if number >= 0. && number.fract() == 0. {
+		PropertyKey::NumberLiteral(NumberRepresentation::from(number), position)
+	} else {
+		PropertyKey::StringLiteral(number.to_string(), Quoted::Double, position)
+	}
+}
+
+fn enum_value_literal(value: &EnumValue, position: Span) -> Expression {
+	match value {
+		EnumValue::Number(number) if number.is_sign_negative() => Expression::UnaryOperation {
+			operator: UnaryOperator::Negation,
+			operand: Box::new(Expression::NumberLiteral(
+				NumberRepresentation::from(-number),
+				position,
+			)),
+			position,
+		},
+		EnumValue::Number(number) => {
+			Expression::NumberLiteral(NumberRepresentation::from(*number), position)
+		}
+		EnumValue::String(content, quoted) => {
+			Expression::StringLiteral(content.clone(), *quoted, position)
+		}
+	}
+}
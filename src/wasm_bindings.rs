@@ -35,9 +35,13 @@ pub fn experimental_build_wasm(
 	let result = crate::build::build(
 		vec![entry_path.into()],
 		&fs_resolver,
-		None,
+		&[],
 		Path::new("out.js"),
-		&crate::build::BuildConfig { strip_whitespace: minify },
+		&crate::build::BuildConfig {
+			strip_whitespace: minify,
+			mangle: minify,
+			..Default::default()
+		},
 		None,
 	);
 
@@ -94,8 +98,9 @@ pub fn check_wasm(entry_path: String, fs_resolver_js: &js_sys::Function) -> WASM
 	WASMCheckOutput(crate::check::check(
 		vec![entry_path.into()],
 		&fs_resolver,
-		None,
+		&[],
 		checker::TypeCheckOptions::default(),
+		Default::default(),
 	))
 }
 
@@ -119,7 +124,13 @@ pub fn check_wasm_with_options(
 
 		res.ok().and_then(|res| res.as_string())
 	};
-	WASMCheckOutput(crate::check::check(vec![entry_path.into()], &fs_resolver, None, options))
+	WASMCheckOutput(crate::check::check(
+		vec![entry_path.into()],
+		&fs_resolver,
+		&[],
+		options,
+		Default::default(),
+	))
 }
 
 #[wasm_bindgen(typescript_custom_section)]
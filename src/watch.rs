@@ -0,0 +1,82 @@
+//! Re-running commands when files change
+
+use std::{
+	collections::HashSet,
+	path::{Path, PathBuf},
+	sync::mpsc::{channel, Receiver},
+	time::Duration,
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Changes in this window after the first are treated as one (editors can write several times when saving)
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Parent directories are watched rather than the files, so files which are replaced
+/// when saving (rather than modified) are picked up
+pub(crate) struct FileWatcher {
+	watcher: RecommendedWatcher,
+	events: Receiver<notify::Result<Event>>,
+	directories: HashSet<PathBuf>,
+}
+
+impl FileWatcher {
+	pub(crate) fn new() -> notify::Result<Self> {
+		let (sender, events) = channel();
+		let watcher = notify::recommended_watcher(sender)?;
+		Ok(Self { watcher, events, directories: HashSet::new() })
+	}
+
+	/// Blocks until one of `paths` is created, modified or removed. Paths in directories that do
+	/// not exist (such as a package that is not installed) are not watched
+	pub(crate) fn wait_for_change(
+		&mut self,
+		paths: impl IntoIterator<Item = PathBuf>,
+	) -> notify::Result<()> {
+		let paths: HashSet<PathBuf> = paths.into_iter().map(absolute).collect();
+
+		let directories: HashSet<PathBuf> = paths
+			.iter()
+			.filter_map(|path| {
+				path.parent().filter(|parent| parent.is_dir()).map(Path::to_path_buf)
+			})
+			.collect();
+		for removed in self.directories.difference(&directories) {
+			// May have been deleted
+			let _ = self.watcher.unwatch(removed);
+		}
+		for added in directories.difference(&self.directories) {
+			self.watcher.watch(added, RecursiveMode::NonRecursive)?;
+		}
+		self.directories = directories;
+
+		loop {
+			let event = self.events.recv().expect("watcher dropped")?;
+			if is_change_to(&event, &paths) {
+				break;
+			}
+		}
+		while self.events.recv_timeout(DEBOUNCE).is_ok() {}
+
+		Ok(())
+	}
+}
+
+fn is_change_to(event: &Event, paths: &HashSet<PathBuf>) -> bool {
+	matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_))
+		&& event.paths.iter().any(|path| paths.contains(path))
+}
+
+/// Event paths are absolute. Also removes `.` components
+fn absolute(path: PathBuf) -> PathBuf {
+	let path = if path.is_absolute() {
+		path
+	} else {
+		std::env::current_dir().map_or_else(|_| path.clone(), |cwd| cwd.join(&path))
+	};
+	path.components().collect()
+}
+
+pub(crate) fn clear_terminal() {
+	let _ = console::Term::stdout().clear_screen();
+}
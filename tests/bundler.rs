@@ -0,0 +1,165 @@
+mod common;
+
+use ezno_lib::{BuildConfig, OutputFormat, SourceMaps};
+
+const FILES: &[(&str, &str)] = &[
+	(
+		"index.ts",
+		"import { value, double } from \"./other\";
+const value2 = 3;
+console.log(double(value), value2);
+export const result = double(2);",
+	),
+	(
+		"other.ts",
+		"const value2 = 4;
+export const value = value2 + 1;
+export function double(n: number) { return n * 2 }",
+	),
+];
+
+#[test]
+fn modules_are_combined_and_colliding_names_renamed() {
+	let outputs = common::build_files(FILES, &BuildConfig::default());
+
+	let expected = "const value2$1 = 4;
+const value = value2$1 + 1;
+function double(n) {
+	return n * 2;
+}
+const value2 = 3;
+console.log(double(value), value2);
+const result = double(2);
+export { result };";
+
+	assert_eq!(outputs.len(), 1);
+	assert_eq!(outputs[0].content, expected);
+}
+
+#[test]
+fn immediately_invoked_function_format_drops_exports() {
+	let config = BuildConfig { output_format: OutputFormat::IIFE, ..Default::default() };
+	let outputs = common::build_files(FILES, &config);
+
+	let expected = "(() => {
+const value2$1 = 4;
+const value = value2$1 + 1;
+function double(n) {
+	return n * 2;
+}
+const value2 = 3;
+console.log(double(value), value2);
+const result = double(2);
+})();";
+
+	assert_eq!(outputs[0].content, expected);
+}
+
+#[test]
+fn dynamic_imports_are_split_into_chunks() {
+	let files = [
+		("index.ts", "export const utils = import(\"./utils\");"),
+		("utils.ts", "export const name = \"utils\";"),
+	];
+	let outputs = common::build_files(&files, &BuildConfig::default());
+
+	assert_eq!(outputs.len(), 2);
+	assert_eq!(outputs[0].content, "const utils = import(\"./utils.js\");\nexport { utils };");
+	assert_eq!(outputs[1].output_path, std::path::Path::new("utils.js"));
+	assert_eq!(outputs[1].content, "const name = \"utils\";\nexport { name };");
+}
+
+#[test]
+fn circular_imports_are_included_once() {
+	let files = [
+		(
+			"index.ts",
+			"import { b } from \"./b\";\nexport function a() { return \"a\" }\nconsole.log(b());",
+		),
+		("b.ts", "import { a } from \"./index\";\nexport function b() { return a() + \"b\" }"),
+	];
+	let outputs = common::build_files(&files, &BuildConfig::default());
+
+	let expected = "function b() {
+	return a() + \"b\";
+}
+function a() {
+	return \"a\";
+}
+console.log(b());
+export { a };";
+
+	assert_eq!(outputs.len(), 1);
+	assert_eq!(outputs[0].content, expected);
+}
+
+#[test]
+fn star_exports_are_exported_from_chunks() {
+	let files = [
+		("index.ts", "export const lazy = import(\"./reexport\");"),
+		("reexport.ts", "export * from \"./inner\";\nexport const own = 1;"),
+		("inner.ts", "export const name = \"inner\";\nexport function greet() { return name }"),
+	];
+	let outputs = common::build_files(&files, &BuildConfig::default());
+
+	let expected = "const name = \"inner\";
+function greet() {
+	return name;
+}
+const own = 1;
+export { greet, name, own };";
+
+	assert_eq!(outputs.len(), 2);
+	assert_eq!(outputs[1].output_path, std::path::Path::new("reexport.js"));
+	assert_eq!(outputs[1].content, expected);
+}
+
+#[test]
+fn colliding_names_in_chunks_are_renamed() {
+	let files = [
+		("index.ts", "export const lazy = import(\"./chunk\");\nconst shared = 0;\nconsole.log(shared);"),
+		(
+			"chunk.ts",
+			"import { value } from \"./other\";\nconst shared = 1;\nexport const total = shared + value;",
+		),
+		("other.ts", "const shared = 2;\nexport const value = shared;"),
+	];
+	let outputs = common::build_files(&files, &BuildConfig::default());
+
+	let expected = "const shared$2 = 2;
+const value = shared$2;
+const shared$1 = 1;
+const total = shared$1 + value;
+export { total };";
+
+	assert_eq!(
+		outputs[0].content,
+		"const lazy = import(\"./chunk.js\");
+const shared = 0;
+console.log(shared);
+export { lazy };"
+	);
+	assert_eq!(outputs[1].content, expected);
+}
+
+#[test]
+fn source_maps_of_bundles_reference_each_module() {
+	let files = [
+		("index.ts", "import { value } from \"./other\";\nconsole.log(value);"),
+		("other.ts", "export const value = 2;"),
+	];
+	let config = BuildConfig { source_maps: SourceMaps::External, ..Default::default() };
+	let outputs = common::build_files(&files, &config);
+
+	assert_eq!(
+		outputs[0].content,
+		"const value = 2;\nconsole.log(value);\n//# sourceMappingURL=out.js.map"
+	);
+
+	let source_map: serde_json::Value = serde_json::from_str(&outputs[0].mappings).unwrap();
+	assert_eq!(source_map["sources"], serde_json::json!(["index.ts", "other.ts"]));
+	assert_eq!(source_map["sourcesContent"], serde_json::json!([files[0].1, files[1].1]));
+	// The first line is from `other.ts` (source 1) and the second from `index.ts` (source 0). Source
+	// indexes are relative to the previous segment
+	assert_eq!(source_map["mappings"], "ACAO,MAAM;ADCb,AAAA,AAAA,YAAY");
+}
@@ -0,0 +1,38 @@
+// Each test file only uses some of the helpers
+#![allow(dead_code)]
+
+use ezno_lib::{BuildConfig, Output};
+
+/// Builds the first of the `(path, content)` pairs, panicking with the diagnostics if it fails
+pub fn build_files(files: &[(&str, &str)], config: &BuildConfig) -> Vec<Output> {
+	let read_from = |path: &std::path::Path| {
+		files.iter().find_map(|(name, content)| {
+			(std::path::Path::new(name) == path).then(|| (*content).to_owned())
+		})
+	};
+
+	let result =
+		ezno_lib::build(vec![files[0].0.into()], &read_from, &[], "out.js".as_ref(), config, None);
+
+	match result {
+		Ok(output) => output.outputs,
+		Err(output) => {
+			let reasons: Vec<_> = output
+				.diagnostics
+				.into_iter()
+				.map(|diagnostic| diagnostic.reason().to_owned())
+				.collect();
+			panic!("build failed with {reasons:?}")
+		}
+	}
+}
+
+/// Builds `source` as a single module, returning the output
+pub fn build_source(source: &str, config: &BuildConfig) -> String {
+	build_files(&[("index.ts", source)], config).remove(0).content
+}
+
+/// [`build_source`] with `optimise` enabled
+pub fn build_optimised(source: &str) -> String {
+	build_source(source, &BuildConfig { optimise: true, ..Default::default() })
+}
@@ -0,0 +1,21 @@
+mod common;
+
+use ezno_lib::BuildConfig;
+
+#[test]
+fn checker_intrinsics_and_type_operators_are_removed() {
+	let source = "declare const p: number;
+const a = 2;
+print_type(a);
+debug_type(a + 1);
+const c = (a satisfies number) + (p as number);
+export const d = [c, print_type(c)];";
+
+	// Calls in expressions are replaced with `void 0` (without evaluating the arguments)
+	let expected = "const a = 2;
+const c = a + p;
+const d = [c, void 0];
+export { d };";
+
+	assert_eq!(common::build_source(source, &BuildConfig::default()), expected);
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use ezno_lib::BuildConfig;
+
+#[test]
+fn expressions_with_constant_types_are_folded() {
+	let source = "const a = 2 + 3;
+const s = \"a\" + \"b\";
+console.log(a * 2, s, typeof a);";
+
+	let expected = "const a = 5;
+const s = \"ab\";
+console.log(10, \"ab\", \"number\");";
+
+	assert_eq!(common::build_optimised(source), expected);
+}
+
+#[test]
+fn only_folds_under_optimise() {
+	let source = "const a = 2 + 3;\nconsole.log(a * 2);";
+
+	assert_eq!(common::build_source(source, &BuildConfig::default()), source);
+}
+
+#[test]
+fn folded_strings_keep_escapes() {
+	let source = "const t = \"a\\\"b\\n\";
+const u = 'it\\'s \"x\"';
+console.log(t + \"c\", u + \"!\");";
+
+	let expected = "const t = \"a\\\"b\\n\";
+const u = 'it\\'s \"x\"';
+console.log(\"a\\\"b\\nc\", \"it\\'s \\\"x\\\"!\");";
+
+	assert_eq!(common::build_optimised(source), expected);
+}
+
+#[test]
+fn variables_assigned_in_inner_scopes_are_not_folded() {
+	let try_block = "declare function maybeThrow(): void;
+let n = 1;
+try {
+	n = 2;
+	maybeThrow();
+	n = 3;
+} catch {}
+console.log(n);";
+
+	let while_loop = "declare function rand(): boolean;
+let k = 0;
+while (rand()) {
+	k = 1;
+}
+console.log(k);";
+
+	let block = "let b = 1;
+{
+	b = 2;
+}
+console.log(b);";
+
+	for source in [try_block, while_loop, block] {
+		let output = common::build_optimised(source);
+		let source_without_declare = source.lines().filter(|line| !line.starts_with("declare"));
+		assert_eq!(output, source_without_declare.collect::<Vec<_>>().join("\n"));
+	}
+}
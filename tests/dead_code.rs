@@ -0,0 +1,70 @@
+mod common;
+
+use ezno_lib::BuildConfig;
+
+#[test]
+fn branches_which_are_never_taken_are_removed() {
+	let source = "const a = 2;
+if (a === 2) { console.log(\"yes\") } else { console.log(\"no\") }
+console.log(a > 5 ? \"big\" : \"small\");";
+
+	let expected = "const a = 2;
+console.log(\"yes\");
+console.log(\"small\");";
+
+	assert_eq!(common::build_optimised(source), expected);
+}
+
+#[test]
+fn only_eliminates_under_optimise() {
+	let source = "const a = 2;
+if (a === 2) {
+	console.log(\"yes\");
+} else {
+	console.log(\"no\");
+}";
+
+	assert_eq!(common::build_source(source, &BuildConfig::default()), source);
+}
+
+#[test]
+fn unreachable_statements_and_loops_without_iterations_are_removed() {
+	let source = "export function f() { return 1; console.log(\"never\"); var hoisted; }
+for (let i = 0; i < 0; i++) { console.log(i) }
+while (false) { console.log(1) }";
+
+	let expected = "function f() {
+	return 1;
+	var hoisted;
+}
+export { f };";
+
+	assert_eq!(common::build_optimised(source), expected);
+}
+
+#[test]
+fn branches_on_variables_assigned_in_inner_scopes_are_kept() {
+	let try_block = "let n = 1;
+try {
+	n = 2;
+	maybeThrow();
+	n = 3;
+} catch {}
+if (n === 1) {
+	console.log(\"one\");
+} else {
+	console.log(\"other\");
+}";
+
+	let while_loop = "let k = 0;
+while (rand()) {
+	k = 1;
+}
+console.log(k === 0 ? \"a\" : \"b\");";
+
+	for source in [try_block, while_loop] {
+		let declarations =
+			"declare function maybeThrow(): void;\ndeclare function rand(): boolean;\n";
+		assert_eq!(common::build_optimised(&format!("{declarations}{source}")), source);
+	}
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use ezno_lib::{BuildConfig, Target};
+
+#[test]
+fn operators_and_object_spread() {
+	let source = "export function f(o: { a: { b: number } }, n: number) {
+	return [o?.a?.b, n ?? 4, 2 ** n, { c: 1, ...o }];
+}";
+
+	let expected = "function f(o, n) {
+	let _ref;
+	return [((_ref = (o == null ? void 0 : o.a)) == null ? void 0 : _ref.b), (n != null ? n : 4), Math.pow(2, n), Object.assign({ c: 1 }, o)];
+}
+export { f };";
+
+	let config = BuildConfig { target: Target::ES2015, ..Default::default() };
+	assert_eq!(common::build_source(source, &config), expected);
+}
+
+#[test]
+fn logical_assignments() {
+	let source = "const o = { a: 0 };
+let v: number | null = null;
+v ??= 2;
+o.a ||= 3;
+export { v };";
+
+	// `??` is kept for ES2020
+	let expected = "const o = { a: 0 };
+let v = null;
+v ?? (v = 2);
+o.a || (o.a = 3);
+export { v };";
+
+	let config = BuildConfig { target: Target::ES2020, ..Default::default() };
+	assert_eq!(common::build_source(source, &config), expected);
+}
+
+#[test]
+fn class_fields_and_private_members() {
+	let source = "export class A {
+	x = 1;
+	static s = 2;
+	#p = 3;
+	getP() { return this.#p }
+}";
+
+	let expected = "const _p = new WeakMap();
+class A {
+	constructor() {
+		this.x = 1;
+		_p.set(this, 3);
+	}
+	getP() {
+		return _p.get(this);
+	}
+}
+A.s = 2;
+export { A };";
+
+	let config = BuildConfig { target: Target::ES2021, ..Default::default() };
+	assert_eq!(common::build_source(source, &config), expected);
+}
+
+#[test]
+fn syntax_supported_by_the_target_is_kept() {
+	let source = "export function f(n: number) {\n\treturn 2 ** n;\n}";
+	let expected = "function f(n) {\n\treturn 2 ** n;\n}\nexport { f };";
+
+	let config = BuildConfig { target: Target::ES2016, ..Default::default() };
+	assert_eq!(common::build_source(source, &config), expected);
+	let config = BuildConfig { target: Target::ESNext, ..Default::default() };
+	assert_eq!(common::build_source(source, &config), expected);
+}
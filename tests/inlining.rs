@@ -0,0 +1,43 @@
+mod common;
+
+#[test]
+fn single_use_pure_functions_are_inlined() {
+	let source = "declare let x: number;
+function square(n: number) { return n * n }
+const twice = (n: number) => n + n;
+console.log(square(x), twice(x));";
+
+	assert_eq!(common::build_optimised(source), "console.log((x * x), (x + x));");
+}
+
+#[test]
+fn functions_which_cannot_be_inlined_are_kept() {
+	// Used twice, has a side effect and an argument that would be evaluated twice
+	let source = "declare let x: number;
+declare function input(): number;
+function inc(n: number) { return n + 1 }
+function log(n: number) { console.log(n); return n }
+function square(n: number) { return n * n }
+console.log(inc(x), inc(x), log(x), square(input()));";
+
+	let expected = "function inc(n) {
+	return n + 1;
+}
+function log(n) {
+	console.log(n);
+	return n;
+}
+function square(n) {
+	return n * n;
+}
+console.log(inc(x), inc(x), log(x), square(input()));";
+
+	assert_eq!(common::build_optimised(source), expected);
+}
+
+#[test]
+fn pure_builtin_calls_are_replaced_with_their_result() {
+	let source = "console.log(Math.floor(2.5), Math.sqrt(16), \"ab\".toUpperCase());";
+
+	assert_eq!(common::build_optimised(source), "console.log(2, 4, \"AB\");");
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use ezno_lib::{BuildConfig, JSXRuntime};
+
+#[test]
+fn automatic_runtime() {
+	let source = "export const a = <div id=\"x\" class={1}>Hello {2}<span ></span></div>;
+export const b = <ul>{[1].map(i => <li key={i}>x</li>)}</ul>;";
+
+	let expected = "import { jsx as _jsx, jsxs as _jsxs } from \"react/jsx-runtime\";
+const a = _jsxs(\"div\", { id: \"x\", class: 1, children: [\"Hello \", 2, _jsx(\"span\", {  })] });
+const b = _jsx(\"ul\", { children: [1].map(i => _jsx(\"li\", { children: \"x\" }, i)) });
+export { a, b };";
+
+	assert_eq!(common::build_source(source, &BuildConfig::default()), expected);
+}
+
+#[test]
+fn classic_runtime_with_import_source() {
+	let source = "export const a = <div id=\"x\">Hello {2}<span ></span></div>;";
+	let config = BuildConfig {
+		jsx_runtime: JSXRuntime::Classic,
+		jsx_import_source: Some("preact".to_owned()),
+		..Default::default()
+	};
+
+	let expected = "import { createElement as _createElement } from \"preact\";
+const a = _createElement(\"div\", { id: \"x\" }, \"Hello \", 2, _createElement(\"span\", null));
+export { a };";
+
+	assert_eq!(common::build_source(source, &config), expected);
+}
+
+#[test]
+fn text_whitespace_and_character_references() {
+	let source = "export const a = <div
+  title=\"&quot;t&quot;\">
+  a\\b &amp; &#65;&#x42;
+  &unknown; & \"q\"
+</div>;";
+
+	let expected = "import { jsx as _jsx } from \"react/jsx-runtime\";
+const a = _jsx(\"div\", { title: \"\\\"t\\\"\", children: \"a\\\\b & AB &unknown; & \\\"q\\\"\" });
+export { a };";
+
+	assert_eq!(common::build_source(source, &BuildConfig::default()), expected);
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use ezno_lib::BuildConfig;
+
+fn minified(mangle_private_members: bool) -> BuildConfig {
+	BuildConfig {
+		strip_whitespace: true,
+		mangle: true,
+		mangle_private_members,
+		..Default::default()
+	}
+}
+
+const COUNTER: &str = "class Counter {
+	#count = 0;
+	increment() { this.#count = this.#count + 1; return this.#count }
+}
+export const counter = new Counter();";
+
+#[test]
+fn local_names_are_shortened() {
+	let source = "export function add(first: number, second: number) {
+	const total = first + second;
+	return total * total;
+}";
+
+	// Exported names are kept
+	assert_eq!(
+		common::build_source(source, &minified(false)),
+		"function add(b,c){const a=b+c;return a*a;}export {add};"
+	);
+}
+
+#[test]
+fn private_members_are_only_shortened_when_enabled() {
+	assert_eq!(
+		common::build_source(COUNTER, &minified(false)),
+		"class a{#count=0;increment(){this.#count=this.#count+1;return this.#count;}}const counter=new a();export {counter};"
+	);
+	assert_eq!(
+		common::build_source(COUNTER, &minified(true)),
+		"class a{#a=0;increment(){this.#a=this.#a+1;return this.#a;}}const counter=new a();export {counter};"
+	);
+}
+
+#[test]
+fn scopes_with_eval_are_not_mangled() {
+	let source = "declare function eval(code: string): any;
+export function f(a: string) { eval(a); const local = 1; return local }";
+
+	assert_eq!(
+		common::build_source(source, &minified(false)),
+		"function f(a){eval(a);const local=1;return local;}export {f};"
+	);
+}
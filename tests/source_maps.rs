@@ -0,0 +1,38 @@
+mod common;
+
+use ezno_lib::{BuildConfig, SourceMaps};
+
+const SOURCE: &str = "const a = 1;\nconsole.log(a);";
+
+#[test]
+fn external_source_map() {
+	let config = BuildConfig { source_maps: SourceMaps::External, ..Default::default() };
+	let outputs = common::build_files(&[("index.ts", SOURCE)], &config);
+
+	let output = &outputs[0];
+	assert_eq!(output.content, "const a = 1;\nconsole.log(a);\n//# sourceMappingURL=out.js.map");
+
+	let source_map: serde_json::Value = serde_json::from_str(&output.mappings).unwrap();
+	assert_eq!(source_map["version"], 3);
+	assert_eq!(source_map["file"], "out.js");
+	assert_eq!(source_map["sources"], serde_json::json!(["index.ts"]));
+	assert_eq!(source_map["sourcesContent"], serde_json::json!([SOURCE]));
+	// `a` in both the declaration and the call
+	assert_eq!(source_map["mappings"], "AAAA,MAAM;AACN,AAAA,AAAA,YAAY");
+}
+
+#[test]
+fn inline_source_map() {
+	use base64::Engine;
+
+	let config = BuildConfig { source_maps: SourceMaps::Inline, ..Default::default() };
+	let outputs = common::build_files(&[("index.ts", SOURCE)], &config);
+
+	let output = &outputs[0];
+	let (content, url) = output.content.split_once("\n//# sourceMappingURL=").unwrap();
+	assert_eq!(content, "const a = 1;\nconsole.log(a);");
+
+	let encoded = url.strip_prefix("data:application/json;base64,").unwrap();
+	let decoded = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+	assert_eq!(String::from_utf8(decoded).unwrap(), output.mappings);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use ezno_lib::BuildConfig;
+
+#[test]
+fn unused_declarations_imports_and_exports_are_removed() {
+	let files = [
+		(
+			"index.ts",
+			"import { used, unused } from \"./other\";
+declare function sideEffect(): number;
+const local = 1;
+const kept = sideEffect();
+function helper() { return 2 }
+class Unused {}
+console.log(used(), used());
+export const api = 3;",
+		),
+		(
+			"other.ts",
+			"export function used() { return 1 }
+export const unused = 2;
+export function alsoUnused() {}",
+		),
+	];
+	let config = BuildConfig { optimise: true, ..Default::default() };
+	let outputs = common::build_files(&files, &config);
+
+	// Initialisers with side effects and the exports of the entry point are kept
+	let expected = "function used() {
+	return 1;
+}
+const kept = sideEffect();
+console.log(used(), used());
+const api = 3;
+export { api };";
+
+	assert_eq!(outputs[0].content, expected);
+}
+
+#[test]
+fn methods_of_classes_used_outside_the_output_are_kept() {
+	// Importers can call the methods of exported classes and of exported instances
+	let exported = "class Point {
+	norm() {
+		return 1;
+	}
+}
+export { Point };";
+	assert_eq!(common::build_optimised(exported), exported);
+
+	let instance = "class Point {
+	norm() {
+		return 1;
+	}
+}
+const origin = new Point();
+export { origin };";
+	assert_eq!(common::build_optimised(instance), instance);
+
+	let internal = "class Point {
+	norm() {
+		return 1;
+	}
+	scale() {
+		return 2;
+	}
+}
+console.log(new Point().norm());";
+	let expected = "class Point {
+	norm() {
+		return 1;
+	}
+}
+console.log(new Point().norm());";
+	assert_eq!(common::build_optimised(internal), expected);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use ezno_lib::BuildConfig;
+
+#[test]
+fn enums() {
+	let source = "enum E { A, B = 4, C, S = \"s\" }
+enum Q { Quote = \"x\" + 'y\"', Line = \"a\\n\" + \"b\" }
+const enum K { X = 1 }
+console.log(E.A, E.S, Q.Quote, K.X);
+export { E };";
+
+	// Numeric members have reverse mappings and `const enum` members are inlined
+	let expected = "const E = { A: 0, 0: \"A\", B: 4, 4: \"B\", C: 5, 5: \"C\", S: \"s\" };
+const Q = { Quote: \"xy\\\"\", Line: \"a\\nb\" };
+console.log(E.A, E.S, Q.Quote, 1);
+export { E };";
+
+	assert_eq!(common::build_source(source, &BuildConfig::default()), expected);
+}
+
+#[test]
+fn merged_namespaces() {
+	let source = "namespace NS {
+	export const v = 2;
+	export function f() { return v * 2 }
+}
+namespace NS {
+	export const w = 3;
+}
+console.log(NS.f(), NS.w);";
+
+	let expected = "let NS;
+(function (NS) {
+	const v = 2;
+	NS.v = v;
+	function f() {
+		return v * 2;
+	}
+	NS.f = f;
+})(NS || (NS = {  }));
+(function (NS) {
+	const w = 3;
+	NS.w = w;
+})(NS || (NS = {  }));
+console.log(NS.f(), NS.w);";
+
+	assert_eq!(common::build_source(source, &BuildConfig::default()), expected);
+}
+
+#[test]
+fn parameter_properties() {
+	let source = "class P {
+	constructor(public x: number, private y: number) {}
+}
+console.log(new P(1, 2).x);";
+
+	let expected = "class P {
+	constructor(x, y) {
+		this.x = x;
+		this.y = y;
+	}
+}
+console.log(new P(1, 2).x);";
+
+	assert_eq!(common::build_source(source, &BuildConfig::default()), expected);
+}
+
+#[test]
+fn type_only_items_are_removed() {
+	let source = "const p = 1;
+interface I { a: number }
+type T = string;
+export type U = number;
+export interface J { b: string }
+declare function g(a: number): void;
+declare class C {}
+console.log(p);";
+
+	assert_eq!(
+		common::build_source(source, &BuildConfig::default()),
+		"const p = 1;\nconsole.log(p);"
+	);
+}